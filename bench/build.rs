@@ -0,0 +1,46 @@
+use std::process::Command;
+
+// Bakes the compiler version, git commit and build profile into the binary so benchmark
+// results can record the configuration they were produced with.
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("-V")
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let git_dirty = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| !out.stdout.is_empty())
+        .unwrap_or(false);
+
+    let profile = std::env::var("PROFILE").unwrap_or_else(|_| "unknown".to_string());
+    let opt_level = std::env::var("OPT_LEVEL").unwrap_or_else(|_| "unknown".to_string());
+
+    println!("cargo:rustc-env=LINKED_RS_RUSTC_VERSION={}", rustc_version);
+    println!(
+        "cargo:rustc-env=LINKED_RS_GIT_COMMIT={}{}",
+        git_commit,
+        if git_dirty { "-dirty" } else { "" }
+    );
+    println!("cargo:rustc-env=LINKED_RS_PROFILE={}", profile);
+    println!("cargo:rustc-env=LINKED_RS_OPT_LEVEL={}", opt_level);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/index");
+    println!("cargo:rerun-if-changed=src");
+}
@@ -0,0 +1,235 @@
+use crate::metadata::Metadata;
+use crate::stats::{welch_t_test, Summary};
+
+const DEFAULT_THRESHOLD: f64 = 5.0;
+const DEFAULT_ALPHA: f64 = 0.05;
+
+pub struct Samples {
+    pub columns: Vec<String>,
+    pub values: Vec<Vec<f64>>,
+}
+
+impl Samples {
+    pub fn load(path: &str) -> Result<Samples, String> {
+        let mut reader = csv::Reader::from_path(path).map_err(|e| format!("{}: {}", path, e))?;
+        let columns: Vec<String> = reader
+            .headers()
+            .map_err(|e| format!("{}: {}", path, e))?
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        let mut values = vec![Vec::new(); columns.len()];
+
+        for (row, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| format!("{}: {}", path, e))?;
+            for (i, field) in record.iter().enumerate().take(columns.len()) {
+                let value = field.trim().parse::<f64>().map_err(|_| {
+                    format!("{}: row {}, column '{}': '{}' is not a number", path, row + 1, columns[i], field)
+                })?;
+                values[i].push(value);
+            }
+        }
+
+        return Ok(Samples { columns, values });
+    }
+
+    pub fn column(&self, name: &str) -> Option<&[f64]> {
+        return self
+            .columns
+            .iter()
+            .position(|c| c == name)
+            .map(|i| self.values[i].as_slice());
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Faster,
+    Slower,
+    Regression,
+    NoChange,
+}
+
+pub struct ColumnComparison {
+    pub column: String,
+    pub baseline_mean: f64,
+    pub candidate_mean: f64,
+    pub change_percent: f64,
+    pub t: f64,
+    pub df: f64,
+    pub p_value: f64,
+    pub verdict: Verdict,
+}
+
+pub fn compare_column(column: &str, baseline: &[f64], candidate: &[f64], threshold: f64, alpha: f64) -> ColumnComparison {
+    let baseline_mean = Summary::of(baseline).mean;
+    let candidate_mean = Summary::of(candidate).mean;
+    let change_percent = if baseline_mean == 0.0 {
+        0.0
+    } else {
+        (candidate_mean - baseline_mean) / baseline_mean * 100.0
+    };
+    let test = welch_t_test(baseline, candidate);
+    let p_value = test.p_value;
+
+    // Times are lower-is-better, so a positive change is a slowdown.
+    let verdict = if p_value >= alpha {
+        Verdict::NoChange
+    } else if change_percent > threshold {
+        Verdict::Regression
+    } else if change_percent > 0.0 {
+        Verdict::Slower
+    } else {
+        Verdict::Faster
+    };
+
+    return ColumnComparison {
+        column: column.to_string(),
+        baseline_mean,
+        candidate_mean,
+        change_percent,
+        t: test.t,
+        df: test.df,
+        p_value,
+        verdict,
+    };
+}
+
+pub fn compare(baseline: &Samples, candidate: &Samples, threshold: f64, alpha: f64) -> Vec<ColumnComparison> {
+    let mut results = Vec::new();
+    for column in &baseline.columns {
+        let (Some(a), Some(b)) = (baseline.column(column), candidate.column(column)) else {
+            continue;
+        };
+        if a.len() < 2 || b.len() < 2 {
+            continue;
+        }
+        results.push(compare_column(column, a, b, threshold, alpha));
+    }
+
+    return results;
+}
+
+fn print_table(results: &[ColumnComparison]) {
+    println!(
+        "{:<12} {:>14} {:>14} {:>10} {:>8} {:>6} {:>10}  verdict",
+        "column", "baseline ms", "candidate ms", "change", "t", "df", "p-value"
+    );
+    for r in results {
+        let verdict = match r.verdict {
+            Verdict::Faster => "faster",
+            Verdict::Slower => "slower",
+            Verdict::Regression => "REGRESSION",
+            Verdict::NoChange => "no change",
+        };
+        println!(
+            "{:<12} {:>14.2} {:>14.2} {:>9.2}% {:>8.2} {:>6.1} {:>10.4}  {}",
+            r.column, r.baseline_mean, r.candidate_mean, r.change_percent, r.t, r.df, r.p_value, verdict
+        );
+    }
+}
+
+// Prints what differs between the two runs' sidecars. Returns false when the comparison
+// should be refused.
+fn check_metadata(baseline: &str, candidate: &str, allow_mismatch: bool) -> bool {
+    let (baseline_metadata, candidate_metadata) = match (Metadata::load_sidecar(baseline), Metadata::load_sidecar(candidate)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("error: {}", e);
+            return false;
+        }
+    };
+
+    let (Some(a), Some(b)) = (&baseline_metadata, &candidate_metadata) else {
+        for (path, metadata) in [(baseline, &baseline_metadata), (candidate, &candidate_metadata)] {
+            if metadata.is_none() {
+                eprintln!("warning: {} has no metadata, its configuration cannot be checked", path);
+            }
+        }
+        return true;
+    };
+
+    let mismatches = a.mismatches(b);
+    for mismatch in mismatches.iter().filter(|m| !m.config) {
+        eprintln!("warning: environment differs: {}", mismatch.describe());
+    }
+    let config: Vec<_> = mismatches.iter().filter(|m| m.config).collect();
+    for mismatch in &config {
+        let level = if allow_mismatch { "warning" } else { "error" };
+        eprintln!("{}: configuration differs: {}", level, mismatch.describe());
+    }
+    if !config.is_empty() && !allow_mismatch {
+        eprintln!("refusing to compare runs with different configurations; pass --allow-mismatch to override");
+        return false;
+    }
+
+    return true;
+}
+
+fn usage() -> i32 {
+    eprintln!("usage: compare <baseline.csv> <candidate.csv> [--threshold <percent>] [--alpha <level>] [--allow-mismatch]");
+    return 2;
+}
+
+// Entry point for `cargo run --release -- compare ...`. Returns the process exit code:
+// 0 when no column regressed, 1 when at least one did, 2 on bad input or when the runs were
+// made with different configurations and `--allow-mismatch` was not given.
+pub fn run(args: &[String]) -> i32 {
+    let mut paths = Vec::new();
+    let mut threshold = DEFAULT_THRESHOLD;
+    let mut alpha = DEFAULT_ALPHA;
+    let mut allow_mismatch = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threshold" | "--alpha" => {
+                let Some(value) = args.next().and_then(|v| v.parse::<f64>().ok()) else {
+                    return usage();
+                };
+                if arg == "--threshold" {
+                    threshold = value;
+                } else {
+                    alpha = value;
+                }
+            }
+            "--allow-mismatch" => allow_mismatch = true,
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if paths.len() != 2 {
+        return usage();
+    }
+
+    let (baseline, candidate) = match (Samples::load(&paths[0]), Samples::load(&paths[1])) {
+        (Ok(baseline), Ok(candidate)) => (baseline, candidate),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("error: {}", e);
+            return 2;
+        }
+    };
+
+    if !check_metadata(&paths[0], &paths[1], allow_mismatch) {
+        return 2;
+    }
+
+    for column in &baseline.columns {
+        if candidate.column(column).is_none() {
+            eprintln!("warning: column '{}' is missing from {}", column, paths[1]);
+        }
+    }
+
+    let results = compare(&baseline, &candidate, threshold, alpha);
+    print_table(&results);
+
+    let regressions = results.iter().filter(|r| r.verdict == Verdict::Regression).count();
+    if regressions > 0 {
+        println!(
+            "\n{} column(s) regressed by more than {}% (alpha = {})",
+            regressions, threshold, alpha
+        );
+        return 1;
+    }
+
+    return 0;
+}
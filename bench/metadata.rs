@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Keys that describe what was measured. Runs that differ in any of these are not comparable.
+pub const CONFIG_KEYS: [&str; 10] = [
+    "mode",
+    "profile",
+    "opt_level",
+    "trace",
+    "seed",
+    "initial",
+    "ops",
+    "mix",
+    "anchors",
+    "runs",
+];
+
+// Keys that describe where it was measured, plus the sizes each crate picks for itself: the two
+// crates benchmark different element counts and pool sizes, so comparing them must still work.
+// Differences are worth a warning, not a refusal.
+pub const ENVIRONMENT_KEYS: [&str; 8] = [
+    "element_count",
+    "pool_size",
+    "rustc",
+    "hostname",
+    "os",
+    "arch",
+    "cpu",
+    "cpus",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    pub entries: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub key: String,
+    pub config: bool,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl Mismatch {
+    pub fn describe(&self) -> String {
+        let show = |v: &Option<String>| v.clone().unwrap_or_else(|| "<missing>".to_string());
+        return format!("{}: {} vs {}", self.key, show(&self.left), show(&self.right));
+    }
+}
+
+fn cpu_model() -> String {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    return cpuinfo
+        .lines()
+        .find(|line| line.starts_with("model name"))
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, model)| model.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+}
+
+fn hostname() -> String {
+    return std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Metadata { entries: BTreeMap::new() }
+    }
+
+    // Records the build and the machine; callers add the run configuration with `set`.
+    pub fn capture(mode: &str) -> Self {
+        let mut metadata = Metadata::new();
+        metadata.set("crate", env!("CARGO_PKG_NAME"));
+        metadata.set("version", env!("CARGO_PKG_VERSION"));
+        metadata.set("mode", mode);
+        metadata.set("profile", env!("LINKED_RS_PROFILE"));
+        metadata.set("opt_level", env!("LINKED_RS_OPT_LEVEL"));
+        metadata.set("rustc", env!("LINKED_RS_RUSTC_VERSION"));
+        metadata.set("git_commit", env!("LINKED_RS_GIT_COMMIT"));
+        metadata.set("hostname", hostname());
+        metadata.set("os", std::env::consts::OS);
+        metadata.set("arch", std::env::consts::ARCH);
+        metadata.set("cpu", cpu_model());
+        metadata.set(
+            "cpus",
+            std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        );
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        metadata.set("timestamp", timestamp);
+
+        return metadata;
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.entries.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        return self.entries.get(key).map(String::as_str);
+    }
+
+    pub fn sidecar_path(csv_path: &str) -> String {
+        let stem = csv_path.strip_suffix(".csv").unwrap_or(csv_path);
+        return format!("{}.meta.json", stem);
+    }
+
+    pub fn write_sidecar(&self, csv_path: &str) -> std::io::Result<()> {
+        return std::fs::write(Metadata::sidecar_path(csv_path), self.to_json());
+    }
+
+    // Ok(None) when the results file has no sidecar, e.g. because it predates metadata.
+    pub fn load_sidecar(csv_path: &str) -> Result<Option<Metadata>, String> {
+        let path = Metadata::sidecar_path(csv_path);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{}: {}", path, e)),
+        };
+
+        return Metadata::parse_json(&text).map(Some).map_err(|e| format!("{}: {}", path, e));
+    }
+
+    // Every key in either run that is a config or environment key and differs between them.
+    pub fn mismatches(&self, other: &Metadata) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        for (keys, config) in [(&CONFIG_KEYS[..], true), (&ENVIRONMENT_KEYS[..], false)] {
+            for key in keys {
+                let (left, right) = (self.get(key), other.get(key));
+                if left != right {
+                    mismatches.push(Mismatch {
+                        key: key.to_string(),
+                        config,
+                        left: left.map(String::from),
+                        right: right.map(String::from),
+                    });
+                }
+            }
+        }
+
+        return mismatches;
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n");
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            let separator = if i + 1 < self.entries.len() { "," } else { "" };
+            writeln!(json, "  {}: {}{}", quote(key), quote(value), separator).unwrap();
+        }
+        json.push_str("}\n");
+
+        return json;
+    }
+
+    // Accepts the flat string-to-string objects written by `to_json`.
+    pub fn parse_json(text: &str) -> Result<Metadata, String> {
+        let mut chars = text.chars().peekable();
+        let mut metadata = Metadata::new();
+
+        let skip_whitespace = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+        };
+
+        skip_whitespace(&mut chars);
+        if chars.next() != Some('{') {
+            return Err("expected '{'".to_string());
+        }
+        skip_whitespace(&mut chars);
+        if chars.peek() == Some(&'}') {
+            chars.next();
+        } else {
+            loop {
+                skip_whitespace(&mut chars);
+                let key = unquote(&mut chars)?;
+                skip_whitespace(&mut chars);
+                if chars.next() != Some(':') {
+                    return Err(format!("expected ':' after \"{}\"", key));
+                }
+                skip_whitespace(&mut chars);
+                let value = unquote(&mut chars)?;
+                metadata.entries.insert(key, value);
+                skip_whitespace(&mut chars);
+                match chars.next() {
+                    Some(',') => continue,
+                    Some('}') => break,
+                    _ => return Err("expected ',' or '}'".to_string()),
+                }
+            }
+        }
+        skip_whitespace(&mut chars);
+        if chars.next().is_some() {
+            return Err("unexpected text after the closing '}'".to_string());
+        }
+
+        return Ok(metadata);
+    }
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    return quoted;
+}
+
+fn unquote(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    if chars.next() != Some('"') {
+        return Err("expected a string".to_string());
+    }
+
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('/') => text.push('/'),
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some('t') => text.push('\t'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or(format!("invalid escape \\u{}", hex))?;
+                    text.push(c);
+                }
+                other => return Err(format!("invalid escape {:?}", other)),
+            },
+            Some(c) => text.push(c),
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt::Write;
+
+use crate::compare::Samples;
+use crate::metadata::{Metadata, CONFIG_KEYS, ENVIRONMENT_KEYS};
+use crate::stats::Summary;
+
+const WIDTH: f64 = 520.0;
+const HEIGHT: f64 = 280.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 30.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+const COLORS: [&str; 6] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b"];
+
+pub struct Series {
+    pub label: String,
+    pub samples: Samples,
+    pub metadata: Option<Metadata>,
+}
+
+impl Series {
+    // Accepts either `path` or `label=path`; the label defaults to the file stem.
+    pub fn load(spec: &str) -> Result<Series, String> {
+        let (label, path) = match spec.split_once('=') {
+            Some((label, path)) => (label.to_string(), path),
+            None => {
+                let stem = std::path::Path::new(spec)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| spec.to_string());
+                (stem, spec)
+            }
+        };
+
+        return Ok(Series {
+            label,
+            samples: Samples::load(path)?,
+            metadata: Metadata::load_sidecar(path)?,
+        });
+    }
+}
+
+pub struct BoxStats {
+    pub min: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub max: f64,
+}
+
+impl BoxStats {
+    pub fn of(samples: &[f64]) -> BoxStats {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        return BoxStats {
+            min: sorted[0],
+            q1: quantile(&sorted, 0.25),
+            median: quantile(&sorted, 0.5),
+            q3: quantile(&sorted, 0.75),
+            max: sorted[sorted.len() - 1],
+        };
+    }
+}
+
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+
+    return sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64);
+}
+
+fn escape(text: &str) -> String {
+    return text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+}
+
+fn color(i: usize) -> &'static str {
+    return COLORS[i % COLORS.len()];
+}
+
+fn nice_max(value: f64) -> f64 {
+    if value <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(value.log10().floor());
+    for step in [1.0, 2.0, 2.5, 5.0, 10.0] {
+        if step * magnitude >= value {
+            return step * magnitude;
+        }
+    }
+
+    return 10.0 * magnitude;
+}
+
+struct Plot {
+    svg: String,
+    y_max: f64,
+}
+
+impl Plot {
+    fn new(title: &str, y_max: f64) -> Plot {
+        let y_max = nice_max(y_max * 1.05);
+        let mut svg = String::new();
+        write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="11">"#,
+            WIDTH, HEIGHT
+        )
+        .unwrap();
+        write!(
+            svg,
+            r#"<text x="{}" y="18" text-anchor="middle" font-size="13" font-weight="bold">{}</text>"#,
+            WIDTH / 2.0,
+            escape(title)
+        )
+        .unwrap();
+
+        for i in 0..=4 {
+            let value = y_max * i as f64 / 4.0;
+            let y = MARGIN_TOP + (HEIGHT - MARGIN_TOP - MARGIN_BOTTOM) * (1.0 - i as f64 / 4.0);
+            write!(
+                svg,
+                r##"<line x1="{}" y1="{y:.1}" x2="{}" y2="{y:.1}" stroke="#ddd"/><text x="{}" y="{:.1}" text-anchor="end">{}</text>"##,
+                MARGIN_LEFT,
+                WIDTH - MARGIN_RIGHT,
+                MARGIN_LEFT - 6.0,
+                y + 4.0,
+                value
+            )
+            .unwrap();
+        }
+        write!(
+            svg,
+            r#"<text x="14" y="{}" transform="rotate(-90 14 {})" text-anchor="middle">ms</text>"#,
+            HEIGHT / 2.0,
+            HEIGHT / 2.0
+        )
+        .unwrap();
+
+        return Plot { svg, y_max };
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        return MARGIN_TOP + plot_height * (1.0 - value / self.y_max);
+    }
+
+    fn finish(mut self) -> String {
+        self.svg.push_str("</svg>");
+        return self.svg;
+    }
+}
+
+fn time_series(column: &str, series: &[(&str, &[f64])]) -> String {
+    let y_max = series.iter().flat_map(|(_, v)| v.iter()).fold(0.0, |a: f64, b| a.max(*b));
+    let runs = series.iter().map(|(_, v)| v.len()).max().unwrap_or(1).max(2);
+    let mut plot = Plot::new(&format!("{} per run", column), y_max);
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let x = |i: usize| MARGIN_LEFT + plot_width * i as f64 / (runs - 1) as f64;
+
+    for i in 0..runs {
+        write!(
+            plot.svg,
+            r#"<text x="{:.1}" y="{}" text-anchor="middle">{}</text>"#,
+            x(i),
+            HEIGHT - MARGIN_BOTTOM + 16.0,
+            i + 1
+        )
+        .unwrap();
+    }
+
+    for (s, (label, values)) in series.iter().enumerate() {
+        let points: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| format!("{:.1},{:.1}", x(i), plot.y(*v)))
+            .collect();
+        write!(
+            plot.svg,
+            r#"<polyline fill="none" stroke="{}" stroke-width="2" points="{}"><title>{}</title></polyline>"#,
+            color(s),
+            points.join(" "),
+            escape(label)
+        )
+        .unwrap();
+        for point in &points {
+            let (px, py) = point.split_once(',').unwrap();
+            write!(plot.svg, r#"<circle cx="{}" cy="{}" r="3" fill="{}"/>"#, px, py, color(s)).unwrap();
+        }
+    }
+
+    return plot.finish();
+}
+
+fn box_plot(column: &str, series: &[(&str, &[f64])]) -> String {
+    let y_max = series.iter().flat_map(|(_, v)| v.iter()).fold(0.0, |a: f64, b| a.max(*b));
+    let mut plot = Plot::new(&format!("{} distribution", column), y_max);
+    let slot = (WIDTH - MARGIN_LEFT - MARGIN_RIGHT) / series.len() as f64;
+    let box_width = (slot * 0.5).min(80.0);
+
+    for (s, (label, values)) in series.iter().enumerate() {
+        let stats = BoxStats::of(values);
+        let center = MARGIN_LEFT + slot * (s as f64 + 0.5);
+        let left = center - box_width / 2.0;
+        write!(
+            plot.svg,
+            concat!(
+                r#"<g stroke="{color}" stroke-width="1.5"><title>{label}: min {min}, q1 {q1}, median {median}, q3 {q3}, max {max}</title>"#,
+                r#"<line x1="{center:.1}" y1="{ymax:.1}" x2="{center:.1}" y2="{yq3:.1}"/>"#,
+                r#"<line x1="{center:.1}" y1="{yq1:.1}" x2="{center:.1}" y2="{ymin:.1}"/>"#,
+                r#"<line x1="{wl:.1}" y1="{ymax:.1}" x2="{wr:.1}" y2="{ymax:.1}"/>"#,
+                r#"<line x1="{wl:.1}" y1="{ymin:.1}" x2="{wr:.1}" y2="{ymin:.1}"/>"#,
+                r#"<rect x="{left:.1}" y="{yq3:.1}" width="{width:.1}" height="{height:.1}" fill="{color}" fill-opacity="0.25"/>"#,
+                r#"<line x1="{left:.1}" y1="{ymed:.1}" x2="{right:.1}" y2="{ymed:.1}" stroke-width="3"/></g>"#,
+                r#"<text x="{center:.1}" y="{ylabel}" text-anchor="middle">{label}</text>"#
+            ),
+            color = color(s),
+            label = escape(label),
+            min = stats.min,
+            q1 = stats.q1,
+            median = stats.median,
+            q3 = stats.q3,
+            max = stats.max,
+            center = center,
+            left = left,
+            right = left + box_width,
+            wl = center - box_width / 4.0,
+            wr = center + box_width / 4.0,
+            width = box_width,
+            height = plot.y(stats.q1) - plot.y(stats.q3),
+            ymin = plot.y(stats.min),
+            yq1 = plot.y(stats.q1),
+            ymed = plot.y(stats.median),
+            yq3 = plot.y(stats.q3),
+            ymax = plot.y(stats.max),
+            ylabel = HEIGHT - MARGIN_BOTTOM + 16.0,
+        )
+        .unwrap();
+    }
+
+    return plot.finish();
+}
+
+fn comparison_table(columns: &[String], series: &[Series]) -> String {
+    let mut html = String::from("<table><tr><th>payload</th>");
+    for s in series {
+        write!(html, "<th>{} mean ms</th><th>median ms</th>", escape(&s.label)).unwrap();
+    }
+    for s in series.iter().skip(1) {
+        write!(html, "<th>{} vs {}</th>", escape(&s.label), escape(&series[0].label)).unwrap();
+    }
+    html.push_str("</tr>");
+
+    for column in columns {
+        write!(html, "<tr><td>{}</td>", escape(column)).unwrap();
+        let means: Vec<Option<f64>> = series
+            .iter()
+            .map(|s| s.samples.column(column).filter(|v| !v.is_empty()).map(|v| Summary::of(v).mean))
+            .collect();
+        for s in series {
+            match s.samples.column(column).filter(|v| !v.is_empty()) {
+                Some(values) => write!(
+                    html,
+                    "<td>{:.1}</td><td>{:.1}</td>",
+                    Summary::of(values).mean,
+                    BoxStats::of(values).median
+                )
+                .unwrap(),
+                None => html.push_str("<td>-</td><td>-</td>"),
+            }
+        }
+        for mean in means.iter().skip(1) {
+            match (means[0], mean) {
+                (Some(base), Some(mean)) if base > 0.0 => {
+                    let change = (mean - base) / base * 100.0;
+                    let class = if change > 0.0 { "slower" } else { "faster" };
+                    write!(html, r#"<td class="{}">{:+.1}%</td>"#, class, change).unwrap();
+                }
+                _ => html.push_str("<td>-</td>"),
+            }
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+
+    return html;
+}
+
+// Configuration and environment differences between each series and the first one.
+pub fn warnings(series: &[Series]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for s in series {
+        if s.metadata.is_none() {
+            warnings.push(format!("{} has no metadata; its configuration cannot be checked", s.label));
+        }
+    }
+
+    let Some(first) = series.first().filter(|s| s.metadata.is_some()) else {
+        return warnings;
+    };
+    for s in series.iter().skip(1) {
+        let Some(metadata) = &s.metadata else {
+            continue;
+        };
+        for mismatch in first.metadata.as_ref().unwrap().mismatches(metadata) {
+            let kind = if mismatch.config { "configuration" } else { "environment" };
+            warnings.push(format!(
+                "{} differs between {} and {}: {}",
+                kind,
+                first.label,
+                s.label,
+                mismatch.describe()
+            ));
+        }
+    }
+
+    return warnings;
+}
+
+fn metadata_table(series: &[Series]) -> String {
+    let mut keys: Vec<String> = Vec::new();
+    for s in series {
+        for key in s.metadata.iter().flat_map(|m| m.entries.keys()) {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+    }
+    // Most relevant first: what was measured, then where.
+    keys.sort_by_key(|k| {
+        let rank = CONFIG_KEYS
+            .iter()
+            .position(|c| c == k)
+            .or_else(|| ENVIRONMENT_KEYS.iter().position(|e| e == k).map(|p| p + CONFIG_KEYS.len()))
+            .unwrap_or(usize::MAX);
+        (rank, k.clone())
+    });
+
+    let mut html = String::from("<table><tr><th>key</th>");
+    for s in series {
+        write!(html, "<th>{}</th>", escape(&s.label)).unwrap();
+    }
+    html.push_str("</tr>");
+    for key in &keys {
+        write!(html, "<tr><td>{}</td>", escape(key)).unwrap();
+        for s in series {
+            let value = s.metadata.as_ref().and_then(|m| m.get(key)).unwrap_or("-");
+            write!(html, "<td>{}</td>", escape(value)).unwrap();
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+
+    return html;
+}
+
+pub fn render(series: &[Series]) -> String {
+    let mut columns: Vec<String> = Vec::new();
+    for s in series {
+        for column in &s.samples.columns {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+    }
+
+    let mut html = String::from(concat!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>linked-rs benchmark report</title>",
+        "<style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}",
+        "td,th{border:1px solid #ccc;padding:4px 8px;text-align:right}",
+        ".warning{background:#fff3cd;border:1px solid #e0c36c;padding:0.5em 1em}",
+        ".slower{color:#c00}.faster{color:#080}.charts{display:flex;flex-wrap:wrap;gap:1em}</style>",
+        "</head><body>\n<h1>linked-rs benchmark report</h1>\n"
+    ));
+
+    html.push_str("<p>");
+    for (i, s) in series.iter().enumerate() {
+        write!(
+            html,
+            r#"<span style="color:{}">&#9632;</span> {} &nbsp; "#,
+            color(i),
+            escape(&s.label)
+        )
+        .unwrap();
+    }
+    html.push_str("</p>\n");
+
+    let warnings = warnings(series);
+    if !warnings.is_empty() {
+        html.push_str("<div class=\"warning\"><strong>These runs may not be comparable:</strong><ul>");
+        for warning in &warnings {
+            write!(html, "<li>{}</li>", escape(warning)).unwrap();
+        }
+        html.push_str("</ul></div>\n");
+    }
+
+    html.push_str("<h2>Summary</h2>\n");
+    html.push_str(&comparison_table(&columns, series));
+    html.push('\n');
+
+    for column in &columns {
+        let data: Vec<(&str, &[f64])> = series
+            .iter()
+            .filter_map(|s| {
+                s.samples
+                    .column(column)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| (s.label.as_str(), v))
+            })
+            .collect();
+        if data.is_empty() {
+            continue;
+        }
+
+        write!(html, "<h2>{}</h2>\n<div class=\"charts\">", escape(column)).unwrap();
+        html.push_str(&time_series(column, &data));
+        html.push_str(&box_plot(column, &data));
+        html.push_str("</div>\n");
+    }
+    html.push_str("<h2>Run metadata</h2>\n");
+    html.push_str(&metadata_table(series));
+    html.push_str("\n</body></html>\n");
+
+    return html;
+}
+
+fn usage() -> i32 {
+    eprintln!("usage: report <output.html> [label=]<results.csv>...");
+    return 2;
+}
+
+// Entry point for `cargo run --release -- report ...`.
+pub fn run(args: &[String]) -> i32 {
+    if args.len() < 2 {
+        return usage();
+    }
+
+    let mut series = Vec::new();
+    for spec in &args[1..] {
+        match Series::load(spec) {
+            Ok(s) => series.push(s),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return 2;
+            }
+        }
+    }
+
+    for warning in warnings(&series) {
+        eprintln!("warning: {}", warning);
+    }
+
+    if let Err(e) = std::fs::write(&args[0], render(&series)) {
+        eprintln!("error: {}: {}", args[0], e);
+        return 2;
+    }
+    println!("Report written to {}", args[0]);
+
+    return 0;
+}
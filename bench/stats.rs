@@ -0,0 +1,145 @@
+pub struct Summary {
+    pub n: usize,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl Summary {
+    pub fn of(samples: &[f64]) -> Summary {
+        let n = samples.len();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+
+        return Summary { n, mean, variance };
+    }
+}
+
+pub struct TTest {
+    pub t: f64,
+    pub df: f64,
+    pub p_value: f64,
+}
+
+// Welch's unequal-variance t-test, two-sided.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> TTest {
+    let sa = Summary::of(a);
+    let sb = Summary::of(b);
+
+    let va = sa.variance / sa.n as f64;
+    let vb = sb.variance / sb.n as f64;
+    let se2 = va + vb;
+
+    if se2 == 0.0 {
+        // Both samples are constant: either identical or infinitely significant.
+        let p_value = if sa.mean == sb.mean { 1.0 } else { 0.0 };
+        let t = if sa.mean == sb.mean { 0.0 } else { f64::INFINITY };
+        return TTest { t, df: (sa.n + sb.n - 2) as f64, p_value };
+    }
+
+    let t = (sb.mean - sa.mean) / se2.sqrt();
+    let df = se2 * se2
+        / (va * va / (sa.n as f64 - 1.0).max(1.0) + vb * vb / (sb.n as f64 - 1.0).max(1.0));
+    let p_value = incomplete_beta(df / (df + t * t), df / 2.0, 0.5);
+
+    return TTest { t, df, p_value };
+}
+
+fn ln_gamma(x: f64) -> f64 {
+    // Lanczos approximation, g = 7.
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum = COEFFS[0];
+    for (i, c) in COEFFS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+
+    return 0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln();
+}
+
+// Regularized incomplete beta function I_x(a, b).
+pub fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+
+    // The continued fraction converges quickly only below the mean of the distribution.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        return ln_front.exp() * beta_continued_fraction(x, a, b) / a;
+    }
+
+    return 1.0 - ln_front.exp() * beta_continued_fraction(1.0 - x, b, a) / b;
+}
+
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-14;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+
+    for m in 1..300 {
+        let m = m as f64;
+
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + even * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + even / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        result *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + odd * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + odd / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        result *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    return result;
+}
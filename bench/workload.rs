@@ -0,0 +1,342 @@
+use std::collections::{LinkedList, VecDeque};
+use std::fmt;
+use crate::enums::Side;
+use crate::trace::Op;
+
+// SplitMix64: tiny, fast and good enough to make workloads reproducible from a seed.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+
+    // Uniform in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    }
+
+    // Uniform in [0, n).
+    pub fn below(&mut self, n: usize) -> usize {
+        return ((self.next_u64() as u128 * n as u128) >> 64) as usize;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpMix {
+    pub push_first: u32,
+    pub push_back: u32,
+    pub pop_first: u32,
+    pub pop_back: u32,
+    pub insert: u32,
+    pub remove: u32,
+}
+
+impl OpMix {
+    // Parses `push_back=4,insert=3,remove=2`; operations that are not listed get weight 0.
+    pub fn parse(spec: &str) -> Result<OpMix, String> {
+        let mut mix = OpMix { push_first: 0, push_back: 0, pop_first: 0, pop_back: 0, insert: 0, remove: 0 };
+        for part in spec.split(',').filter(|p| !p.is_empty()) {
+            let (name, weight) = part.split_once('=').ok_or(format!("expected name=weight, found '{}'", part))?;
+            let weight = weight.parse::<u32>().map_err(|_| format!("'{}' is not a weight", weight))?;
+            match name {
+                "push_first" => mix.push_first = weight,
+                "push_back" => mix.push_back = weight,
+                "pop_first" => mix.pop_first = weight,
+                "pop_back" => mix.pop_back = weight,
+                "insert" => mix.insert = weight,
+                "remove" => mix.remove = weight,
+                _ => return Err(format!("unknown operation '{}'", name)),
+            }
+        }
+        if mix.total() == 0 {
+            return Err("operation mix has no weight".to_string());
+        }
+
+        return Ok(mix);
+    }
+
+    fn total(&self) -> u64 {
+        return [self.push_first, self.push_back, self.pop_first, self.pop_back, self.insert, self.remove]
+            .iter()
+            .map(|w| *w as u64)
+            .sum();
+    }
+}
+
+impl fmt::Display for OpMix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "push_first={},push_back={},pop_first={},pop_back={},insert={},remove={}",
+            self.push_first, self.push_back, self.pop_first, self.pop_back, self.insert, self.remove
+        )
+    }
+}
+
+impl Default for OpMix {
+    fn default() -> Self {
+        OpMix { push_first: 1, push_back: 1, pop_first: 1, pop_back: 1, insert: 4, remove: 2 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnchorDistribution {
+    Uniform,
+    HeadBiased,
+    TailBiased,
+    // Centre and width are fractions of the list length.
+    HotSpot { center: f64, width: f64 },
+}
+
+impl AnchorDistribution {
+    // Parses `uniform`, `head`, `tail` or `hot-spot[:center[:width]]`.
+    pub fn parse(spec: &str) -> Result<AnchorDistribution, String> {
+        let mut parts = spec.split(':');
+        let fraction = |part: Option<&str>, default: f64| -> Result<f64, String> {
+            match part {
+                None => Ok(default),
+                Some(p) => match p.parse::<f64>() {
+                    Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+                    _ => Err(format!("'{}' is not a fraction between 0 and 1", p)),
+                },
+            }
+        };
+
+        return match parts.next() {
+            Some("uniform") => Ok(AnchorDistribution::Uniform),
+            Some("head") => Ok(AnchorDistribution::HeadBiased),
+            Some("tail") => Ok(AnchorDistribution::TailBiased),
+            Some("hot-spot") => Ok(AnchorDistribution::HotSpot {
+                center: fraction(parts.next(), 0.5)?,
+                width: fraction(parts.next(), 0.05)?,
+            }),
+            _ => Err(format!("unknown anchor distribution '{}'", spec)),
+        };
+    }
+
+    pub fn pick(&self, rng: &mut Rng, len: usize) -> usize {
+        let position = match *self {
+            AnchorDistribution::Uniform => return rng.below(len),
+            AnchorDistribution::HeadBiased => rng.next_f64().powi(3),
+            AnchorDistribution::TailBiased => 1.0 - rng.next_f64().powi(3),
+            AnchorDistribution::HotSpot { center, width } => {
+                // Triangular distribution around the centre.
+                center + (rng.next_f64() + rng.next_f64() - 1.0) * width
+            }
+        };
+
+        return ((position * len as f64) as usize).min(len - 1);
+    }
+}
+
+impl fmt::Display for AnchorDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnchorDistribution::Uniform => write!(f, "uniform"),
+            AnchorDistribution::HeadBiased => write!(f, "head"),
+            AnchorDistribution::TailBiased => write!(f, "tail"),
+            AnchorDistribution::HotSpot { center, width } => write!(f, "hot-spot:{}:{}", center, width),
+        }
+    }
+}
+
+// Ids of the live nodes in list order, split into blocks so that positional inserts and
+// removals stay cheap while generating millions of operations.
+struct Positions {
+    blocks: Vec<Vec<u64>>,
+    len: usize,
+}
+
+const BLOCK_SIZE: usize = 1024;
+
+impl Positions {
+    fn new() -> Self {
+        Positions { blocks: vec![Vec::new()], len: 0 }
+    }
+
+    fn locate(&self, mut index: usize) -> (usize, usize) {
+        for (b, block) in self.blocks.iter().enumerate() {
+            if index < block.len() {
+                return (b, index);
+            }
+            index -= block.len();
+        }
+        let last = self.blocks.len() - 1;
+        return (last, self.blocks[last].len());
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        let (b, i) = self.locate(index);
+        return self.blocks[b][i];
+    }
+
+    fn insert(&mut self, index: usize, id: u64) {
+        let (b, i) = self.locate(index);
+        self.blocks[b].insert(i, id);
+        if self.blocks[b].len() > 2 * BLOCK_SIZE {
+            let rest = self.blocks[b].split_off(BLOCK_SIZE);
+            self.blocks.insert(b + 1, rest);
+        }
+        self.len += 1;
+    }
+
+    fn remove(&mut self, index: usize) -> u64 {
+        let (b, i) = self.locate(index);
+        let id = self.blocks[b].remove(i);
+        if self.blocks[b].is_empty() && self.blocks.len() > 1 {
+            self.blocks.remove(b);
+        }
+        self.len -= 1;
+        return id;
+    }
+}
+
+pub struct Workload {
+    pub ops: Vec<Op>,
+    // For inserts and removes, the index of the anchor or removed node at the time of the
+    // operation. Positional baselines use it instead of searching by id.
+    pub positions: Vec<usize>,
+}
+
+pub struct WorkloadConfig {
+    pub seed: u64,
+    pub initial: usize,
+    pub ops: usize,
+    pub mix: OpMix,
+    pub anchors: AnchorDistribution,
+}
+
+pub fn generate(config: &WorkloadConfig) -> Workload {
+    let mut rng = Rng::new(config.seed);
+    let mut order = Positions::new();
+    let mut ops = Vec::with_capacity(config.initial + config.ops);
+    let mut positions = Vec::with_capacity(config.initial + config.ops);
+    let mut next_id = 0;
+
+    for _ in 0..config.initial {
+        order.insert(order.len, next_id);
+        ops.push(Op::PushBack(next_id));
+        positions.push(0);
+        next_id += 1;
+    }
+
+    let mix = config.mix;
+    let total = mix.total();
+    let pushes = mix.push_first as u64 + mix.push_back as u64;
+
+    for _ in 0..config.ops {
+        let mut roll = rng.next_u64() % total;
+        if order.len == 0 && roll >= pushes {
+            // Nothing to pop, anchor on or remove: push instead when the mix allows it.
+            if pushes == 0 {
+                ops.push(Op::PopBack(None));
+                positions.push(0);
+                continue;
+            }
+            roll = rng.next_u64() % pushes;
+        }
+
+        let mut choose = |weight: u32| {
+            if roll < weight as u64 {
+                return true;
+            }
+            roll -= weight as u64;
+            return false;
+        };
+
+        let (op, position) = if choose(mix.push_first) {
+            order.insert(0, next_id);
+            next_id += 1;
+            (Op::PushFirst(next_id - 1), 0)
+        } else if choose(mix.push_back) {
+            order.insert(order.len, next_id);
+            next_id += 1;
+            (Op::PushBack(next_id - 1), 0)
+        } else if choose(mix.pop_first) {
+            (Op::PopFirst(Some(order.remove(0))), 0)
+        } else if choose(mix.pop_back) {
+            (Op::PopBack(Some(order.remove(order.len - 1))), 0)
+        } else if choose(mix.insert) {
+            let position = config.anchors.pick(&mut rng, order.len);
+            let anchor = order.get(position);
+            let side = if rng.next_u64() & 1 == 0 { Side::Before } else { Side::After };
+            let index = if side == Side::Before { position } else { position + 1 };
+            order.insert(index, next_id);
+            next_id += 1;
+            (Op::Insert(side, anchor, next_id - 1), position)
+        } else {
+            let position = config.anchors.pick(&mut rng, order.len);
+            (Op::Remove(order.remove(position)), position)
+        };
+        ops.push(op);
+        positions.push(position);
+    }
+
+    return Workload { ops, positions };
+}
+
+pub fn run_vec_deque<T>(workload: &Workload, mut make: impl FnMut(u64) -> T) -> VecDeque<T> {
+    let mut deque = VecDeque::new();
+    for (op, position) in workload.ops.iter().zip(&workload.positions) {
+        match *op {
+            Op::PushFirst(id) => deque.push_front(make(id)),
+            Op::PushBack(id) => deque.push_back(make(id)),
+            Op::PopFirst(_) => {
+                deque.pop_front();
+            }
+            Op::PopBack(_) => {
+                deque.pop_back();
+            }
+            Op::Insert(side, _, id) => {
+                let index = if side == Side::Before { *position } else { position + 1 };
+                deque.insert(index, make(id));
+            }
+            Op::Remove(_) => {
+                deque.remove(*position);
+            }
+        }
+    }
+
+    return deque;
+}
+
+// std's LinkedList has no stable cursor API, so positional edits split and re-append.
+pub fn run_std_linked_list<T>(workload: &Workload, mut make: impl FnMut(u64) -> T) -> LinkedList<T> {
+    let mut list = LinkedList::new();
+    for (op, position) in workload.ops.iter().zip(&workload.positions) {
+        match *op {
+            Op::PushFirst(id) => list.push_front(make(id)),
+            Op::PushBack(id) => list.push_back(make(id)),
+            Op::PopFirst(_) => {
+                list.pop_front();
+            }
+            Op::PopBack(_) => {
+                list.pop_back();
+            }
+            Op::Insert(side, _, id) => {
+                let index = if side == Side::Before { *position } else { position + 1 };
+                let mut rest = list.split_off(index);
+                rest.push_front(make(id));
+                list.append(&mut rest);
+            }
+            Op::Remove(_) => {
+                let mut rest = list.split_off(*position);
+                rest.pop_front();
+                list.append(&mut rest);
+            }
+        }
+    }
+
+    return list;
+}
@@ -0,0 +1,421 @@
+// Behavioural contract shared by every list backend in this repository.
+//
+// A backend crate pulls this file into a test module, implements `ListBackend` for its list
+// and instantiates the tests:
+//
+//     #[cfg(test)]
+//     mod conformance_tests {
+//         include!("../../conformance/suite.rs");
+//
+//         impl<T: Clone> ListBackend<T> for MyList<T> { ... }
+//
+//         conformance_suite!(MyList);
+//     }
+//
+// The scenarios never use a handle after its node was removed, because the backends answer
+// differently: `doubly` panics on reads and refuses the handle elsewhere, while `doubly-pool`
+// returns `HandleError::Stale` throughout.
+
+use crate::enums::Side;
+
+pub trait ListBackend<T: Clone> {
+    type Handle: Clone;
+
+    fn create() -> Self;
+    fn push_first(&mut self, data: T) -> Self::Handle;
+    fn push_back(&mut self, data: T) -> Self::Handle;
+    fn pop_first(&mut self) -> Option<T>;
+    fn pop_back(&mut self) -> Option<T>;
+    fn insert(&mut self, side: Side, data: T, anchor: &Self::Handle) -> Self::Handle;
+    fn remove(&mut self, node: Self::Handle) -> Option<T>;
+
+    fn head(&self) -> Option<Self::Handle>;
+    fn tail(&self) -> Option<Self::Handle>;
+    fn next(&self, node: &Self::Handle) -> Option<Self::Handle>;
+    fn prev(&self, node: &Self::Handle) -> Option<Self::Handle>;
+    fn value(&self, node: &Self::Handle) -> T;
+    fn same(a: &Self::Handle, b: &Self::Handle) -> bool;
+}
+
+// Walks the list both ways, checking every link, and returns the contents front to back.
+pub fn contents<T: Clone + PartialEq + std::fmt::Debug, L: ListBackend<T>>(list: &L) -> Vec<T> {
+    let mut forward = Vec::new();
+    let mut previous: Option<L::Handle> = None;
+    let mut current = list.head();
+    while let Some(node) = current {
+        match (list.prev(&node), &previous) {
+            (None, None) => {}
+            (Some(a), Some(b)) => assert!(L::same(&a, b), "prev link of node {} is wrong", forward.len()),
+            (a, _) => panic!("prev link of node {} is {:?}", forward.len(), a.map(|a| list.value(&a))),
+        }
+        forward.push(list.value(&node));
+        assert!(forward.len() <= 1_000_000, "cycle in the next links");
+        current = list.next(&node);
+        previous = Some(node);
+    }
+    match (list.tail(), &previous) {
+        (None, None) => {}
+        (Some(a), Some(b)) => assert!(L::same(&a, b), "tail is not the last node"),
+        _ => panic!("tail does not match the last node"),
+    }
+
+    let mut backward = Vec::new();
+    let mut current = list.tail();
+    while let Some(node) = current {
+        backward.push(list.value(&node));
+        assert!(backward.len() <= forward.len(), "backward walk is longer than forward walk");
+        current = list.prev(&node);
+    }
+    backward.reverse();
+    assert_eq!(backward, forward, "backward walk differs from forward walk");
+
+    return forward;
+}
+
+pub mod scenarios {
+    use std::collections::VecDeque;
+    use super::{contents, ListBackend};
+    use crate::enums::Side;
+    use crate::workload::Rng;
+
+    pub fn empty_list<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        assert!(list.head().is_none());
+        assert!(list.tail().is_none());
+        assert_eq!(list.pop_first(), None);
+        assert_eq!(list.pop_back(), None);
+        assert!(contents(&list).is_empty());
+    }
+
+    pub fn push_first_and_pop_first<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_first(1);
+        list.push_first(2);
+        list.push_first(3);
+        assert_eq!(contents(&list), vec![3, 2, 1]);
+
+        assert_eq!(list.pop_first(), Some(3));
+        assert_eq!(list.pop_first(), Some(2));
+        assert_eq!(contents(&list), vec![1]);
+        assert_eq!(list.pop_first(), Some(1));
+        assert_eq!(list.pop_first(), None);
+        assert!(contents(&list).is_empty());
+    }
+
+    pub fn push_back_and_pop_back<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+        assert_eq!(contents(&list), vec![1, 2, 3]);
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(contents(&list), vec![1]);
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_back(), None);
+        assert!(contents(&list).is_empty());
+    }
+
+    pub fn push_back_and_pop_first<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_first(0);
+        assert_eq!(list.pop_first(), Some(0));
+        assert_eq!(list.pop_first(), Some(1));
+        assert_eq!(contents(&list), vec![2]);
+    }
+
+    pub fn reuse_after_emptying<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_back(1);
+        assert_eq!(list.pop_back(), Some(1));
+        list.push_back(2);
+        list.push_first(3);
+        assert_eq!(contents(&list), vec![3, 2]);
+    }
+
+    pub fn insert_before_head<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        let first = list.push_back(1);
+        let new = list.insert(Side::Before, 2, &first);
+        assert!(L::same(&list.head().unwrap(), &new));
+        assert!(L::same(&list.tail().unwrap(), &first));
+        assert_eq!(contents(&list), vec![2, 1]);
+    }
+
+    pub fn insert_after_tail<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        let first = list.push_back(1);
+        let new = list.insert(Side::After, 2, &first);
+        assert!(L::same(&list.head().unwrap(), &first));
+        assert!(L::same(&list.tail().unwrap(), &new));
+        assert_eq!(contents(&list), vec![1, 2]);
+    }
+
+    pub fn insert_between_nodes<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        let first = list.push_back(1);
+        let third = list.push_back(3);
+        list.insert(Side::After, 2, &first);
+        list.insert(Side::Before, 4, &third);
+        assert_eq!(contents(&list), vec![1, 2, 4, 3]);
+    }
+
+    pub fn insert_on_single_element<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        let anchor = list.push_back(1);
+        list.insert(Side::After, 2, &anchor);
+        list.insert(Side::Before, 0, &anchor);
+        assert_eq!(contents(&list), vec![0, 1, 2]);
+    }
+
+    pub fn repeated_insert_at_anchor<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        let anchor = list.push_back(1);
+        list.insert(Side::After, 2, &anchor);
+        list.insert(Side::After, 3, &anchor);
+        list.insert(Side::Before, 4, &anchor);
+        list.insert(Side::Before, 5, &anchor);
+        assert_eq!(contents(&list), vec![4, 5, 1, 3, 2]);
+    }
+
+    pub fn returned_handles_are_usable_anchors<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        let a = list.push_first(1);
+        let b = list.insert(Side::After, 2, &a);
+        let c = list.insert(Side::After, 3, &b);
+        list.insert(Side::Before, 4, &c);
+        assert_eq!(list.value(&b), 2);
+        assert_eq!(contents(&list), vec![1, 2, 4, 3]);
+    }
+
+    pub fn remove_single_element<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_first(10);
+        let head = list.head().unwrap();
+        assert_eq!(list.remove(head), Some(10));
+        assert!(contents(&list).is_empty());
+    }
+
+    pub fn remove_head<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_back(10);
+        list.push_back(20);
+        list.push_back(30);
+        let head = list.head().unwrap();
+        assert_eq!(list.remove(head), Some(10));
+        assert_eq!(contents(&list), vec![20, 30]);
+    }
+
+    pub fn remove_tail<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_back(10);
+        list.push_back(20);
+        list.push_back(30);
+        let tail = list.tail().unwrap();
+        assert_eq!(list.remove(tail), Some(30));
+        assert_eq!(contents(&list), vec![10, 20]);
+    }
+
+    pub fn remove_middle<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_back(10);
+        list.push_back(20);
+        list.push_back(30);
+        let middle = list.next(&list.head().unwrap()).unwrap();
+        assert_eq!(list.remove(middle), Some(20));
+        assert_eq!(contents(&list), vec![10, 30]);
+        assert_eq!(list.pop_first(), Some(10));
+        assert_eq!(list.pop_first(), Some(30));
+        assert_eq!(list.pop_first(), None);
+    }
+
+    pub fn pop_keeps_end_links_clear<L: ListBackend<i32>>() {
+        let mut list = L::create();
+        list.push_back(10);
+        list.push_back(20);
+        list.push_back(30);
+        assert_eq!(list.pop_first(), Some(10));
+        assert!(list.prev(&list.head().unwrap()).is_none());
+        assert_eq!(list.pop_back(), Some(30));
+        assert!(list.next(&list.tail().unwrap()).is_none());
+        assert_eq!(contents(&list), vec![20]);
+    }
+
+    pub fn owned_payloads<L: ListBackend<String>>() {
+        let mut list = L::create();
+        let alice = list.push_back("Alice".to_string());
+        list.insert(Side::After, "Charlie".to_string(), &alice);
+        list.insert(Side::Before, "Bob".to_string(), &alice);
+        drop(alice);
+        assert_eq!(contents(&list), vec!["Bob", "Alice", "Charlie"]);
+
+        let middle = list.next(&list.head().unwrap()).unwrap();
+        assert_eq!(list.remove(middle), Some("Alice".to_string()));
+        assert_eq!(list.pop_back(), Some("Charlie".to_string()));
+        assert_eq!(list.pop_back(), Some("Bob".to_string()));
+        assert_eq!(list.pop_back(), None);
+    }
+
+    // Applies a seeded random sequence of operations to the list and to a VecDeque model,
+    // checking contents and links after every step.
+    pub fn matches_vec_deque<L: ListBackend<i32>>(seed: u64, steps: usize) {
+        let mut rng = Rng::new(seed);
+        let mut list = L::create();
+        let mut model = VecDeque::new();
+        // Handles in list order, so a random index picks a random existing node.
+        let mut handles: VecDeque<L::Handle> = VecDeque::new();
+        // Keep some runs tiny so the head/tail edge cases come up constantly.
+        let cap = 1 + rng.below(12);
+
+        for step in 0..steps {
+            let value = step as i32;
+            let mut op = rng.below(6);
+            if model.len() >= cap && op < 2 {
+                op += 2;
+            }
+            let context = format!("seed {} step {} op {}", seed, step, op);
+
+            match op {
+                0 => {
+                    handles.push_front(list.push_first(value));
+                    model.push_front(value);
+                }
+                1 => {
+                    handles.push_back(list.push_back(value));
+                    model.push_back(value);
+                }
+                2 => {
+                    handles.pop_front();
+                    assert_eq!(list.pop_first(), model.pop_front(), "{}", context);
+                }
+                3 => {
+                    handles.pop_back();
+                    assert_eq!(list.pop_back(), model.pop_back(), "{}", context);
+                }
+                4 if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    let side = if rng.below(2) == 0 { Side::Before } else { Side::After };
+                    let at = if side == Side::Before { index } else { index + 1 };
+                    let node = list.insert(side, value, &handles[index]);
+                    handles.insert(at, node);
+                    model.insert(at, value);
+                }
+                5 if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    let node = handles.remove(index).unwrap();
+                    assert_eq!(list.remove(node), model.remove(index), "{}", context);
+                }
+                _ => {}
+            }
+
+            assert_eq!(contents(&list), Vec::from(model.clone()), "{}", context);
+        }
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! conformance_suite {
+    ($backend:ident) => {
+        #[test]
+        fn empty_list() {
+            scenarios::empty_list::<$backend<i32>>();
+        }
+
+        #[test]
+        fn push_first_and_pop_first() {
+            scenarios::push_first_and_pop_first::<$backend<i32>>();
+        }
+
+        #[test]
+        fn push_back_and_pop_back() {
+            scenarios::push_back_and_pop_back::<$backend<i32>>();
+        }
+
+        #[test]
+        fn push_back_and_pop_first() {
+            scenarios::push_back_and_pop_first::<$backend<i32>>();
+        }
+
+        #[test]
+        fn reuse_after_emptying() {
+            scenarios::reuse_after_emptying::<$backend<i32>>();
+        }
+
+        #[test]
+        fn insert_before_head() {
+            scenarios::insert_before_head::<$backend<i32>>();
+        }
+
+        #[test]
+        fn insert_after_tail() {
+            scenarios::insert_after_tail::<$backend<i32>>();
+        }
+
+        #[test]
+        fn insert_between_nodes() {
+            scenarios::insert_between_nodes::<$backend<i32>>();
+        }
+
+        #[test]
+        fn insert_on_single_element() {
+            scenarios::insert_on_single_element::<$backend<i32>>();
+        }
+
+        #[test]
+        fn repeated_insert_at_anchor() {
+            scenarios::repeated_insert_at_anchor::<$backend<i32>>();
+        }
+
+        #[test]
+        fn returned_handles_are_usable_anchors() {
+            scenarios::returned_handles_are_usable_anchors::<$backend<i32>>();
+        }
+
+        #[test]
+        fn remove_single_element() {
+            scenarios::remove_single_element::<$backend<i32>>();
+        }
+
+        #[test]
+        fn remove_head() {
+            scenarios::remove_head::<$backend<i32>>();
+        }
+
+        #[test]
+        fn remove_tail() {
+            scenarios::remove_tail::<$backend<i32>>();
+        }
+
+        #[test]
+        fn remove_middle() {
+            scenarios::remove_middle::<$backend<i32>>();
+        }
+
+        #[test]
+        fn pop_keeps_end_links_clear() {
+            scenarios::pop_keeps_end_links_clear::<$backend<i32>>();
+        }
+
+        #[test]
+        fn owned_payloads() {
+            scenarios::owned_payloads::<$backend<String>>();
+        }
+
+        #[test]
+        fn random_operations_match_vec_deque() {
+            for seed in 0..500 {
+                scenarios::matches_vec_deque::<$backend<i32>>(seed, 200);
+            }
+        }
+
+        #[test]
+        fn long_random_sequences_match_vec_deque() {
+            for seed in 1_000..1_010 {
+                scenarios::matches_vec_deque::<$backend<i32>>(seed, 3_000);
+            }
+        }
+    };
+}
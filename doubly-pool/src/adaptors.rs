@@ -0,0 +1,144 @@
+use std::cell::Ref;
+use crate::linked_list::DoublyLinkedList;
+use crate::singly_list::SinglyLinkedList;
+
+// Thin wrappers that name the access pattern and expose only its operations. `Stack` and
+// `Queue` sit on `SinglyLinkedList`, whose nodes have no `prev` link; `Deque` needs both ends
+// and sits on `DoublyLinkedList`. `init` pre-fills the backend's pool in each of them.
+
+#[derive(Debug)]
+pub struct Stack<T: Clone> {
+    list: SinglyLinkedList<T>,
+}
+
+impl<T: Clone> Stack<T> {
+    pub fn new() -> Self {
+        return Stack { list: SinglyLinkedList::new() };
+    }
+
+    pub fn init(&mut self, size: usize, default_value: T) {
+        self.list.init(size, default_value);
+    }
+
+    pub fn push(&mut self, data: T) {
+        self.list.push_first(data);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        return self.list.pop_first();
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        return self.list.front();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.list.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.list.is_empty();
+    }
+}
+
+impl<T: Clone> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct Queue<T: Clone> {
+    list: SinglyLinkedList<T>,
+}
+
+impl<T: Clone> Queue<T> {
+    pub fn new() -> Self {
+        return Queue { list: SinglyLinkedList::new() };
+    }
+
+    pub fn init(&mut self, size: usize, default_value: T) {
+        self.list.init(size, default_value);
+    }
+
+    // Adds at the back.
+    pub fn push(&mut self, data: T) {
+        self.list.push_back(data);
+    }
+
+    // Takes from the front.
+    pub fn pop(&mut self) -> Option<T> {
+        return self.list.pop_first();
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        return self.list.front();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.list.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.list.is_empty();
+    }
+}
+
+impl<T: Clone> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct Deque<T: Clone> {
+    list: DoublyLinkedList<T>,
+}
+
+impl<T: Clone> Deque<T> {
+    pub fn new() -> Self {
+        return Deque { list: DoublyLinkedList::new() };
+    }
+
+    pub fn init(&mut self, size: usize, default_value: T) {
+        self.list.init(size, default_value);
+    }
+
+    pub fn push_first(&mut self, data: T) {
+        self.list.push_first(data);
+    }
+
+    pub fn push_back(&mut self, data: T) {
+        self.list.push_back(data);
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        return self.list.pop_first();
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        return self.list.pop_back();
+    }
+
+    pub fn front(&self) -> Option<Ref<'_, T>> {
+        return self.list.front();
+    }
+
+    pub fn back(&self) -> Option<Ref<'_, T>> {
+        return self.list.back();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.list.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.list.is_empty();
+    }
+}
+
+impl<T: Clone> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,4 +1,71 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Side {
     Before,
     After,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HandleError {
+    // The element was removed; its node may already hold another element. `SkipListMap` also
+    // reports a handle to an entry of another map this way.
+    Stale,
+    // A node the operation has to change is borrowed through `get` or `get_mut`.
+    Borrowed,
+}
+
+impl fmt::Display for HandleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandleError::Stale => write!(f, "handle refers to an element that was removed"),
+            HandleError::Borrowed => write!(f, "a node the operation needs is borrowed"),
+        }
+    }
+}
+
+// The first broken invariant found by `DoublyLinkedList::validate`. Indexes count nodes from
+// the head.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidationError {
+    HeadHasPrev,
+    TailHasNext,
+    // The prev link of the node after `index` does not point back at it.
+    BrokenLink { index: usize },
+    TailNotLast { count: usize },
+    CountMismatch { counted: usize, len: usize },
+    // The node at `index` is in the list and also waiting in the pool.
+    LinkedNodeInPool { index: usize },
+    PooledNodeLinked,
+    PooledTwice,
+    // The entry at `index` of a `SkipListMap` does not have a larger key than the one before it.
+    KeyOrder { index: usize },
+    // A lane of a `SkipListMap` is out of key order or skips to an entry missing from the lane
+    // below.
+    BrokenLane { lane: usize },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::HeadHasPrev => write!(f, "head has a prev link"),
+            ValidationError::TailHasNext => write!(f, "tail has a next link"),
+            ValidationError::BrokenLink { index } => {
+                write!(f, "node {} links to node {}, whose prev does not point back", index, index + 1)
+            }
+            ValidationError::TailNotLast { count } => {
+                write!(f, "tail is not the last of the {} nodes reachable from head", count)
+            }
+            ValidationError::CountMismatch { counted, len } => {
+                write!(f, "{} nodes reachable from head but the length is {}", counted, len)
+            }
+            ValidationError::LinkedNodeInPool { index } => write!(f, "node {} is in the list and in the pool", index),
+            ValidationError::PooledNodeLinked => write!(f, "a node in the pool still has links"),
+            ValidationError::PooledTwice => write!(f, "a node is in the pool twice"),
+            ValidationError::KeyOrder { index } => {
+                write!(f, "entry {} does not have a larger key than the entry before it", index)
+            }
+            ValidationError::BrokenLane { lane } => write!(f, "express lane {} does not match the entries", lane),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+pub mod node;
+pub mod node_pool;
+pub mod enums;
+pub mod linked_list;
+pub mod skip_list_map;
+pub mod singly_list;
+pub mod adaptors;
+pub mod snapshot;
+pub mod transaction;
+#[path = "../../bench/stats.rs"]
+pub mod stats;
+#[path = "../../bench/compare.rs"]
+pub mod compare;
+#[path = "../../bench/report.rs"]
+pub mod report;
+pub mod trace;
+#[path = "../../bench/workload.rs"]
+pub mod workload;
+#[path = "../../bench/metadata.rs"]
+pub mod metadata;
+pub mod fuzz;
+mod tests;
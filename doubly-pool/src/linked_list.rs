@@ -1,14 +1,22 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-use crate::enums::Side;
-use crate::node::Node;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashSet;
+use std::rc::{Rc, Weak};
+use crate::enums::{HandleError, Side, ValidationError};
+use crate::node::{Handle, Node, NodeRef, NodeRefMut};
 use crate::node_pool::NodePool;
+use crate::snapshot::{Snapshot, SnapshotState};
+use crate::transaction::Transaction;
+
+// The nodes on either side of a detached node.
+pub(crate) type Neighbours<T> = (Option<Rc<RefCell<Node<T>>>>, Option<Rc<RefCell<Node<T>>>>);
 
 #[derive(Debug)]
 pub struct DoublyLinkedList<T: Clone> {
     pub(crate) head: Option<Rc<RefCell<Node<T>>>>,
     pub(crate) tail: Option<Rc<RefCell<Node<T>>>>,
-    pool: NodePool<T>,
+    len: usize,
+    snapshots: RefCell<Vec<Weak<SnapshotState<T>>>>,
+    pub(crate) pool: NodePool<T>,
 }
 
 impl<T: Clone> DoublyLinkedList<T> {
@@ -16,15 +24,29 @@ impl<T: Clone> DoublyLinkedList<T> {
         DoublyLinkedList {
             head: None,
             tail: None,
+            len: 0,
+            snapshots: RefCell::new(Vec::new()),
             pool: NodePool::new(),
         }
     }
 
     pub fn init(&mut self, size: usize, default_value: T) {
         self.pool.populate(size, default_value);
+        self.debug_validate();
     }
 
-    pub fn insert(&mut self, side: Side, data: T, anchor: Rc<RefCell<Node<T>>>) {
+    pub fn insert(&mut self, side: Side, data: T, anchor: Handle<T>) -> Result<Handle<T>, HandleError> {
+        if anchor.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        return Ok(Handle::new(self.link(side, data, anchor.node)));
+    }
+
+    fn link(&mut self, side: Side, data: T, anchor: Rc<RefCell<Node<T>>>) -> Rc<RefCell<Node<T>>> {
+        match side {
+            Side::Before => self.touch_prev(&anchor),
+            Side::After => self.touch(&anchor),
+        }
         let new_node = self.pool.get_node(data);
 
         match side {
@@ -41,7 +63,7 @@ impl<T: Clone> DoublyLinkedList<T> {
                     self.head = Some(new_node.clone());
                 }
 
-                anchor_borrow.prev = Some(new_node);
+                anchor_borrow.prev = Some(new_node.clone());
             }
             Side::After => {
                 let mut anchor_borrow = anchor.borrow_mut();
@@ -56,65 +78,480 @@ impl<T: Clone> DoublyLinkedList<T> {
                     self.tail = Some(new_node.clone());
                 }
 
-                anchor_borrow.next = Some(new_node);
+                anchor_borrow.next = Some(new_node.clone());
             }
         }
+
+        self.len += 1;
+        self.debug_validate();
+        return new_node;
     }
 
-    pub fn push_first(&mut self, data: T) {
+    pub fn push_first(&mut self, data: T) -> Handle<T> {
         if let Some(head) = self.head.clone() {
-            self.insert(Side::Before, data, head);
+            return Handle::new(self.link(Side::Before, data, head));
         } else {
             let new_node = self.pool.get_node(data);
             self.head = Some(new_node.clone());
-            self.tail = Some(new_node);
+            self.tail = Some(new_node.clone());
+            self.len += 1;
+            self.debug_validate();
+            return Handle::new(new_node);
         }
     }
 
-    pub fn push_back(&mut self, data: T) {
+    pub fn push_back(&mut self, data: T) -> Handle<T> {
         if let Some(tail) = self.tail.clone() {
-            self.insert(Side::After, data, tail);
+            return Handle::new(self.link(Side::After, data, tail));
         } else {
             let new_node = self.pool.get_node(data);
             self.head = Some(new_node.clone());
-            self.tail = Some(new_node);
+            self.tail = Some(new_node.clone());
+            self.len += 1;
+            self.debug_validate();
+            return Handle::new(new_node);
+        }
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Result<T, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
         }
+        return Ok(self.unlink(handle.node));
     }
 
-    pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
-        let node_borrow = node.borrow();
-        let (_prev, next) = (node_borrow.prev.clone(), node_borrow.next.clone());
+    fn unlink(&mut self, node: Rc<RefCell<Node<T>>>) -> T {
+        // Clone first: if the payload's `Clone` panics, the list has not been touched yet.
+        let data: T = node.borrow().data.clone();
+        self.detach(&node);
+        self.pool.return_node(node);
+        self.debug_validate();
+        return data;
+    }
 
-        if let Some(prev) = _prev.clone() {
-            prev.borrow_mut().next = next.clone();
-        } else {
-            self.head = next.clone();
+    // Unlinks `node` and clears its own links, but leaves the node to the caller instead of
+    // the pool. Returns its former neighbours, which `reattach` takes to put it back.
+    pub(crate) fn detach(&mut self, node: &Rc<RefCell<Node<T>>>) -> Neighbours<T> {
+        self.touch_prev(node);
+        self.touch(node);
+        let (prev, next) = {
+            let mut node_borrow = node.borrow_mut();
+            (node_borrow.prev.take(), node_borrow.next.take())
+        };
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = next.clone(),
+            None => self.head = next.clone(),
         }
-        if let Some(next) = next {
-            next.borrow_mut().prev = _prev.clone();
-        } else {
-            self.tail = _prev.clone();
+        match &next {
+            Some(next) => next.borrow_mut().prev = prev.clone(),
+            None => self.tail = prev.clone(),
         }
+        self.len -= 1;
+        return (prev, next);
+    }
 
-        let data: T = node_borrow.data.clone();
+    // Links a detached node back in between `prev` and `next`, which must be adjacent, as they
+    // are right after `detach` returned them.
+    pub(crate) fn reattach(
+        &mut self,
+        node: &Rc<RefCell<Node<T>>>,
+        prev: Option<Rc<RefCell<Node<T>>>>,
+        next: Option<Rc<RefCell<Node<T>>>>,
+    ) {
+        if let Some(prev) = &prev {
+            self.touch(prev);
+        }
+        self.touch(node);
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = Some(node.clone()),
+            None => self.head = Some(node.clone()),
+        }
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(node.clone()),
+            None => self.tail = Some(node.clone()),
+        }
+        let mut node_borrow = node.borrow_mut();
+        node_borrow.prev = prev;
+        node_borrow.next = next;
         drop(node_borrow);
-        self.pool.return_node(node);
-        return Some(data);
+        self.len += 1;
+    }
+
+    // Borrow rules: `get` and `get_mut` borrow the payload of one node until the guard is
+    // dropped. Meanwhile every operation that has to relink that node panics: inserting next to
+    // it, removing it or a neighbour, or popping it. The `try_` variants return
+    // `HandleError::Borrowed` instead and leave the list unchanged. `get` panics if the node is
+    // mutably borrowed and `get_mut` if it is borrowed at all.
+    pub fn get<'a>(&self, handle: &'a Handle<T>) -> Result<Ref<'a, T>, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        return Ok(Ref::map(handle.node.borrow(), |node| &node.data));
+    }
+
+    pub fn get_mut<'a>(&mut self, handle: &'a Handle<T>) -> Result<RefMut<'a, T>, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        self.touch(&handle.node);
+        return Ok(RefMut::map(handle.node.borrow_mut(), |node| &mut node.data));
+    }
+
+    pub fn front(&self) -> Option<Ref<'_, T>> {
+        return self.head.as_ref().map(|node| Ref::map(node.borrow(), |node| &node.data));
+    }
+
+    pub fn back(&self) -> Option<Ref<'_, T>> {
+        return self.tail.as_ref().map(|node| Ref::map(node.borrow(), |node| &node.data));
+    }
+
+    pub fn front_mut(&mut self) -> Option<RefMut<'_, T>> {
+        if let Some(head) = self.head.clone() {
+            self.touch(&head);
+        }
+        return self.head.as_ref().map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.data));
+    }
+
+    pub fn back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        if let Some(tail) = self.tail.clone() {
+            self.touch(&tail);
+        }
+        return self.tail.as_ref().map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.data));
+    }
+
+    // Puts `data` in the element and returns what it held. The element stays where it is.
+    pub fn replace(&mut self, handle: &Handle<T>, data: T) -> Result<T, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        self.touch(&handle.node);
+        return Ok(std::mem::replace(&mut handle.node.borrow_mut().data, data));
+    }
+
+    // Exchanges the payloads of two elements without relinking either.
+    pub fn swap_data(&mut self, a: &Handle<T>, b: &Handle<T>) -> Result<(), HandleError> {
+        if a.is_stale() || b.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        if !Rc::ptr_eq(&a.node, &b.node) {
+            self.touch(&a.node);
+            self.touch(&b.node);
+            std::mem::swap(&mut a.node.borrow_mut().data, &mut b.node.borrow_mut().data);
+        }
+        return Ok(());
+    }
+
+    pub fn next_of(&self, handle: &Handle<T>) -> Result<Option<Handle<T>>, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        return Ok(handle.node.borrow().next.clone().map(Handle::new));
+    }
+
+    pub fn prev_of(&self, handle: &Handle<T>) -> Result<Option<Handle<T>>, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        return Ok(handle.node.borrow().prev.clone().map(Handle::new));
+    }
+
+    pub fn try_get<'a>(&self, handle: &'a Handle<T>) -> Result<Ref<'a, T>, HandleError> {
+        let node_borrow = handle.node.try_borrow().map_err(|_| HandleError::Borrowed)?;
+        if node_borrow.generation != handle.generation {
+            return Err(HandleError::Stale);
+        }
+        return Ok(Ref::map(node_borrow, |node| &node.data));
+    }
+
+    pub fn try_insert(&mut self, side: Side, data: T, anchor: Handle<T>) -> Result<Handle<T>, HandleError> {
+        let neighbour = {
+            let anchor_borrow = anchor.node.try_borrow_mut().map_err(|_| HandleError::Borrowed)?;
+            if anchor_borrow.generation != anchor.generation {
+                return Err(HandleError::Stale);
+            }
+            match side {
+                Side::Before => anchor_borrow.prev.clone(),
+                Side::After => anchor_borrow.next.clone(),
+            }
+        };
+        if let Some(neighbour) = &neighbour {
+            unborrowed(neighbour)?;
+        }
+        return self.insert(side, data, anchor);
+    }
+
+    pub fn try_remove(&mut self, handle: Handle<T>) -> Result<T, HandleError> {
+        let (prev, next) = {
+            let node_borrow = handle.node.try_borrow_mut().map_err(|_| HandleError::Borrowed)?;
+            if node_borrow.generation != handle.generation {
+                return Err(HandleError::Stale);
+            }
+            (node_borrow.prev.clone(), node_borrow.next.clone())
+        };
+        for neighbour in prev.iter().chain(next.iter()) {
+            unborrowed(neighbour)?;
+        }
+        return self.remove(handle);
     }
 
     pub fn pop_back(&mut self) -> Option<T> {
-        return self.tail.clone().map(|tail| self.remove(tail)).flatten();
+        return self.tail.clone().map(|tail| self.unlink(tail));
     }
 
     pub fn pop_first(&mut self) -> Option<T> {
-        return self.head.clone().map(|head| self.remove(head)).flatten();
+        return self.head.clone().map(|head| self.unlink(head));
+    }
+
+    // The node at `index`, walking from whichever end is closer.
+    fn node_at(&self, index: usize) -> Option<Rc<RefCell<Node<T>>>> {
+        if index >= self.len {
+            return None;
+        }
+        let mut current;
+        if index < self.len / 2 {
+            current = self.head.clone()?;
+            for _ in 0..index {
+                let next = current.borrow().next.clone()?;
+                current = next;
+            }
+        } else {
+            current = self.tail.clone()?;
+            for _ in index + 1..self.len {
+                let prev = current.borrow().prev.clone()?;
+                current = prev;
+            }
+        }
+        return Some(current);
+    }
+
+    pub fn handle_at(&self, index: usize) -> Option<Handle<T>> {
+        return self.node_at(index).map(Handle::new);
+    }
+
+    // The guards hold on to the node rather than borrow the list, so the list stays usable
+    // while they are alive. Like `get`/`get_mut`, they block relinking the node until dropped.
+    pub fn get_nth(&self, index: usize) -> Option<NodeRef<T>> {
+        return self.node_at(index).map(NodeRef::new);
+    }
+
+    pub fn get_nth_mut(&mut self, index: usize) -> Option<NodeRefMut<T>> {
+        let node = self.node_at(index)?;
+        self.touch(&node);
+        return Some(NodeRefMut::new(node));
+    }
+
+    // Inserts so that the new element ends up at `index`; `index == len` appends. Panics if
+    // `index > len`, like `Vec::insert`.
+    pub fn insert_at(&mut self, index: usize, data: T) -> Handle<T> {
+        assert!(index <= self.len, "insertion index {} is out of bounds for length {}", index, self.len);
+        return match self.node_at(index) {
+            Some(anchor) => Handle::new(self.link(Side::Before, data, anchor)),
+            None => self.push_back(data),
+        };
+    }
+
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let node = self.node_at(index)?;
+        return Some(self.unlink(node));
+    }
+
+    // Position of `node`, found by walking towards both ends at once and stopping at whichever
+    // is reached first. `handle` must belong to this list.
+    pub fn index_of(&self, handle: &Handle<T>) -> Result<usize, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        let (mut backward, mut forward) = (handle.node.clone(), handle.node.clone());
+        let mut steps = 0;
+        loop {
+            let prev = backward.borrow().prev.clone();
+            match prev {
+                Some(prev) => backward = prev,
+                None => return Ok(steps),
+            }
+            let next = forward.borrow().next.clone();
+            match next {
+                Some(next) => forward = next,
+                None => return Ok(self.len - 1 - steps),
+            }
+            steps += 1;
+        }
+    }
+
+    // A read-only view of the list as it is now. See `Snapshot` for what it costs the list.
+    pub fn snapshot(&self) -> Snapshot<T> {
+        let state = Rc::new(SnapshotState::new(&self.head, self.len));
+        self.snapshots.borrow_mut().push(Rc::downgrade(&state));
+        return Snapshot::new(state);
+    }
+
+    // Runs `edits` against the list and keeps them only if it returns `Ok`. On `Err`, or if it
+    // panics, every edit is undone in reverse order: removed elements are linked back in with
+    // their handles valid again, and inserted ones go back to the pool with their handles stale.
+    pub fn transaction<R, E>(&mut self, edits: impl FnOnce(&mut Transaction<'_, T>) -> Result<R, E>) -> Result<R, E> {
+        let mut transaction = Transaction::new(self);
+        let result = edits(&mut transaction);
+        if result.is_ok() {
+            transaction.commit();
+        }
+        return result;
+    }
+
+    // Lets every live snapshot copy `node` before it changes. Snapshots that were dropped are
+    // forgotten on the way.
+    fn touch(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let snapshots = self.snapshots.get_mut();
+        if snapshots.is_empty() {
+            return;
+        }
+        snapshots.retain(|state| state.strong_count() > 0);
+        for state in snapshots.iter().filter_map(Weak::upgrade) {
+            state.save(node);
+        }
+    }
+
+    // Snapshots only follow `next` links, so relinking copies just the node whose `next`
+    // changes: the one before the gap.
+    fn touch_prev(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        if self.snapshots.get_mut().is_empty() {
+            return;
+        }
+        let prev = node.borrow().prev.clone();
+        if let Some(prev) = prev {
+            self.touch(&prev);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    // Hands every node back to the pool, which makes all outstanding handles stale.
+    pub fn clear(&mut self) {
+        if !self.snapshots.get_mut().is_empty() {
+            let mut current = self.head.clone();
+            while let Some(node) = current {
+                self.touch(&node);
+                current = node.borrow().next.clone();
+            }
+        }
+        let mut current = self.head.take();
+        self.tail = None;
+        self.len = 0;
+        while let Some(node) = current {
+            current = node.borrow_mut().next.take();
+            self.pool.return_node(node);
+        }
+        self.debug_validate();
+    }
+
+    // Walks the list from head and reports the first broken invariant: links at either end,
+    // next/prev pairs that do not agree, and a length that does not match the count.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.head.as_ref().is_some_and(|head| head.borrow().prev.is_some()) {
+            return Err(ValidationError::HeadHasPrev);
+        }
+        if self.tail.as_ref().is_some_and(|tail| tail.borrow().next.is_some()) {
+            return Err(ValidationError::TailHasNext);
+        }
+
+        let mut pooled = HashSet::new();
+        for node in &self.pool.pool {
+            if !pooled.insert(Rc::as_ptr(node)) {
+                return Err(ValidationError::PooledTwice);
+            }
+        }
+        // With no prev link on head and every back link checked, the walk cannot reach a node
+        // twice, so this also rules out cycles.
+        let mut last: Option<Rc<RefCell<Node<T>>>> = None;
+        let mut current = self.head.clone();
+        let mut index = 0;
+        while let Some(node) = current {
+            if pooled.contains(&Rc::as_ptr(&node)) {
+                return Err(ValidationError::LinkedNodeInPool { index });
+            }
+            let next = node.borrow().next.clone();
+            if let Some(next) = &next {
+                let points_back = next.borrow().prev.as_ref().is_some_and(|prev| Rc::ptr_eq(prev, &node));
+                if !points_back {
+                    return Err(ValidationError::BrokenLink { index });
+                }
+            }
+            last = Some(node);
+            current = next;
+            index += 1;
+        }
+
+        let tail_is_last = match (&self.tail, &last) {
+            (None, None) => true,
+            (Some(tail), Some(last)) => Rc::ptr_eq(tail, last),
+            _ => false,
+        };
+        if !tail_is_last {
+            return Err(ValidationError::TailNotLast { count: index });
+        }
+        if index != self.len {
+            return Err(ValidationError::CountMismatch { counted: index, len: self.len });
+        }
+        for node in &self.pool.pool {
+            let borrow = node.borrow();
+            if borrow.prev.is_some() || borrow.next.is_some() {
+                return Err(ValidationError::PooledNodeLinked);
+            }
+        }
+        return Ok(());
+    }
+
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    pub(crate) fn debug_validate(&self) {
+        if let Err(error) = self.validate() {
+            panic!("list is corrupted: {}", error);
+        }
     }
+
+    #[cfg(not(any(debug_assertions, feature = "validate")))]
+    pub(crate) fn debug_validate(&self) {}
+}
+
+fn unborrowed<T>(node: &Rc<RefCell<Node<T>>>) -> Result<(), HandleError> {
+    return node.try_borrow_mut().map(|_| ()).map_err(|_| HandleError::Borrowed);
 }
 
 impl<T: Clone> Drop for DoublyLinkedList<T> {
     fn drop(&mut self) {
-        while self.tail.is_some() {
-            self.pop_back();
-        }
+        self.clear();
+    }
+}
+
+pub struct IntoIter<T: Clone>(DoublyLinkedList<T>);
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        return self.0.pop_first();
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.0.len(), Some(self.0.len()));
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        return self.0.pop_back();
+    }
+}
+
+impl<T: Clone> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        return IntoIter(self);
     }
 }
@@ -1,13 +1,10 @@
-mod node;
-mod node_pool;
-mod enums;
-mod linked_list;
-mod tests;
-
+use std::collections::BTreeMap;
 use std::time::SystemTime;
-use crate::enums::Side;
-use crate::linked_list::DoublyLinkedList;
+use doubly_pool::{compare, linked_list, metadata, report, skip_list_map, trace, workload};
 
+const ELEMENT_COUNT: usize = 10_000_000;
+const POOL_SIZE: usize = 10_000_000;
+const RUNS: usize = 10;
 
 #[derive(Debug, Clone, Copy)]
 struct Complex {
@@ -25,9 +22,9 @@ struct User {
 
 fn test_i32_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
-    custom_list.init(10_000_000, 0);
+    custom_list.init(POOL_SIZE, 0);
     let start_custom = SystemTime::now();
-    for i in 0..10_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(346);
     }
     let end_custom = SystemTime::now();
@@ -38,9 +35,9 @@ fn test_i32_doubly_linked_list() -> u128 {
 
 fn test_f64_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
-    custom_list.init(10_000_000, 0.0);
+    custom_list.init(POOL_SIZE, 0.0);
     let start_custom = SystemTime::now();
-    for i in 0..10_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(364.3433);
     }
     let end_custom = SystemTime::now();
@@ -51,12 +48,12 @@ fn test_f64_doubly_linked_list() -> u128 {
 
 fn test_complex_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
-    custom_list.init(10_000_000, Complex {
+    custom_list.init(POOL_SIZE, Complex {
         real: 0.0,
         imag: 0.0,
     });
     let start_custom = SystemTime::now();
-    for i in 0..10_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(Complex {
             real: 999.999,
             imag: 999.999,
@@ -70,14 +67,14 @@ fn test_complex_doubly_linked_list() -> u128 {
 
 fn test_user_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
-    custom_list.init(10_000_000, User {
+    custom_list.init(POOL_SIZE, User {
         id: 0,
         name: "".to_string(),
         age: 0,
         email: "".to_string(),
     });
     let start_custom = SystemTime::now();
-    for i in 0..10_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(User {
             id: 1,
             name: "John Doe".to_string(),
@@ -102,13 +99,13 @@ fn run_test() -> (u128, u128, u128, u128)
     return (i32_time, f64_time, complex_time, user_time);
 }
 
-fn main() {
+fn run_benchmark() {
         let mut i32_times = Vec::new();
     let mut f64_times = Vec::new();
     let mut complex_times = Vec::new();
     let mut user_times = Vec::new();
 
-    for i in 0..10 {
+    for i in 0..RUNS {
         let (i32_time, f64_time, complex_time, user_time) = run_test();
         i32_times.push(i32_time);
         f64_times.push(f64_time);
@@ -120,8 +117,402 @@ fn main() {
 
         let mut writer = csv::Writer::from_path("doubly_linked_list.csv").unwrap();
     writer.write_record(&["i32", "f64", "complex", "user"]).unwrap();
-    for i in 0..10 {
+    for i in 0..RUNS {
+        writer.write_record(&[i32_times[i].to_string(), f64_times[i].to_string(), complex_times[i].to_string(), user_times[i].to_string()]).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut metadata = metadata::Metadata::capture("benchmark");
+    metadata.set("element_count", ELEMENT_COUNT);
+    metadata.set("pool_size", POOL_SIZE);
+    metadata.set("runs", RUNS);
+    metadata.write_sidecar("doubly_linked_list.csv").unwrap();
+}
+
+fn time_replay<T: Clone>(ops: &[trace::Op], default_value: T, make: impl FnMut(u64) -> T) -> u128 {
+    let mut list = linked_list::DoublyLinkedList::new();
+    list.init(trace::peak_live(ops), default_value);
+    let start = SystemTime::now();
+    trace::replay(&mut list, ops, make);
+    let end = SystemTime::now();
+
+    return end.duration_since(start).unwrap().as_millis();
+}
+
+fn replay_usage() -> i32 {
+    eprintln!("usage: replay <trace> [--runs <n>] [--out <results.csv>]");
+    return 2;
+}
+
+// Replays a recorded trace once per payload type and run, writing timings in the same CSV
+// layout as the synthetic benchmark so `compare` and `report` can consume them.
+fn run_replay(args: &[String]) -> i32 {
+    let mut path = None;
+    let mut runs = 10;
+    let mut out = "replay.csv".to_string();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => match args.next().and_then(|v| v.parse::<usize>().ok()) {
+                Some(value) => runs = value,
+                None => return replay_usage(),
+            },
+            "--out" => match args.next() {
+                Some(value) => out = value.clone(),
+                None => return replay_usage(),
+            },
+            _ => path = Some(arg.clone()),
+        }
+    }
+    let Some(path) = path else {
+        return replay_usage();
+    };
+
+    let ops = match trace::load_trace(&path) {
+        Ok(ops) => ops,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 2;
+        }
+    };
+
+    let mut i32_times = Vec::new();
+    let mut f64_times = Vec::new();
+    let mut complex_times = Vec::new();
+    let mut user_times = Vec::new();
+
+    for i in 0..runs {
+        i32_times.push(time_replay(&ops, 0, |id| id as i32));
+        f64_times.push(time_replay(&ops, 0.0, |id| id as f64));
+        complex_times.push(time_replay(&ops, Complex { real: 0.0, imag: 0.0 }, |id| Complex {
+            real: id as f64,
+            imag: id as f64,
+        }));
+        user_times.push(time_replay(&ops, User {
+            id: 0,
+            name: "".to_string(),
+            age: 0,
+            email: "".to_string(),
+        }, |id| User {
+            id: id as i32,
+            name: "John Doe".to_string(),
+            age: 30,
+            email: "john@gmail.com".to_string(),
+        }));
+
+        println!("Replay {} done...", i + 1);
+    }
+
+    let mut writer = csv::Writer::from_path(&out).unwrap();
+    writer.write_record(["i32", "f64", "complex", "user"]).unwrap();
+    for i in 0..runs {
         writer.write_record(&[i32_times[i].to_string(), f64_times[i].to_string(), complex_times[i].to_string(), user_times[i].to_string()]).unwrap();
     }
     writer.flush().unwrap();
-}
\ No newline at end of file
+
+    let mut metadata = metadata::Metadata::capture("replay");
+    metadata.set("trace", &path);
+    metadata.set("ops", ops.len());
+    metadata.set("pool_size", trace::peak_live(&ops));
+    metadata.set("runs", runs);
+    metadata.write_sidecar(&out).unwrap();
+
+    return 0;
+}
+
+// Times one workload against this crate's list and the std baselines. Each structure is
+// dropped after its timer stops, as in the synthetic benchmark.
+fn time_workload<T: Clone>(workload: &workload::Workload, default_value: T, mut make: impl FnMut(u64) -> T) -> [u128; 3] {
+    let mut list = linked_list::DoublyLinkedList::new();
+    list.init(trace::peak_live(&workload.ops), default_value);
+    let start = SystemTime::now();
+    trace::replay(&mut list, &workload.ops, &mut make);
+    let list_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+
+    let start = SystemTime::now();
+    let deque = workload::run_vec_deque(workload, &mut make);
+    let deque_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+    drop(deque);
+
+    let start = SystemTime::now();
+    let std_list = workload::run_std_linked_list(workload, &mut make);
+    let std_list_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+    drop(std_list);
+
+    return [list_time, deque_time, std_list_time];
+}
+
+fn workload_usage() -> i32 {
+    eprintln!(concat!(
+        "usage: workload [--seed <n>] [--initial <n>] [--ops <n>] [--mix <op=weight,...>]\n",
+        "                [--anchors uniform|head|tail|hot-spot[:center[:width]]] [--runs <n>]\n",
+        "                [--out <results.csv>] [--save-trace <trace>]"
+    ));
+    return 2;
+}
+
+// Generates a seeded mixed workload and runs it against every backend. The same seed and
+// options produce the same workload in both crates, so their CSVs are directly comparable.
+fn run_workload(args: &[String]) -> i32 {
+    let mut config = workload::WorkloadConfig {
+        seed: 42,
+        initial: 10_000,
+        ops: 100_000,
+        mix: workload::OpMix::default(),
+        anchors: workload::AnchorDistribution::Uniform,
+    };
+    let mut runs = 10;
+    let mut out = "workload.csv".to_string();
+    let mut save_trace = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            return workload_usage();
+        };
+        let parsed = match arg.as_str() {
+            "--seed" => value.parse().map(|v| config.seed = v).map_err(|_| value.clone()),
+            "--initial" => value.parse().map(|v| config.initial = v).map_err(|_| value.clone()),
+            "--ops" => value.parse().map(|v| config.ops = v).map_err(|_| value.clone()),
+            "--runs" => value.parse().map(|v| runs = v).map_err(|_| value.clone()),
+            "--mix" => workload::OpMix::parse(value).map(|v| config.mix = v),
+            "--anchors" => workload::AnchorDistribution::parse(value).map(|v| config.anchors = v),
+            "--out" => {
+                out = value.clone();
+                Ok(())
+            }
+            "--save-trace" => {
+                save_trace = Some(value.clone());
+                Ok(())
+            }
+            _ => return workload_usage(),
+        };
+        if let Err(e) = parsed {
+            eprintln!("error: {}: {}", arg, e);
+            return workload_usage();
+        }
+    }
+
+    let workload = workload::generate(&config);
+    if let Some(path) = save_trace {
+        let written = std::fs::File::create(&path)
+            .and_then(|file| trace::write_trace(std::io::BufWriter::new(file), &workload.ops));
+        if let Err(e) = written {
+            eprintln!("error: {}: {}", path, e);
+            return 2;
+        }
+    }
+
+    let mut rows = Vec::new();
+    for i in 0..runs {
+        let times = [
+            time_workload(&workload, 0, |id| id as i32),
+            time_workload(&workload, 0.0, |id| id as f64),
+            time_workload(&workload, Complex { real: 0.0, imag: 0.0 }, |id| Complex {
+                real: id as f64,
+                imag: id as f64,
+            }),
+            time_workload(&workload, User {
+                id: 0,
+                name: "".to_string(),
+                age: 0,
+                email: "".to_string(),
+            }, |id| User {
+                id: id as i32,
+                name: "John Doe".to_string(),
+                age: 30,
+                email: "john@gmail.com".to_string(),
+            }),
+        ];
+        rows.push(times.concat());
+
+        println!("Workload {} done...", i + 1);
+    }
+
+    let mut writer = csv::Writer::from_path(&out).unwrap();
+    let mut header = Vec::new();
+    for payload in ["i32", "f64", "complex", "user"] {
+        for backend in ["list", "vec_deque", "std_linked_list"] {
+            header.push(format!("{}_{}", backend, payload));
+        }
+    }
+    writer.write_record(&header).unwrap();
+    for row in rows {
+        writer.write_record(row.iter().map(|t| t.to_string())).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut metadata = metadata::Metadata::capture("workload");
+    metadata.set("seed", config.seed);
+    metadata.set("initial", config.initial);
+    metadata.set("ops", config.ops);
+    metadata.set("mix", config.mix);
+    metadata.set("anchors", config.anchors);
+    metadata.set("pool_size", trace::peak_live(&workload.ops));
+    metadata.set("runs", runs);
+    metadata.write_sidecar(&out).unwrap();
+
+    return 0;
+}
+
+fn elapsed(start: SystemTime) -> u128 {
+    return SystemTime::now().duration_since(start).unwrap().as_millis();
+}
+
+// Inserts every key, looks each one up, sums the values of `ranges` key ranges and removes every
+// key again, timing each phase. Range starts are drawn from the inserted keys.
+fn time_skip_list_map(keys: &[u64], starts: &[u64], width: usize) -> [u128; 4] {
+    let mut map = skip_list_map::SkipListMap::new();
+    map.init(keys.len(), 0, 0);
+    let start = SystemTime::now();
+    for &key in keys {
+        map.insert(key, key);
+    }
+    let insert_time = elapsed(start);
+
+    let start = SystemTime::now();
+    let mut found = 0;
+    for key in keys {
+        found += map.contains_key(key) as usize;
+    }
+    let get_time = elapsed(start);
+    assert_eq!(found, map.len());
+
+    let start = SystemTime::now();
+    let mut sum = 0u64;
+    for key in starts {
+        sum = map.range(key..).take(width).fold(sum, |sum, entry| sum.wrapping_add(entry.1));
+    }
+    let range_time = elapsed(start);
+    std::hint::black_box(sum);
+
+    let start = SystemTime::now();
+    for key in keys {
+        map.remove(key);
+    }
+    let remove_time = elapsed(start);
+
+    return [insert_time, get_time, range_time, remove_time];
+}
+
+fn time_btree_map(keys: &[u64], starts: &[u64], width: usize) -> [u128; 4] {
+    let mut map = BTreeMap::new();
+    let start = SystemTime::now();
+    for &key in keys {
+        map.insert(key, key);
+    }
+    let insert_time = elapsed(start);
+
+    let start = SystemTime::now();
+    let mut found = 0;
+    for key in keys {
+        found += map.contains_key(key) as usize;
+    }
+    let get_time = elapsed(start);
+    assert_eq!(found, map.len());
+
+    let start = SystemTime::now();
+    let mut sum = 0u64;
+    for key in starts {
+        sum = map.range(key..).take(width).fold(sum, |sum, (_, value)| sum.wrapping_add(*value));
+    }
+    let range_time = elapsed(start);
+    std::hint::black_box(sum);
+
+    let start = SystemTime::now();
+    for key in keys {
+        map.remove(key);
+    }
+    let remove_time = elapsed(start);
+
+    return [insert_time, get_time, range_time, remove_time];
+}
+
+fn map_usage() -> i32 {
+    eprintln!("usage: map [--seed <n>] [--keys <n>] [--ranges <n>] [--width <n>] [--runs <n>] [--out <results.csv>]");
+    return 2;
+}
+
+// Times SkipListMap against BTreeMap on the same random keys, one column per map and phase.
+fn run_map(args: &[String]) -> i32 {
+    let mut seed = 42;
+    let mut key_count = 200_000;
+    let mut ranges = 10_000;
+    let mut width = 100;
+    let mut runs = 10;
+    let mut out = "map.csv".to_string();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            return map_usage();
+        };
+        let parsed = match arg.as_str() {
+            "--seed" => value.parse().map(|v| seed = v).is_ok(),
+            "--keys" => value.parse().map(|v| key_count = v).is_ok(),
+            "--ranges" => value.parse().map(|v| ranges = v).is_ok(),
+            "--width" => value.parse().map(|v| width = v).is_ok(),
+            "--runs" => value.parse().map(|v| runs = v).is_ok(),
+            "--out" => {
+                out = value.clone();
+                true
+            }
+            _ => return map_usage(),
+        };
+        if !parsed {
+            eprintln!("error: {}: {}", arg, value);
+            return map_usage();
+        }
+    }
+
+    let mut rng = workload::Rng::new(seed);
+    let keys: Vec<u64> = (0..key_count).map(|_| rng.next_u64()).collect();
+    let starts: Vec<u64> = match keys.len() {
+        0 => Vec::new(),
+        len => (0..ranges).map(|_| keys[rng.below(len)]).collect(),
+    };
+
+    let mut rows = Vec::new();
+    for i in 0..runs {
+        rows.push([time_skip_list_map(&keys, &starts, width), time_btree_map(&keys, &starts, width)].concat());
+
+        println!("Map {} done...", i + 1);
+    }
+
+    let mut writer = csv::Writer::from_path(&out).unwrap();
+    let mut header = Vec::new();
+    for backend in ["skip_list", "btree_map"] {
+        for phase in ["insert", "get", "range", "remove"] {
+            header.push(format!("{}_{}", backend, phase));
+        }
+    }
+    writer.write_record(&header).unwrap();
+    for row in rows {
+        writer.write_record(row.iter().map(|t| t.to_string())).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut metadata = metadata::Metadata::capture("map");
+    metadata.set("seed", seed);
+    metadata.set("keys", key_count);
+    metadata.set("ranges", ranges);
+    metadata.set("width", width);
+    metadata.set("runs", runs);
+    metadata.write_sidecar(&out).unwrap();
+
+    return 0;
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("compare") => std::process::exit(compare::run(&args[1..])),
+        Some("report") => std::process::exit(report::run(&args[1..])),
+        Some("replay") => std::process::exit(run_replay(&args[1..])),
+        Some("workload") => std::process::exit(run_workload(&args[1..])),
+        Some("map") => std::process::exit(run_map(&args[1..])),
+        _ => run_benchmark(),
+    }
+}
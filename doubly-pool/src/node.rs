@@ -1,4 +1,6 @@
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell, RefMut};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
 #[derive(Debug)]
@@ -6,6 +8,8 @@ pub struct Node<T> {
     pub(crate) data: T,
     pub(crate) prev: Option<Rc<RefCell<Node<T>>>>,
     pub(crate) next: Option<Rc<RefCell<Node<T>>>>,
+    // Bumped every time the node goes back to the pool, which invalidates its handles.
+    pub(crate) generation: u64,
 }
 
 impl<T> Node<T> {
@@ -14,6 +18,150 @@ impl<T> Node<T> {
             data,
             prev: None,
             next: None,
+            generation: 0,
         };
     }
-}
\ No newline at end of file
+}
+
+// Refers to one element of a list. Once the element is removed the handle is stale and the
+// list refuses it, even after the pool has reused the node for another element.
+#[derive(Debug)]
+pub struct Handle<T> {
+    pub(crate) node: Rc<RefCell<Node<T>>>,
+    pub(crate) generation: u64,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle {
+            node: self.node.clone(),
+            generation: self.generation,
+        }
+    }
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(node: Rc<RefCell<Node<T>>>) -> Self {
+        let generation = node.borrow().generation;
+        return Handle { node, generation };
+    }
+
+    pub fn is_stale(&self) -> bool {
+        return self.node.borrow().generation != self.generation;
+    }
+
+    // True when both handles refer to the same element, not just the same node.
+    pub fn ptr_eq(&self, other: &Handle<T>) -> bool {
+        return Rc::ptr_eq(&self.node, &other.node) && self.generation == other.generation;
+    }
+}
+
+// A borrow of part of a node's payload that owns the node, so it does not depend on the list:
+// relinking the node while the guard is alive panics, like it does during `get`.
+pub struct NodeRef<T, U: ?Sized = T> {
+    // Declared before `node` so the borrow ends before the node is released.
+    _borrow: Ref<'static, ()>,
+    data: *const U,
+    node: Rc<RefCell<Node<T>>>,
+}
+
+impl<T> NodeRef<T> {
+    pub(crate) fn new(node: Rc<RefCell<Node<T>>>) -> Self {
+        return NodeRef::map(node, |data| data);
+    }
+}
+
+impl<T, U: ?Sized> NodeRef<T, U> {
+    pub(crate) fn map(node: Rc<RefCell<Node<T>>>, part: impl FnOnce(&T) -> &U) -> Self {
+        let borrow = node.borrow();
+        let data: *const U = part(&borrow.data);
+        let borrow = Ref::map(borrow, |_| &());
+        // SAFETY: `node` keeps the cell alive and in place for as long as the guard exists, and
+        // `_borrow` is dropped first. Until then the cell counts as borrowed, so nothing can
+        // get `data` mutably or overwrite it.
+        let borrow = unsafe { std::mem::transmute::<Ref<'_, ()>, Ref<'static, ()>>(borrow) };
+        return NodeRef { _borrow: borrow, data, node };
+    }
+
+    pub fn handle(&self) -> Handle<T> {
+        return Handle::new(self.node.clone());
+    }
+}
+
+impl<T, U: ?Sized> Deref for NodeRef<T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        // SAFETY: see `NodeRef::map`.
+        return unsafe { &*self.data };
+    }
+}
+
+impl<T, U: ?Sized + fmt::Debug> fmt::Debug for NodeRef<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Debug::fmt(&**self, f);
+    }
+}
+
+// The mutable counterpart of `NodeRef`.
+pub struct NodeRefMut<T, U: ?Sized = T> {
+    // Declared before `node` so the borrow ends before the node is released.
+    _borrow: RefMut<'static, ()>,
+    data: *mut U,
+    node: Rc<RefCell<Node<T>>>,
+}
+
+impl<T> NodeRefMut<T> {
+    pub(crate) fn new(node: Rc<RefCell<Node<T>>>) -> Self {
+        return NodeRefMut::map(node, |data| data);
+    }
+}
+
+impl<T, U: ?Sized> NodeRefMut<T, U> {
+    pub(crate) fn map(node: Rc<RefCell<Node<T>>>, part: impl FnOnce(&mut T) -> &mut U) -> Self {
+        let mut borrow = node.borrow_mut();
+        let data: *mut U = part(&mut borrow.data);
+        let borrow = RefMut::map(borrow, |_| Box::leak(Box::new(())));
+        // SAFETY: as for `NodeRef::map`. The cell counts as mutably borrowed, so `data` is the
+        // only way to reach that part of the payload.
+        let borrow = unsafe { std::mem::transmute::<RefMut<'_, ()>, RefMut<'static, ()>>(borrow) };
+        return NodeRefMut { _borrow: borrow, data, node };
+    }
+
+    pub fn handle(&self) -> Handle<T> {
+        return Handle::new(self.node.clone());
+    }
+}
+
+impl<T, U: ?Sized> Deref for NodeRefMut<T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        // SAFETY: see `NodeRefMut::map`.
+        return unsafe { &*self.data };
+    }
+}
+
+impl<T, U: ?Sized> DerefMut for NodeRefMut<T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        // SAFETY: see `NodeRefMut::map`.
+        return unsafe { &mut *self.data };
+    }
+}
+
+impl<T, U: ?Sized + fmt::Debug> fmt::Debug for NodeRefMut<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Debug::fmt(&**self, f);
+    }
+}
+
+// Marks the end of a singly linked chain.
+pub(crate) const NIL: usize = usize::MAX;
+
+// A node of a `SinglyLinkedList`. Nodes live in their pool's arena and link by index, so each
+// one costs the payload plus a single word.
+#[derive(Debug)]
+pub struct SinglyNode<T> {
+    pub(crate) data: T,
+    pub(crate) next: usize,
+}
@@ -1,10 +1,10 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use crate::node::Node;
+use crate::node::{Node, SinglyNode, NIL};
 
 #[derive(Debug)]
 pub struct NodePool<T> {
-    pool: Vec<Rc<RefCell<Node<T>>>>,
+    pub(crate) pool: Vec<Rc<RefCell<Node<T>>>>,
 }
 
 impl<T: Clone> NodePool<T> {
@@ -12,13 +12,18 @@ impl<T: Clone> NodePool<T> {
         NodePool { pool: Vec::new() }
     }
 
+    // All or nothing: the nodes are built before any is added, so a panicking `Clone` leaves
+    // the pool as it was.
     pub fn populate(&mut self, size: usize, default_value: T) {
+        let mut nodes = Vec::with_capacity(size);
         for _ in 0..size {
-            self.pool
-                .push(Rc::new(RefCell::new(Node::new(default_value.clone()))));
+            nodes.push(Rc::new(RefCell::new(Node::new(default_value.clone()))));
         }
+        self.pool.append(&mut nodes);
     }
 
+    // Overwriting drops the node's previous payload. If that `Drop` panics the node is lost
+    // from the pool, but it is not linked anywhere yet.
     pub fn get_node(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
         if let Some(node) = self.pool.pop() {
             node.borrow_mut().data = data;
@@ -31,9 +36,58 @@ impl<T: Clone> NodePool<T> {
     }
 
     pub fn return_node(&mut self, node: Rc<RefCell<Node<T>>>) {
-        node.borrow_mut().next = None;
-        node.borrow_mut().prev = None;
+        let mut node_borrow = node.borrow_mut();
+        node_borrow.next = None;
+        node_borrow.prev = None;
+        node_borrow.generation = node_borrow.generation.wrapping_add(1);
+        drop(node_borrow);
         self.pool.push(node);
     }
 }
 
+// The arena behind a `SinglyLinkedList`. Slots of removed nodes wait in `free` to be reused,
+// the way `NodePool` keeps spare nodes.
+#[derive(Debug)]
+pub struct SinglyNodePool<T> {
+    pub(crate) nodes: Vec<SinglyNode<T>>,
+    pub(crate) free: Vec<usize>,
+}
+
+impl<T: Clone> SinglyNodePool<T> {
+    pub fn new() -> Self {
+        SinglyNodePool { nodes: Vec::new(), free: Vec::new() }
+    }
+
+    // All or nothing, like `NodePool::populate`.
+    pub fn populate(&mut self, size: usize, default_value: T) {
+        let mut nodes = Vec::with_capacity(size);
+        for _ in 0..size {
+            nodes.push(SinglyNode { data: default_value.clone(), next: NIL });
+        }
+        self.free.extend(self.nodes.len()..self.nodes.len() + size);
+        self.nodes.append(&mut nodes);
+    }
+
+    pub fn get_node(&mut self, data: T) -> usize {
+        if let Some(index) = self.free.pop() {
+            let node = &mut self.nodes[index];
+            node.data = data;
+            node.next = NIL;
+            index
+        } else {
+            self.nodes.push(SinglyNode { data, next: NIL });
+            self.nodes.len() - 1
+        }
+    }
+
+    pub fn return_node(&mut self, index: usize) {
+        self.nodes[index].next = NIL;
+        self.free.push(index);
+    }
+}
+
+impl<T: Clone> Default for SinglyNodePool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+use crate::enums::ValidationError;
+use crate::node::{SinglyNode, NIL};
+use crate::node_pool::SinglyNodePool;
+
+// A singly linked list for LIFO and FIFO use. Nodes carry no `prev` link and sit in an arena
+// that recycles removed slots, so a node is the payload plus one index. Only the front can be
+// popped; `pop_back` would need a walk from the head.
+#[derive(Debug)]
+pub struct SinglyLinkedList<T: Clone> {
+    head: usize,
+    tail: usize,
+    len: usize,
+    pub(crate) pool: SinglyNodePool<T>,
+}
+
+impl<T: Clone> SinglyLinkedList<T> {
+    pub fn new() -> Self {
+        return SinglyLinkedList { head: NIL, tail: NIL, len: 0, pool: SinglyNodePool::new() };
+    }
+
+    pub fn init(&mut self, size: usize, default_value: T) {
+        self.pool.populate(size, default_value);
+        self.debug_validate();
+    }
+
+    pub fn push_first(&mut self, data: T) {
+        let index = self.pool.get_node(data);
+        self.pool.nodes[index].next = self.head;
+        if self.head == NIL {
+            self.tail = index;
+        }
+        self.head = index;
+        self.len += 1;
+        self.debug_validate();
+    }
+
+    pub fn push_back(&mut self, data: T) {
+        let index = self.pool.get_node(data);
+        if self.tail == NIL {
+            self.head = index;
+        } else {
+            self.pool.nodes[self.tail].next = index;
+        }
+        self.tail = index;
+        self.len += 1;
+        self.debug_validate();
+    }
+
+    // Clones the payload before unlinking, like the pops of `DoublyLinkedList`, so a panicking
+    // `Clone` leaves the list unchanged.
+    pub fn pop_first(&mut self) -> Option<T> {
+        if self.head == NIL {
+            return None;
+        }
+        let index = self.head;
+        let data = self.pool.nodes[index].data.clone();
+        self.head = self.pool.nodes[index].next;
+        if self.head == NIL {
+            self.tail = NIL;
+        }
+        self.pool.return_node(index);
+        self.len -= 1;
+        self.debug_validate();
+        return Some(data);
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        return self.node(self.head).map(|node| &node.data);
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        return self.node(self.tail).map(|node| &node.data);
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        if self.head == NIL {
+            return None;
+        }
+        return Some(&mut self.pool.nodes[self.head].data);
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        return Iter { list: self, next: self.head };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    // Hands every node back to the pool.
+    pub fn clear(&mut self) {
+        let mut current = self.head;
+        while current != NIL {
+            let next = self.pool.nodes[current].next;
+            self.pool.return_node(current);
+            current = next;
+        }
+        self.head = NIL;
+        self.tail = NIL;
+        self.len = 0;
+        self.debug_validate();
+    }
+
+    // Walks the list from head and reports the first broken invariant: a link out of the arena,
+    // a node that is linked and pooled, a tail that is not the last node and a length that does
+    // not match the count.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut pooled = HashSet::new();
+        for &index in &self.pool.free {
+            if !pooled.insert(index) {
+                return Err(ValidationError::PooledTwice);
+            }
+            if self.pool.nodes[index].next != NIL {
+                return Err(ValidationError::PooledNodeLinked);
+            }
+        }
+        // Every slot is either linked or pooled, so a walk longer than the linked slots has
+        // looped.
+        let linked = self.pool.nodes.len() - pooled.len();
+        let mut last = NIL;
+        let mut current = self.head;
+        let mut index: usize = 0;
+        while current != NIL {
+            if current >= self.pool.nodes.len() {
+                return Err(ValidationError::BrokenLink { index: index.saturating_sub(1) });
+            }
+            if pooled.contains(&current) {
+                return Err(ValidationError::LinkedNodeInPool { index });
+            }
+            if index == linked {
+                return Err(ValidationError::CountMismatch { counted: index + 1, len: self.len });
+            }
+            last = current;
+            current = self.pool.nodes[current].next;
+            index += 1;
+        }
+        if self.tail != last {
+            return Err(ValidationError::TailNotLast { count: index });
+        }
+        if index != self.len {
+            return Err(ValidationError::CountMismatch { counted: index, len: self.len });
+        }
+        return Ok(());
+    }
+
+    fn node(&self, index: usize) -> Option<&SinglyNode<T>> {
+        if index == NIL {
+            return None;
+        }
+        return Some(&self.pool.nodes[index]);
+    }
+
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    fn debug_validate(&self) {
+        if let Err(error) = self.validate() {
+            panic!("list is corrupted: {}", error);
+        }
+    }
+
+    #[cfg(not(any(debug_assertions, feature = "validate")))]
+    fn debug_validate(&self) {}
+}
+
+impl<T: Clone> Default for SinglyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a, T: Clone> {
+    list: &'a SinglyLinkedList<T>,
+    next: usize,
+}
+
+impl<'a, T: Clone> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.list.node(self.next)?;
+        self.next = node.next;
+        return Some(&node.data);
+    }
+}
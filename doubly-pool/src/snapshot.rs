@@ -0,0 +1,111 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use crate::node::Node;
+
+type NodeLink<T> = Option<Weak<RefCell<Node<T>>>>;
+
+// A node as it was when the snapshot was taken, saved just before the list first changed it.
+struct Saved<T> {
+    data: T,
+    next: NodeLink<T>,
+}
+
+// Shared by a `Snapshot` and the list it was taken from. The list only holds a `Weak` to it, so
+// dropping every `Snapshot` stops the copying.
+pub(crate) struct SnapshotState<T> {
+    head: NodeLink<T>,
+    len: usize,
+    saved: RefCell<HashMap<*const RefCell<Node<T>>, Saved<T>>>,
+}
+
+impl<T: Clone> SnapshotState<T> {
+    pub(crate) fn new(head: &Option<Rc<RefCell<Node<T>>>>, len: usize) -> Self {
+        return SnapshotState {
+            head: head.as_ref().map(Rc::downgrade),
+            len,
+            saved: RefCell::new(HashMap::new()),
+        };
+    }
+
+    // Copies `node` unless an earlier change already did. Must be called before the list
+    // changes the node's payload or `next` link, or returns it to the pool. A pooled node keeps
+    // its address, so once copied it is read from the copy even after it has been reused.
+    pub(crate) fn save(&self, node: &Rc<RefCell<Node<T>>>) {
+        let mut saved = self.saved.borrow_mut();
+        saved.entry(Rc::as_ptr(node)).or_insert_with(|| {
+            let borrow = node.borrow();
+            Saved { data: borrow.data.clone(), next: borrow.next.as_ref().map(Rc::downgrade) }
+        });
+    }
+}
+
+// A read-only view of a `DoublyLinkedList` as it was when `snapshot` was called.
+//
+// Taking one is O(1). Afterwards the list copies each node the first time it changes or pools
+// it, so the snapshot costs memory only for the nodes touched since. Untouched nodes are read
+// from the list itself. Links are `Weak`, so a snapshot never keeps a node out of the pool.
+pub struct Snapshot<T> {
+    state: Rc<SnapshotState<T>>,
+}
+
+impl<T: Clone> Snapshot<T> {
+    pub(crate) fn new(state: Rc<SnapshotState<T>>) -> Self {
+        return Snapshot { state };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.state.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.state.len == 0;
+    }
+
+    // Nodes the list has copied for this snapshot so far.
+    pub fn copied(&self) -> usize {
+        return self.state.saved.borrow().len();
+    }
+
+    // Yields clones of the elements one at a time. The list may be changed between steps.
+    pub fn iter(&self) -> Iter<'_, T> {
+        return Iter { state: &self.state, next: self.state.head.clone(), remaining: self.state.len };
+    }
+}
+
+impl<T> Clone for Snapshot<T> {
+    fn clone(&self) -> Self {
+        return Snapshot { state: self.state.clone() };
+    }
+}
+
+pub struct Iter<'a, T> {
+    state: &'a SnapshotState<T>,
+    next: NodeLink<T>,
+    remaining: usize,
+}
+
+impl<'a, T: Clone> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let link = self.next.take()?;
+        self.remaining -= 1;
+        if let Some(saved) = self.state.saved.borrow().get(&link.as_ptr()) {
+            self.next = saved.next.clone();
+            return Some(saved.data.clone());
+        }
+        // A node that was never copied has not changed and is still in the list.
+        let node = link.upgrade().expect("unchanged nodes are still linked");
+        let borrow = node.borrow();
+        self.next = borrow.next.as_ref().map(Rc::downgrade);
+        return Some(borrow.data.clone());
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.remaining, Some(self.remaining));
+    }
+}
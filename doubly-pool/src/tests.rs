@@ -1,349 +1,1584 @@
-use crate::enums::Side;
-use crate::linked_list::DoublyLinkedList;
-
 #[cfg(test)]
-mod i32_tests {
-    use super::*;
+mod node_pool_tests {
+    use std::rc::Rc;
+    use crate::enums::{HandleError, Side};
+    use crate::linked_list::DoublyLinkedList;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    impl Person {
+        fn new(name: &str, age: u32) -> Self {
+            Person {
+                name: name.to_string(),
+                age,
+            }
+        }
+    }
 
     #[test]
-    fn test_push_and_pop_back() {
-        let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
-        assert!(list.pop_back().is_none());
+    fn test_node_pool_efficiency() {
+        let mut list = DoublyLinkedList::<i32>::new();
+        list.init(3, 0);
 
         list.push_back(1);
         list.push_back(2);
         list.push_back(3);
+        list.push_back(4);
 
-        assert_eq!(list.pop_back().unwrap(), 3);
+        list.pop_back();
+        list.pop_back();
+
+        list.push_back(5);
+        list.push_back(6);
+
+        assert_eq!(list.pop_back().unwrap(), 6);
+        assert_eq!(list.pop_back().unwrap(), 5);
         assert_eq!(list.pop_back().unwrap(), 2);
         assert_eq!(list.pop_back().unwrap(), 1);
         assert!(list.pop_back().is_none());
     }
 
     #[test]
-    fn test_push_and_pop_first() {
-        let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
-        assert!(list.pop_first().is_none());
+    fn test_node_pool_efficiency_with_complex_struct() {
+        let mut list = DoublyLinkedList::<Person>::new();
+        list.init(3, Person::new("Default", 0));
 
-        list.push_first(1);
-        list.push_first(2);
-        list.push_first(3);
+        list.push_back(Person::new("Alice", 30));
+        list.push_back(Person::new("Bob", 25));
+        list.push_back(Person::new("Charlie", 35));
+        list.push_back(Person::new("Diana", 40));
+
+        list.pop_back();
+        list.pop_back();
 
-        assert_eq!(list.pop_first().unwrap(), 3);
-        assert_eq!(list.pop_first().unwrap(), 2);
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert!(list.pop_first().is_none());
+        list.push_back(Person::new("Eve", 45));
+        list.push_back(Person::new("Frank", 50));
+
+        assert_eq!(list.pop_back().unwrap(), Person::new("Frank", 50));
+        assert_eq!(list.pop_back().unwrap(), Person::new("Eve", 45));
+        assert_eq!(list.pop_back().unwrap(), Person::new("Bob", 25));
+        assert_eq!(list.pop_back().unwrap(), Person::new("Alice", 30));
+        assert!(list.pop_back().is_none());
     }
 
     #[test]
-    fn test_insert_nodes() {
+    fn test_reused_node_refuses_stale_handle() {
+        let mut list = DoublyLinkedList::<i32>::new();
+        list.init(1, 0);
+        let removed = list.push_back(1);
+        assert_eq!(list.remove(removed.clone()), Ok(1));
+        assert!(removed.is_stale());
+
+        // The pool hands the same node out again for an unrelated element.
+        let reused = list.push_back(2);
+        assert!(Rc::ptr_eq(&removed.node, &reused.node));
+        assert!(!removed.ptr_eq(&reused));
+
+        assert_eq!(list.insert(Side::Before, 3, removed.clone()).err(), Some(HandleError::Stale));
+        assert_eq!(list.remove(removed), Err(HandleError::Stale));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.remove(reused), Ok(2));
+    }
+
+    #[test]
+    fn test_popped_handle_is_stale() {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        let first = list.push_back("first".to_string());
+        let second = list.push_back("second".to_string());
+        assert_eq!(list.pop_first(), Some("first".to_string()));
+        assert!(first.is_stale());
+        assert!(!second.is_stale());
+        assert_eq!(list.remove(first), Err(HandleError::Stale));
+        assert_eq!(list.remove(second), Ok("second".to_string()));
+    }
+}
 
-        list.push_back(1);
-        let anchor = list.head.clone().unwrap();
+#[cfg(test)]
+mod conformance_tests {
+    use crate::linked_list::DoublyLinkedList;
+    use crate::node::Handle;
+
+    include!("../../conformance/suite.rs");
+
+    impl<T: Clone> ListBackend<T> for DoublyLinkedList<T> {
+        type Handle = Handle<T>;
+
+        fn create() -> Self {
+            DoublyLinkedList::new()
+        }
+
+        fn push_first(&mut self, data: T) -> Self::Handle {
+            DoublyLinkedList::push_first(self, data)
+        }
 
-        list.insert(Side::After, 2, anchor.clone());
-        list.insert(Side::Before, 0, anchor);
+        fn push_back(&mut self, data: T) -> Self::Handle {
+            DoublyLinkedList::push_back(self, data)
+        }
+
+        fn pop_first(&mut self) -> Option<T> {
+            DoublyLinkedList::pop_first(self)
+        }
+
+        fn pop_back(&mut self) -> Option<T> {
+            DoublyLinkedList::pop_back(self)
+        }
+
+        fn insert(&mut self, side: Side, data: T, anchor: &Self::Handle) -> Self::Handle {
+            DoublyLinkedList::insert(self, side, data, anchor.clone()).expect("anchor is stale")
+        }
 
-        assert_eq!(list.pop_first().unwrap(), 0);
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert_eq!(list.pop_first().unwrap(), 2);
-        assert!(list.pop_first().is_none());
+        fn remove(&mut self, node: Self::Handle) -> Option<T> {
+            DoublyLinkedList::remove(self, node).ok()
+        }
+
+        fn head(&self) -> Option<Self::Handle> {
+            self.head.clone().map(Handle::new)
+        }
+
+        fn tail(&self) -> Option<Self::Handle> {
+            self.tail.clone().map(Handle::new)
+        }
+
+        fn next(&self, node: &Self::Handle) -> Option<Self::Handle> {
+            self.next_of(node).expect("handle is stale")
+        }
+
+        fn prev(&self, node: &Self::Handle) -> Option<Self::Handle> {
+            self.prev_of(node).expect("handle is stale")
+        }
+
+        fn value(&self, node: &Self::Handle) -> T {
+            self.get(node).expect("handle is stale").clone()
+        }
+
+        fn same(a: &Self::Handle, b: &Self::Handle) -> bool {
+            a.ptr_eq(b)
+        }
     }
 
-    #[test]
-    fn test_remove_nodes() {
-        let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+    conformance_suite!(DoublyLinkedList);
+}
 
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
+#[cfg(test)]
+mod compare_tests {
+    use crate::compare::{compare_column, Verdict};
+    use crate::stats::welch_t_test;
 
-        let node_to_remove = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
-        list.remove(node_to_remove);
+    #[test]
+    fn test_welch_t_test_known_value() {
+        let result = welch_t_test(&[1.0, 2.0, 3.0, 4.0, 5.0], &[3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert!((result.t - 2.0).abs() < 1e-9);
+        assert!((result.df - 8.0).abs() < 1e-9);
+        assert!((result.p_value - 0.0805).abs() < 1e-3);
+    }
 
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert_eq!(list.pop_first().unwrap(), 3);
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_identical_samples_are_no_change() {
+        let samples = [100.0, 102.0, 98.0, 101.0, 99.0];
+        let result = compare_column("i32", &samples, &samples, 5.0, 0.05);
+        assert_eq!(result.verdict, Verdict::NoChange);
+        assert!((result.p_value - 1.0).abs() < 1e-9);
     }
 
     #[test]
-    fn test_node_pool_efficiency() {
-        let mut list = DoublyLinkedList::<i32>::new();
-        list.init(3, 0);
+    fn test_regression_beyond_threshold() {
+        let baseline = [100.0, 102.0, 98.0, 101.0, 99.0];
+        let candidate = [120.0, 122.0, 118.0, 121.0, 119.0];
+        let result = compare_column("i32", &baseline, &candidate, 5.0, 0.05);
+        assert_eq!(result.verdict, Verdict::Regression);
+        assert!((result.change_percent - 20.0).abs() < 1e-9);
+
+        let result = compare_column("i32", &baseline, &candidate, 25.0, 0.05);
+        assert_eq!(result.verdict, Verdict::Slower);
+    }
 
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
-        list.push_back(4);
+    #[test]
+    fn test_speedup_is_not_a_regression() {
+        let baseline = [120.0, 122.0, 118.0, 121.0, 119.0];
+        let candidate = [100.0, 102.0, 98.0, 101.0, 99.0];
+        let result = compare_column("user", &baseline, &candidate, 5.0, 0.05);
+        assert_eq!(result.verdict, Verdict::Faster);
+    }
+}
 
-        list.pop_back();
-        list.pop_back();
+#[cfg(test)]
+mod report_tests {
+    use crate::compare::Samples;
+    use crate::report::{render, BoxStats, Series};
+
+    fn series(label: &str, i32_times: Vec<f64>) -> Series {
+        Series {
+            label: label.to_string(),
+            samples: Samples {
+                columns: vec!["i32".to_string()],
+                values: vec![i32_times],
+            },
+            metadata: None,
+        }
+    }
 
-        list.push_back(5);
-        list.push_back(6);
+    #[test]
+    fn test_box_stats_quartiles() {
+        let stats = BoxStats::of(&[5.0, 1.0, 4.0, 2.0, 3.0]);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.q1, 2.0);
+        assert_eq!(stats.median, 3.0);
+        assert_eq!(stats.q3, 4.0);
+        assert_eq!(stats.max, 5.0);
+    }
 
-        assert_eq!(list.pop_back().unwrap(), 6);
-        assert_eq!(list.pop_back().unwrap(), 5);
-        assert_eq!(list.pop_back().unwrap(), 2);
-        assert_eq!(list.pop_back().unwrap(), 1);
-        assert!(list.pop_back().is_none());
+    #[test]
+    fn test_render_plain_versus_pooled() {
+        let html = render(&[
+            series("plain", vec![100.0, 110.0, 105.0]),
+            series("<pooled>", vec![50.0, 55.0, 60.0]),
+        ]);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html.matches("<svg").count(), 2);
+        assert!(html.contains("<polyline"));
+        assert!(html.contains("&lt;pooled&gt;"));
+        assert!(!html.contains("<pooled>"));
+        assert!(html.contains("-47.6%"));
     }
 }
 
 #[cfg(test)]
-mod complex_struct_tests {
-    use super::*;
-    use super::*;
+mod trace_tests {
+    use crate::enums::Side;
+    use crate::linked_list::DoublyLinkedList;
+    use crate::trace::{peak_live, read_trace, replay, write_trace, Op, TraceRecorder};
+
+    fn contents(list: &DoublyLinkedList<u64>) -> Vec<u64> {
+        let mut values = Vec::new();
+        let mut current = list.head.clone();
+        while let Some(node) = current {
+            values.push(node.borrow().data);
+            current = node.borrow().next.clone();
+        }
+        values
+    }
 
-    #[derive(Clone, Debug, PartialEq)]
-    struct Person {
-        name: String,
-        age: u32,
+    #[test]
+    fn test_record_and_replay() {
+        let mut recorder = TraceRecorder::new(Vec::new());
+        let first = recorder.push_back(0);
+        let second = recorder.push_back(1);
+        let third = recorder.insert(Side::Before, 2, second.clone()).unwrap();
+        recorder.push_first(3);
+        recorder.remove(third).unwrap();
+        recorder.insert(Side::After, 5, first).unwrap();
+        drop(second);
+        recorder.pop_back();
+        recorder.pop_first();
+
+        let (list, out) = recorder.finish().unwrap();
+        let ops = read_trace(out.as_slice()).unwrap();
+        assert_eq!(
+            ops,
+            vec![
+                Op::PushBack(0),
+                Op::PushBack(1),
+                Op::Insert(Side::Before, 1, 2),
+                Op::PushFirst(3),
+                Op::Remove(2),
+                Op::Insert(Side::After, 0, 4),
+                Op::PopBack(Some(1)),
+                Op::PopFirst(Some(3)),
+            ]
+        );
+
+        let mut replayed = DoublyLinkedList::new();
+        replayed.init(peak_live(&ops), 0);
+        replay(&mut replayed, &ops, |id| id);
+        assert_eq!(contents(&replayed), vec![0, 4]);
+        assert_eq!(contents(&list), vec![0, 5]);
     }
 
-    impl Person {
-        fn new(name: &str, age: u32) -> Self {
-            Person {
-                name: name.to_string(),
-                age,
-            }
+    #[test]
+    fn test_write_and_read_round_trip() {
+        let ops = vec![
+            Op::PushFirst(0),
+            Op::Insert(Side::After, 0, 1),
+            Op::PopBack(Some(1)),
+            Op::PopFirst(Some(0)),
+            Op::PopFirst(None),
+        ];
+        let mut out = Vec::new();
+        write_trace(&mut out, &ops).unwrap();
+        assert_eq!(read_trace(out.as_slice()).unwrap(), ops);
+    }
+
+    #[test]
+    fn test_rejects_invalid_traces() {
+        let error = read_trace("push_back 0\ninsert after 3 1\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let error = read_trace("push_back 1\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 1);
+
+        let error = read_trace("push_back 0\nremove 0\nremove 0\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 3);
+
+        let error = read_trace("# comment\n\nshuffle 0\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+}
+
+#[cfg(test)]
+mod workload_tests {
+    use crate::linked_list::DoublyLinkedList;
+    use crate::trace::{read_trace, replay, write_trace};
+    use crate::workload::{generate, run_std_linked_list, run_vec_deque, AnchorDistribution, OpMix, Rng, WorkloadConfig};
+
+    fn config(seed: u64, anchors: AnchorDistribution) -> WorkloadConfig {
+        WorkloadConfig {
+            seed,
+            initial: 100,
+            ops: 2_000,
+            mix: OpMix::default(),
+            anchors,
         }
     }
 
     #[test]
-    fn test_complex_push_and_pop() {
-        let mut list = DoublyLinkedList::new();
-        list.init(5, Person::new("Default", 0));
+    fn test_same_seed_same_workload() {
+        let a = generate(&config(7, AnchorDistribution::Uniform));
+        let b = generate(&config(7, AnchorDistribution::Uniform));
+        let c = generate(&config(8, AnchorDistribution::Uniform));
+        assert_eq!(a.ops, b.ops);
+        assert_eq!(a.positions, b.positions);
+        assert_ne!(a.ops, c.ops);
+    }
 
-        list.push_back(Person::new("Alice", 30));
-        list.push_back(Person::new("Bob", 25));
-        list.push_back(Person::new("Charlie", 35));
+    #[test]
+    fn test_workload_is_a_valid_trace() {
+        let workload = generate(&config(1, AnchorDistribution::HotSpot { center: 0.2, width: 0.1 }));
+        let mut out = Vec::new();
+        write_trace(&mut out, &workload.ops).unwrap();
+        assert_eq!(read_trace(out.as_slice()).unwrap(), workload.ops);
+    }
 
-        assert_eq!(list.pop_back().unwrap(), Person::new("Charlie", 35));
-        assert_eq!(list.pop_back().unwrap(), Person::new("Bob", 25));
-        assert_eq!(list.pop_back().unwrap(), Person::new("Alice", 30));
-        assert!(list.pop_back().is_none());
+    #[test]
+    fn test_backends_agree() {
+        for anchors in [
+            AnchorDistribution::Uniform,
+            AnchorDistribution::HeadBiased,
+            AnchorDistribution::TailBiased,
+            AnchorDistribution::HotSpot { center: 0.5, width: 0.05 },
+        ] {
+            let workload = generate(&config(3, anchors));
+            let mut list = DoublyLinkedList::new();
+            replay(&mut list, &workload.ops, |id| id);
+
+            let mut values = Vec::new();
+            let mut current = list.head.clone();
+            while let Some(node) = current {
+                values.push(node.borrow().data);
+                current = node.borrow().next.clone();
+            }
+
+            let deque: Vec<u64> = run_vec_deque(&workload, |id| id).into_iter().collect();
+            let std_list: Vec<u64> = run_std_linked_list(&workload, |id| id).into_iter().collect();
+            assert_eq!(values, deque);
+            assert_eq!(values, std_list);
+        }
     }
 
     #[test]
-    fn test_complex_insert() {
-        let mut list = DoublyLinkedList::new();
-        list.init(5, Person::new("Default", 0));
+    fn test_anchor_bias() {
+        let mut rng = Rng::new(11);
+        let head: usize = (0..1_000).map(|_| AnchorDistribution::HeadBiased.pick(&mut rng, 1_000)).sum();
+        let tail: usize = (0..1_000).map(|_| AnchorDistribution::TailBiased.pick(&mut rng, 1_000)).sum();
+        assert!(head / 1_000 < 300);
+        assert!(tail / 1_000 > 700);
+
+        let hot = AnchorDistribution::HotSpot { center: 0.5, width: 0.1 };
+        assert!((0..1_000).map(|_| hot.pick(&mut rng, 1_000)).all(|p| (400..=600).contains(&p)));
+    }
 
-        list.push_back(Person::new("Alice", 30));
-        let anchor = list.head.clone().unwrap();
+    #[test]
+    fn test_parse_options() {
+        let mix = OpMix::parse("push_back=4,remove=1").unwrap();
+        assert_eq!(mix.push_back, 4);
+        assert_eq!(mix.remove, 1);
+        assert_eq!(mix.insert, 0);
+        assert!(OpMix::parse("push_back=0").is_err());
+        assert!(OpMix::parse("shuffle=1").is_err());
+
+        assert_eq!(
+            AnchorDistribution::parse("hot-spot:0.25").unwrap(),
+            AnchorDistribution::HotSpot { center: 0.25, width: 0.05 }
+        );
+        assert!(AnchorDistribution::parse("hot-spot:2").is_err());
+    }
+}
 
-        list.insert(Side::After, Person::new("Charlie", 35), anchor.clone());
-        list.insert(Side::Before, Person::new("Bob", 25), anchor);
+#[cfg(test)]
+mod metadata_tests {
+    use crate::compare::Samples;
+    use crate::metadata::Metadata;
+    use crate::report::{warnings, Series};
+
+    fn run(element_count: usize, host: &str) -> Metadata {
+        let mut metadata = Metadata::new();
+        metadata.set("mode", "benchmark");
+        metadata.set("profile", "release");
+        metadata.set("element_count", element_count);
+        metadata.set("hostname", host);
+        metadata.set("git_commit", "abc123");
+        metadata
+    }
 
-        assert_eq!(list.pop_first().unwrap(), Person::new("Bob", 25));
-        assert_eq!(list.pop_first().unwrap(), Person::new("Alice", 30));
-        assert_eq!(list.pop_first().unwrap(), Person::new("Charlie", 35));
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_json_round_trip() {
+        let mut metadata = run(20_000_000, "bench-01");
+        metadata.set("cpu", "Quirky \"CPU\" \\ model\twith\ncontrol \u{1}");
+        let parsed = Metadata::parse_json(&metadata.to_json()).unwrap();
+        assert_eq!(parsed, metadata);
+
+        assert_eq!(Metadata::parse_json(" { } ").unwrap(), Metadata::new());
+        assert!(Metadata::parse_json("{\"a\": \"b\",}").is_err());
+        assert!(Metadata::parse_json("{\"a\": 1}").is_err());
+        assert!(Metadata::parse_json("{\"a\": \"b\"} trailing").is_err());
     }
 
     #[test]
-    fn test_complex_remove() {
-        let mut list = DoublyLinkedList::new();
-        list.init(5, Person::new("Default", 0));
+    fn test_sidecar_path() {
+        assert_eq!(Metadata::sidecar_path("out/doubly_linked_list.csv"), "out/doubly_linked_list.meta.json");
+        assert_eq!(Metadata::sidecar_path("results"), "results.meta.json");
+    }
 
-        list.push_back(Person::new("Alice", 30));
-        list.push_back(Person::new("Bob", 25));
-        list.push_back(Person::new("Charlie", 35));
+    #[test]
+    fn test_mismatches() {
+        let baseline = run(20_000_000, "bench-01");
+        assert!(baseline.mismatches(&baseline).is_empty());
+
+        let mut candidate = run(10_000_000, "bench-02");
+        candidate.set("git_commit", "def456");
+        let mismatches = candidate.mismatches(&baseline);
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].key, "element_count");
+        assert!(!mismatches[0].config);
+        assert_eq!(mismatches[1].key, "hostname");
+        assert!(!mismatches[1].config);
+        assert_eq!(mismatches[0].describe(), "element_count: 10000000 vs 20000000");
+
+        candidate.set("mode", "replay");
+        let mismatches = candidate.mismatches(&baseline);
+        assert_eq!(mismatches.len(), 3);
+        assert_eq!(mismatches[0].key, "mode");
+        assert!(mismatches[0].config);
+    }
 
-        let node_to_remove = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
-        list.remove(node_to_remove);
+    #[test]
+    fn test_report_warnings() {
+        let series = |label: &str, metadata: Option<Metadata>| Series {
+            label: label.to_string(),
+            samples: Samples { columns: Vec::new(), values: Vec::new() },
+            metadata,
+        };
+
+        let same = [series("a", Some(run(10, "h"))), series("b", Some(run(10, "h")))];
+        assert!(warnings(&same).is_empty());
+
+        let different = [series("plain", Some(run(20, "h"))), series("pooled", Some(run(10, "h"))), series("old", None)];
+        let warnings = warnings(&different);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("old has no metadata"));
+        assert!(warnings[1].contains("element_count: 20 vs 10"));
+    }
+}
 
-        assert_eq!(list.pop_first().unwrap(), Person::new("Alice", 30));
-        assert_eq!(list.pop_first().unwrap(), Person::new("Charlie", 35));
-        assert!(list.pop_first().is_none());
+#[cfg(test)]
+mod fuzz_tests {
+    use crate::fuzz::{decode, run, Harness, Step, STEP_KINDS};
+    use crate::enums::Side;
+    use crate::workload::Rng;
+
+    fn program(seed: u64, steps: usize, kinds: u8) -> Vec<u8> {
+        let mut rng = Rng::new(seed);
+        let mut data = Vec::with_capacity(2 * steps);
+        for _ in 0..steps {
+            data.push(rng.below(kinds as usize) as u8);
+            data.push(rng.next_u64() as u8);
+        }
+        return data;
     }
 
     #[test]
-    fn test_node_pool_efficiency_with_complex_struct() {
-        let mut list = DoublyLinkedList::<Person>::new();
-        list.init(3, Person::new("Default", 0));
+    fn test_decode_pairs_of_bytes() {
+        assert_eq!(decode(&[]), vec![]);
+        assert_eq!(decode(&[1, 9, 4]), vec![Step::PushBack]);
+        assert_eq!(decode(&[4, 3, 5, 200]), vec![Step::Insert(Side::Before, 3), Step::Insert(Side::After, 200)]);
+        assert_eq!(decode(&[STEP_KINDS, 0]), vec![Step::PushFirst]);
+    }
 
-        list.push_back(Person::new("Alice", 30));
-        list.push_back(Person::new("Bob", 25));
-        list.push_back(Person::new("Charlie", 35));
-        list.push_back(Person::new("Diana", 40));
+    #[test]
+    fn test_harness_tracks_the_list() {
+        let mut harness = Harness::new();
+        for step in [Step::PushBack, Step::PushFirst, Step::Insert(Side::After, 0), Step::Remove(2), Step::PopBack] {
+            harness.apply(step);
+            harness.check().unwrap();
+        }
+    }
 
-        list.pop_back();
-        list.pop_back();
+    #[test]
+    fn test_random_programs() {
+        for seed in 0..300 {
+            run(&program(seed, 400, STEP_KINDS));
+        }
+    }
 
-        list.push_back(Person::new("Eve", 45));
-        list.push_back(Person::new("Frank", 50));
+    #[test]
+    fn test_program_with_recycling() {
+        // Reserve two nodes, push and remove so the nodes go round the pool, then push again.
+        run(&[8, 2, 1, 0, 1, 0, 6, 0, 3, 0, 0, 0, 1, 0, 4, 1, 7, 0, 1, 0]);
+    }
 
-        assert_eq!(list.pop_back().unwrap(), Person::new("Frank", 50));
-        assert_eq!(list.pop_back().unwrap(), Person::new("Eve", 45));
-        assert_eq!(list.pop_back().unwrap(), Person::new("Bob", 25));
-        assert_eq!(list.pop_back().unwrap(), Person::new("Alice", 30));
-        assert!(list.pop_back().is_none());
+    #[test]
+    fn test_stale_handle_program() {
+        // push_back, pop_back, then remove through the popped handle.
+        run(&[1, 0, 3, 0, 10, 0]);
+        // push_back, remove it, push_back again so the pool reuses the node, then insert next
+        // to the removed element and remove it again.
+        run(&[1, 0, 6, 0, 1, 0, 9, 0, 9, 1, 10, 0]);
     }
 }
 
 #[cfg(test)]
-mod insert_tests {
-    use super::*;
+mod validation_tests {
+    use std::mem::ManuallyDrop;
+    use crate::enums::{Side, ValidationError};
+    use crate::linked_list::DoublyLinkedList;
+
+    // Corrupted lists would trip the debug check again while dropping, so they are leaked.
+    fn three() -> ManuallyDrop<DoublyLinkedList<i32>> {
+        let mut list = ManuallyDrop::new(DoublyLinkedList::new());
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+        return list;
+    }
 
     #[test]
-    fn test_insert_after() {
+    fn test_validate_accepts_well_formed_lists() {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        assert_eq!(list.validate(), Ok(()));
+        let middle = list.push_back(2);
+        list.push_first(1);
+        list.insert(Side::After, 3, middle.clone()).unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.validate(), Ok(()));
+        list.remove(middle).unwrap();
+        list.pop_first();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.validate(), Ok(()));
+    }
 
-        list.push_back(1);
-        let anchor = list.head.clone().unwrap();
+    #[test]
+    fn test_validate_reports_head_with_prev() {
+        let list = three();
+        let tail = list.tail.clone();
+        list.head.as_ref().unwrap().borrow_mut().prev = tail;
+        assert_eq!(list.validate(), Err(ValidationError::HeadHasPrev));
+    }
+
+    #[test]
+    fn test_validate_reports_broken_link() {
+        let list = three();
+        let second = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
+        second.borrow_mut().prev = None;
+        assert_eq!(list.validate(), Err(ValidationError::BrokenLink { index: 0 }));
+        assert_eq!(
+            list.validate().unwrap_err().to_string(),
+            "node 0 links to node 1, whose prev does not point back"
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_tail_not_last() {
+        let list = three();
+        let second = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
+        second.borrow_mut().next = None;
+        assert_eq!(list.validate(), Err(ValidationError::TailNotLast { count: 2 }));
+    }
 
-        list.insert(Side::After, 2, anchor.clone());
-        list.insert(Side::After, 3, anchor.clone());
+    #[test]
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    #[should_panic(expected = "list is corrupted: head has a prev link")]
+    fn test_mutations_check_the_list_under_validation() {
+        let mut list = three();
+        let tail = list.tail.clone();
+        list.head.as_ref().unwrap().borrow_mut().prev = tail;
+        list.push_back(4);
+    }
 
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert_eq!(list.pop_first().unwrap(), 3);
-        assert_eq!(list.pop_first().unwrap(), 2);
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_validate_reports_linked_node_in_pool() {
+        let mut list = three();
+        let head = list.head.clone().unwrap();
+        list.pool.pool.push(head);
+        assert_eq!(list.validate(), Err(ValidationError::LinkedNodeInPool { index: 0 }));
     }
 
     #[test]
-    fn test_insert_before() {
+    fn test_validate_reports_pooled_node_with_links() {
+        let mut list = three();
+        list.init(1, 0);
+        let tail = list.tail.clone();
+        list.pool.pool[0].borrow_mut().next = tail;
+        assert_eq!(list.validate(), Err(ValidationError::PooledNodeLinked));
+    }
+}
+
+#[cfg(test)]
+mod drop_tests {
+    use std::cell::RefCell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+    use crate::enums::Side;
+    use crate::linked_list::DoublyLinkedList;
+
+    // Counts live instances per id. Every construction or clone adds one and every drop takes
+    // one away, so a count that goes negative is a double drop and one left over is a leak.
+    #[derive(Debug)]
+    struct Tracked {
+        id: usize,
+        live: Rc<RefCell<Vec<isize>>>,
+    }
+
+    impl Tracked {
+        fn new(id: usize, live: &Rc<RefCell<Vec<isize>>>) -> Self {
+            let mut counts = live.borrow_mut();
+            if counts.len() <= id {
+                counts.resize(id + 1, 0);
+            }
+            counts[id] += 1;
+            Tracked { id, live: live.clone() }
+        }
+    }
+
+    impl Clone for Tracked {
+        fn clone(&self) -> Self {
+            Tracked::new(self.id, &self.live)
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            let mut counts = self.live.borrow_mut();
+            counts[self.id] -= 1;
+            assert!(counts[self.id] >= 0, "payload {} dropped more often than created", self.id);
+        }
+    }
+
+    fn live(counts: &Rc<RefCell<Vec<isize>>>) -> isize {
+        counts.borrow().iter().sum()
+    }
+
+    fn filled(n: usize, counts: &Rc<RefCell<Vec<isize>>>) -> DoublyLinkedList<Tracked> {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        for id in 0..n {
+            if id % 3 == 0 {
+                list.push_first(Tracked::new(id, counts));
+            } else {
+                list.push_back(Tracked::new(id, counts));
+            }
+        }
+        list
+    }
 
-        list.push_back(1);
-        let anchor = list.head.clone().unwrap();
+    #[test]
+    fn test_dropping_the_list_drops_every_element_once() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = filled(50, &counts);
+        let anchor = list.push_back(Tracked::new(50, &counts));
+        list.insert(Side::Before, Tracked::new(51, &counts), anchor).unwrap();
+        assert_eq!(live(&counts), 52);
+        drop(list);
+        assert_eq!(live(&counts), 0);
+    }
 
-        list.insert(Side::Before, 2, anchor.clone());
-        list.insert(Side::Before, 3, anchor.clone());
+    #[test]
+    fn test_remove_returns_a_clone_and_the_pool_keeps_the_original() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = DoublyLinkedList::new();
+        let handle = list.push_back(Tracked::new(0, &counts));
+        let removed = list.remove(handle).unwrap();
+        // The pooled node holds on to its payload until it is reused or the list is dropped.
+        assert_eq!(counts.borrow()[0], 2);
+        drop(removed);
+        assert_eq!(counts.borrow()[0], 1);
+        list.push_back(Tracked::new(1, &counts));
+        assert_eq!(counts.borrow()[0], 0);
+        drop(list);
+        assert_eq!(live(&counts), 0);
+    }
 
-        assert_eq!(list.pop_first().unwrap(), 2);
-        assert_eq!(list.pop_first().unwrap(), 3);
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_pops_and_clear() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = filled(20, &counts);
+        drop(list.pop_first());
+        drop(list.pop_back());
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.validate(), Ok(()));
+        // Everything is parked in the pool until it is reused or the list goes away.
+        assert_eq!(live(&counts), 20);
+        for id in 20..40 {
+            list.push_back(Tracked::new(id, &counts));
+        }
+        assert_eq!(live(&counts), 20);
+        drop(list);
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_init_clones_the_default() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = DoublyLinkedList::new();
+        list.init(5, Tracked::new(0, &counts));
+        assert_eq!(live(&counts), 5);
+        drop(list);
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_panic_during_iteration_drops_the_rest() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let list = filled(10, &counts);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            for element in list {
+                if element.id == 4 {
+                    panic!("payload {}", element.id);
+                }
+            }
+        }));
+        assert!(result.is_err());
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_dropping_the_list_while_handles_are_alive() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = filled(4, &counts);
+        let held = list.push_back(Tracked::new(4, &counts));
+        list.push_back(Tracked::new(5, &counts));
+
+        drop(list);
+        assert_eq!(live(&counts), 1);
+        assert!(held.is_stale());
+        assert!(held.node.borrow().prev.is_none() && held.node.borrow().next.is_none());
+        drop(held);
+        assert_eq!(live(&counts), 0);
     }
 }
 
 #[cfg(test)]
-mod remove_tests {
-    use super::*;
+mod panic_tests {
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+    use crate::linked_list::DoublyLinkedList;
+
+    // A payload whose `Clone` or `Drop` panics for one chosen id, the clone only after
+    // `clones_before_panic` successful ones. `live` counts instances so the tests can tell that
+    // nothing leaked or was dropped twice.
+    #[derive(Debug, Default)]
+    struct Faults {
+        clone_panics_on: Cell<Option<usize>>,
+        clones_before_panic: Cell<usize>,
+        drop_panics_on: Cell<Option<usize>>,
+        live: Cell<isize>,
+    }
+
+    #[derive(Debug)]
+    struct Fragile {
+        id: usize,
+        faults: Rc<Faults>,
+    }
+
+    impl Fragile {
+        fn new(id: usize, faults: &Rc<Faults>) -> Self {
+            faults.live.set(faults.live.get() + 1);
+            Fragile { id, faults: faults.clone() }
+        }
+    }
+
+    impl Clone for Fragile {
+        fn clone(&self) -> Self {
+            if self.faults.clone_panics_on.get() == Some(self.id) {
+                match self.faults.clones_before_panic.get() {
+                    0 => panic!("clone of payload {}", self.id),
+                    n => self.faults.clones_before_panic.set(n - 1),
+                }
+            }
+            Fragile::new(self.id, &self.faults)
+        }
+    }
+
+    impl Drop for Fragile {
+        fn drop(&mut self) {
+            self.faults.live.set(self.faults.live.get() - 1);
+            // Panic once, so that dropping what is left while unwinding does not abort.
+            if self.faults.drop_panics_on.get() == Some(self.id) {
+                self.faults.drop_panics_on.set(None);
+                panic!("drop of payload {}", self.id);
+            }
+        }
+    }
 
     #[test]
-    fn test_remove_middle() {
+    fn test_panicking_clone_in_remove_leaves_the_list_unchanged() {
+        let faults = Rc::new(Faults::default());
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        let handles: Vec<_> = (0..5).map(|id| list.push_back(Fragile::new(id, &faults))).collect();
+        faults.clone_panics_on.set(Some(2));
+        assert!(catch_unwind(AssertUnwindSafe(|| list.remove(handles[2].clone()))).is_err());
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), 5);
+        assert!(!handles[2].is_stale());
+
+        faults.clone_panics_on.set(None);
+        assert_eq!(list.remove(handles[2].clone()).unwrap().id, 2);
+        assert_eq!(list.len(), 4);
+    }
 
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
+    #[test]
+    fn test_panicking_clone_in_pops_leaves_the_list_unchanged() {
+        let faults = Rc::new(Faults::default());
+        let mut list = DoublyLinkedList::new();
+        for id in 0..3 {
+            list.push_back(Fragile::new(id, &faults));
+        }
+        faults.clone_panics_on.set(Some(0));
+        assert!(catch_unwind(AssertUnwindSafe(|| list.pop_first())).is_err());
+        faults.clone_panics_on.set(Some(2));
+        assert!(catch_unwind(AssertUnwindSafe(|| list.pop_back())).is_err());
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), 3);
+        drop(list);
+        assert_eq!(faults.live.get(), 0);
+    }
 
-        let node_to_remove = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
-        list.remove(node_to_remove);
+    #[test]
+    fn test_panicking_clone_in_init_leaves_the_pool_unchanged() {
+        let faults = Rc::new(Faults::default());
+        let mut list = DoublyLinkedList::new();
+        list.init(2, Fragile::new(0, &faults));
+        faults.clone_panics_on.set(Some(1));
+        faults.clones_before_panic.set(3);
+        assert!(catch_unwind(AssertUnwindSafe(|| list.init(5, Fragile::new(1, &faults)))).is_err());
+        assert_eq!(list.pool.pool.len(), 2);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(faults.live.get(), 2);
+    }
 
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert_eq!(list.pop_first().unwrap(), 3);
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_panicking_drop_when_a_pooled_node_is_reused() {
+        let faults = Rc::new(Faults::default());
+        let mut list = DoublyLinkedList::new();
+        list.push_back(Fragile::new(0, &faults));
+        let pooled = list.push_back(Fragile::new(1, &faults));
+        drop(list.remove(pooled));
+        faults.drop_panics_on.set(Some(1));
+        assert!(catch_unwind(AssertUnwindSafe(|| list.push_back(Fragile::new(2, &faults)))).is_err());
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), 1);
+        list.push_back(Fragile::new(3, &faults));
+        assert_eq!(list.len(), 2);
+        drop(list);
+        assert_eq!(faults.live.get(), 0);
     }
 
     #[test]
-    fn test_remove_first() {
+    fn test_panicking_drop_while_dropping_the_list() {
+        let faults = Rc::new(Faults::default());
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        for id in 0..10 {
+            list.push_back(Fragile::new(id, &faults));
+        }
+        faults.drop_panics_on.set(Some(3));
+        assert!(catch_unwind(AssertUnwindSafe(move || drop(list))).is_err());
+        assert_eq!(faults.live.get(), 0);
+    }
+}
 
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
+#[cfg(test)]
+mod borrow_tests {
+    use crate::enums::{HandleError, Side};
+    use crate::linked_list::DoublyLinkedList;
 
-        let node_to_remove = list.head.clone().unwrap();
-        list.remove(node_to_remove);
+    #[test]
+    fn test_get_and_get_mut() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(String::from("a"));
+        list.get_mut(&node).unwrap().push('b');
+        assert_eq!(*list.get(&node).unwrap(), "ab");
+        assert_eq!(*list.try_get(&node).unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_accessors_refuse_stale_handles() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(1);
+        list.remove(node.clone()).unwrap();
+        assert_eq!(list.get(&node).err(), Some(HandleError::Stale));
+        assert_eq!(list.get_mut(&node).err(), Some(HandleError::Stale));
+        assert_eq!(list.try_get(&node).err(), Some(HandleError::Stale));
+        assert_eq!(list.try_insert(Side::After, 2, node.clone()).err(), Some(HandleError::Stale));
+        assert_eq!(list.try_remove(node), Err(HandleError::Stale));
+    }
 
-        assert_eq!(list.pop_first().unwrap(), 2);
-        assert_eq!(list.pop_first().unwrap(), 3);
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_try_get_reports_a_mutable_borrow() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(1);
+        let guard = list.get_mut(&node).unwrap();
+        assert_eq!(list.try_get(&node).err(), Some(HandleError::Borrowed));
+        drop(guard);
+        assert_eq!(*list.try_get(&node).unwrap(), 1);
     }
 
     #[test]
-    fn test_remove_last() {
+    fn test_try_insert_next_to_a_borrowed_node() {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        let first = list.push_back(1);
+        let second = list.push_back(2);
+
+        let peek = list.get(&first).unwrap();
+        assert_eq!(list.try_insert(Side::Before, 0, first.clone()).err(), Some(HandleError::Borrowed));
+        // Inserting before `second` has to relink `first` as well.
+        assert_eq!(list.try_insert(Side::Before, 9, second.clone()).err(), Some(HandleError::Borrowed));
+        // Inserting after `second` does not touch `first`.
+        assert!(list.try_insert(Side::After, 3, second.clone()).is_ok());
+        drop(peek);
+
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), 3);
+        assert!(list.try_insert(Side::Before, 0, first).is_ok());
+        assert_eq!(list.len(), 4);
+    }
 
-        list.push_back(1);
-        list.push_back(2);
+    #[test]
+    fn test_try_remove_next_to_a_borrowed_node() {
+        let mut list = DoublyLinkedList::new();
+        let first = list.push_back(1);
+        let second = list.push_back(2);
         list.push_back(3);
 
-        let node_to_remove = list.tail.clone().unwrap();
-        list.remove(node_to_remove);
+        let peek = list.get(&first).unwrap();
+        assert_eq!(list.try_remove(second.clone()), Err(HandleError::Borrowed));
+        drop(peek);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.try_remove(second), Ok(2));
+        assert_eq!(list.len(), 2);
+    }
 
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert_eq!(list.pop_first().unwrap(), 2);
-        assert!(list.pop_first().is_none());
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn test_insert_panics_next_to_a_borrowed_node() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(1);
+        let _peek = list.get(&node).unwrap();
+        let _ = list.insert(Side::After, 2, node.clone());
     }
 }
 
 #[cfg(test)]
-mod edge_case_tests {
-    use super::*;
+mod access_tests {
+    use crate::enums::HandleError;
+    use crate::linked_list::DoublyLinkedList;
 
     #[test]
-    fn test_empty_list() {
+    fn test_front_and_back() {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        assert!(list.front().is_none() && list.back().is_none());
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(*list.front().unwrap(), 1);
+        assert_eq!(*list.back().unwrap(), 2);
+        *list.front_mut().unwrap() += 10;
+        *list.back_mut().unwrap() += 20;
+        assert_eq!(list.pop_first(), Some(11));
+        assert_eq!(list.pop_first(), Some(22));
+        assert!(list.front_mut().is_none() && list.back_mut().is_none());
+    }
 
-        assert!(list.pop_first().is_none());
-        assert!(list.pop_back().is_none());
+    #[test]
+    fn test_replace_keeps_the_element_in_place() {
+        let mut list = DoublyLinkedList::new();
+        list.push_back("a");
+        let middle = list.push_back("b");
+        list.push_back("c");
+        assert_eq!(list.replace(&middle, "x"), Ok("b"));
+        assert_eq!(*list.get(&middle).unwrap(), "x");
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec!["a", "x", "c"]);
     }
 
     #[test]
-    fn test_single_element_list() {
+    fn test_swap_data() {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        let first = list.push_back(1);
+        let last = list.push_back(2);
+        list.swap_data(&first, &last).unwrap();
+        list.swap_data(&first, &first).unwrap();
+        assert_eq!(*list.get(&first).unwrap(), 2);
+        assert_eq!(*list.get(&last).unwrap(), 1);
+        assert!(list.next_of(&first).unwrap().is_some_and(|next| next.ptr_eq(&last)));
+    }
 
-        list.push_back(1);
+    #[test]
+    fn test_walk_with_next_of_and_prev_of() {
+        let mut list = DoublyLinkedList::new();
+        let first = list.push_back(0);
+        for value in 1..5 {
+            list.push_back(value);
+        }
+        let last = list.push_back(5);
+
+        let mut forward = Vec::new();
+        let mut current = Some(first);
+        while let Some(handle) = current {
+            forward.push(*list.get(&handle).unwrap());
+            current = list.next_of(&handle).unwrap();
+        }
+        assert_eq!(forward, vec![0, 1, 2, 3, 4, 5]);
 
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert!(list.pop_first().is_none());
+        let mut backward = Vec::new();
+        let mut current = Some(last);
+        while let Some(handle) = current {
+            backward.push(*list.get(&handle).unwrap());
+            current = list.prev_of(&handle).unwrap();
+        }
+        assert_eq!(backward, vec![5, 4, 3, 2, 1, 0]);
     }
 
     #[test]
-    fn test_single_element_list_pop_back() {
+    fn test_stale_handles_are_refused() {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        let kept = list.push_back(1);
+        let removed = list.push_back(2);
+        list.remove(removed.clone()).unwrap();
+        assert_eq!(list.replace(&removed, 3), Err(HandleError::Stale));
+        assert_eq!(list.swap_data(&kept, &removed), Err(HandleError::Stale));
+        assert_eq!(list.next_of(&removed).err(), Some(HandleError::Stale));
+        assert_eq!(list.prev_of(&removed).err(), Some(HandleError::Stale));
+        assert_eq!(*list.get(&kept).unwrap(), 1);
+    }
+}
 
-        list.push_back(1);
+#[cfg(test)]
+mod index_tests {
+    use crate::enums::HandleError;
+    use crate::linked_list::DoublyLinkedList;
 
-        assert_eq!(list.pop_back().unwrap(), 1);
-        assert!(list.pop_back().is_none());
+    fn numbers(count: usize) -> DoublyLinkedList<usize> {
+        let mut list = DoublyLinkedList::new();
+        for value in 0..count {
+            list.push_back(value);
+        }
+        return list;
+    }
+
+    #[test]
+    fn test_get_nth_from_both_ends() {
+        for count in [0, 1, 2, 7, 8] {
+            let list = numbers(count);
+            for index in 0..count {
+                assert_eq!(*list.get_nth(index).unwrap(), index);
+                let handle = list.handle_at(index).unwrap();
+                assert_eq!(list.index_of(&handle), Ok(index));
+            }
+            assert!(list.get_nth(count).is_none());
+            assert!(list.handle_at(count).is_none());
+        }
     }
 
     #[test]
-    fn test_single_element_list_insert() {
+    fn test_insert_at_and_remove_at_match_vec() {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        let mut model = Vec::new();
+        for value in 0..20usize {
+            let index = value * 7 % (model.len() + 1);
+            list.insert_at(index, value);
+            model.insert(index, value);
+        }
+        for step in 0..10usize {
+            let index = step * 5 % model.len();
+            assert_eq!(list.remove_at(index), Some(model.remove(index)));
+        }
+        assert_eq!(list.remove_at(model.len()), None);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), model);
+    }
 
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_insert_at_past_the_end_panics() {
+        numbers(3).insert_at(4, 0);
+    }
+
+    #[test]
+    fn test_index_of_refuses_stale_handle() {
+        let mut list = numbers(3);
+        let handle = list.handle_at(1).unwrap();
+        list.remove_at(1);
+        list.insert_at(1, 7);
+        assert_eq!(list.index_of(&handle), Err(HandleError::Stale));
+    }
+
+    #[test]
+    fn test_get_nth_mut() {
+        let mut list = numbers(5);
+        *list.get_nth_mut(3).unwrap() *= 10;
+        assert_eq!(*list.get_nth(3).unwrap(), 30);
+        assert!(list.get_nth_mut(5).is_none());
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 30, 4]);
+    }
+}
+
+#[cfg(test)]
+mod skip_list_map_tests {
+    use std::collections::BTreeMap;
+    use crate::enums::HandleError;
+    use crate::skip_list_map::SkipListMap;
+    use crate::workload::Rng;
+
+    fn entries(map: &SkipListMap<u64, u64>) -> Vec<(u64, u64)> {
+        return map.iter().map(|entry| *entry).collect();
+    }
+
+    #[test]
+    fn test_matches_btree_map() {
+        let mut rng = Rng::new(43);
+        let mut map = SkipListMap::new();
+        let mut model = BTreeMap::new();
+        for value in 0..3000u64 {
+            let key = rng.below(500) as u64;
+            match rng.below(3) {
+                0 | 1 => assert_eq!(map.insert(key, value), model.insert(key, value)),
+                _ => assert_eq!(map.remove(&key), model.remove(&key)),
+            }
+            assert_eq!(map.len(), model.len());
+            assert_eq!(map.get(&key).map(|value| *value), model.get(&key).copied());
+        }
+        assert_eq!(map.validate(), Ok(()));
+        assert_eq!(entries(&map), model.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_floor_ceiling_and_range() {
+        let mut map = SkipListMap::new();
+        for key in (10..=50).step_by(10) {
+            map.insert(key, key * 2);
+        }
+        assert_eq!(map.floor(&30).map(|entry| entry.0), Some(30));
+        assert_eq!(map.floor(&35).map(|entry| entry.0), Some(30));
+        assert!(map.floor(&5).is_none());
+        assert_eq!(map.ceiling(&35).map(|entry| entry.0), Some(40));
+        assert_eq!(map.ceiling(&50).map(|entry| entry.0), Some(50));
+        assert!(map.ceiling(&51).is_none());
+        assert_eq!(map.first().map(|entry| *entry), Some((10, 20)));
+        assert_eq!(map.last().map(|entry| *entry), Some((50, 100)));
+
+        let keys = |range: Vec<(u64, u64)>| range.into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys(map.range(20..40).map(|entry| *entry).collect()), vec![20, 30]);
+        assert_eq!(keys(map.range(15..=40).map(|entry| *entry).collect()), vec![20, 30, 40]);
+        assert_eq!(keys(map.range(45..).map(|entry| *entry).collect()), vec![50]);
+        let after_20 = (std::ops::Bound::Excluded(20), std::ops::Bound::Unbounded);
+        assert_eq!(keys(map.range(after_20).map(|entry| *entry).collect()), vec![30, 40, 50]);
+        assert_eq!(map.range(60..).count(), 0);
+    }
+
+    #[test]
+    fn test_get_mut_and_contains_key() {
+        let mut map = SkipListMap::new();
+        map.insert("b", 1);
+        map.insert("a", 2);
+        *map.get_mut(&"b").unwrap() += 10;
+        assert_eq!(*map.get(&"b").unwrap(), 11);
+        assert!(map.contains_key(&"a") && !map.contains_key(&"c"));
+        assert!(map.get_mut(&"c").is_none());
+    }
+
+    #[test]
+    fn test_removed_entries_are_recycled_and_handles_go_stale() {
+        let mut map = SkipListMap::new();
+        map.init(4, 0, 0);
+        map.insert(1, 10);
+        let handle = map.handle_of(&1).unwrap();
+        assert_eq!(*map.entry(&handle).unwrap(), (1, 10));
+        assert_eq!(map.remove_handle(handle.clone()), Ok((1, 10)));
+        assert_eq!(map.pool.pool.len(), 4);
+
+        map.insert(2, 20);
+        assert_eq!(map.pool.pool.len(), 3);
+        assert_eq!(map.entry(&handle).err(), Some(HandleError::Stale));
+        assert_eq!(map.remove_handle(handle), Err(HandleError::Stale));
+        assert_eq!(entries(&map), vec![(2, 20)]);
+    }
+
+    #[test]
+    fn test_remove_handle_refuses_entries_of_another_map() {
+        let mut map = SkipListMap::new();
+        let mut other = SkipListMap::default();
+        for key in 0..10 {
+            map.insert(key, key);
+            other.insert(key, key * 10);
+        }
+        let foreign = other.handle_of(&4).unwrap();
+        assert_eq!(map.remove_handle(foreign.clone()), Err(HandleError::Stale));
+        other.remove(&4);
+        map.remove(&4);
+        other.insert(4, 0);
+        let foreign = other.handle_of(&4).unwrap();
+        assert_eq!(map.remove_handle(foreign), Err(HandleError::Stale));
+        assert_eq!(map.len(), 9);
+        assert_eq!(map.validate(), Ok(()));
+        assert_eq!(other.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_clear_returns_every_entry_to_the_pool() {
+        let mut map = SkipListMap::new();
+        for key in 0..100 {
+            map.insert(key, key);
+        }
+        map.clear();
+        assert!(map.is_empty() && map.iter().next().is_none());
+        assert_eq!(map.pool.pool.len(), 100);
+        assert_eq!(map.validate(), Ok(()));
+        map.insert(7, 7);
+        assert_eq!(entries(&map), vec![(7, 7)]);
+    }
+}
+
+#[cfg(test)]
+mod singly_tests {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::mem::size_of;
+    use crate::adaptors::{Deque, Queue, Stack};
+    use crate::enums::ValidationError;
+    use crate::node::{Node, SinglyNode};
+    use crate::singly_list::SinglyLinkedList;
+    use crate::workload::Rng;
+
+    #[test]
+    fn test_matches_vec_deque() {
+        let mut rng = Rng::new(46);
+        let mut list = SinglyLinkedList::new();
+        let mut model = VecDeque::new();
+        for value in 0..2000u64 {
+            match rng.below(3) {
+                0 => {
+                    list.push_first(value);
+                    model.push_front(value);
+                }
+                1 => {
+                    list.push_back(value);
+                    model.push_back(value);
+                }
+                _ => assert_eq!(list.pop_first(), model.pop_front()),
+            }
+            assert_eq!(list.len(), model.len());
+            assert_eq!(list.front(), model.front());
+            assert_eq!(list.back(), model.back());
+        }
+        assert!(list.iter().eq(model.iter()));
+    }
+
+    #[test]
+    fn test_removed_nodes_are_recycled() {
+        let mut list = SinglyLinkedList::new();
+        list.init(3, 0);
         list.push_back(1);
-        let anchor = list.head.clone().unwrap();
+        list.push_back(2);
+        assert_eq!(list.pool.free.len(), 1);
+        assert_eq!(list.pop_first(), Some(1));
+        list.push_first(3);
+        list.push_back(4);
+        list.push_back(5);
+        assert_eq!(list.pool.nodes.len(), 4);
+        list.clear();
+        assert_eq!(list.pool.free.len(), 4);
+        assert!(list.is_empty() && list.front().is_none());
+        assert_eq!(list.validate(), Ok(()));
+    }
 
-        list.insert(Side::After, 2, anchor.clone());
-        list.insert(Side::Before, 0, anchor);
+    #[test]
+    fn test_validate_reports_broken_tail() {
+        let mut list = SinglyLinkedList::new();
+        list.push_back(1);
+        list.push_back(2);
+        *list.front_mut().unwrap() = 10;
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 2]);
+        list.pool.nodes[0].next = crate::node::NIL;
+        assert_eq!(list.validate(), Err(ValidationError::TailNotLast { count: 1 }));
+    }
+
+    #[test]
+    fn test_singly_nodes_are_smaller() {
+        assert!(size_of::<SinglyNode<u64>>() < size_of::<RefCell<Node<u64>>>());
+    }
 
-        assert_eq!(list.pop_first().unwrap(), 0);
-        assert_eq!(list.pop_first().unwrap(), 1);
-        assert_eq!(list.pop_first().unwrap(), 2);
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_stack_is_lifo() {
+        let mut stack = Stack::new();
+        stack.init(2, 0);
+        for value in 1..=3 {
+            stack.push(value);
+        }
+        assert_eq!(stack.peek(), Some(&3));
+        assert_eq!(stack.len(), 3);
+        assert_eq!((stack.pop(), stack.pop(), stack.pop(), stack.pop()), (Some(3), Some(2), Some(1), None));
+        assert!(stack.is_empty());
     }
 
     #[test]
-    fn test_single_element_list_remove() {
+    fn test_queue_is_fifo() {
+        let mut queue = Queue::new();
+        for value in 1..=3 {
+            queue.push(value);
+        }
+        assert_eq!(queue.peek(), Some(&1));
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(4);
+        assert_eq!((queue.pop(), queue.pop(), queue.pop(), queue.pop()), (Some(2), Some(3), Some(4), None));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_deque_uses_both_ends() {
+        let mut deque = Deque::new();
+        deque.push_back(2);
+        deque.push_first(1);
+        deque.push_back(3);
+        assert_eq!((*deque.front().unwrap(), *deque.back().unwrap()), (1, 3));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_first(), Some(1));
+        assert_eq!(deque.len(), 1);
+        assert_eq!((deque.pop_first(), deque.pop_back()), (Some(2), None));
+        assert!(deque.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use crate::linked_list::DoublyLinkedList;
+    use crate::workload::Rng;
+
+    fn list_of(count: u64) -> DoublyLinkedList<u64> {
         let mut list = DoublyLinkedList::new();
-        list.init(5, 0);
+        for value in 0..count {
+            list.push_back(value);
+        }
+        return list;
+    }
+
+    #[test]
+    fn test_snapshots_keep_their_contents_while_the_list_changes() {
+        let mut rng = Rng::new(48);
+        let mut list = list_of(50);
+        list.init(20, 0);
+        let mut snapshots = Vec::new();
+        for value in 100..1500u64 {
+            if rng.below(20) == 0 {
+                let expected = list.snapshot().iter().collect::<Vec<_>>();
+                snapshots.push((list.snapshot(), expected));
+            }
+            let len = list.len();
+            match rng.below(7) {
+                0 => {
+                    list.insert_at(rng.below(len + 1), value);
+                }
+                1 if len > 0 => {
+                    list.remove_at(rng.below(len));
+                }
+                2 if len > 0 => *list.get_nth_mut(rng.below(len)).unwrap() = value,
+                3 if len > 0 => {
+                    let handle = list.handle_at(rng.below(len)).unwrap();
+                    list.replace(&handle, value).unwrap();
+                }
+                4 if len > 1 => {
+                    let a = list.handle_at(rng.below(len)).unwrap();
+                    let b = list.handle_at(rng.below(len)).unwrap();
+                    list.swap_data(&a, &b).unwrap();
+                }
+                5 => {
+                    list.pop_first();
+                }
+                _ => {
+                    list.push_first(value);
+                }
+            }
+            if rng.below(50) == 0 && !snapshots.is_empty() {
+                snapshots.swap_remove(rng.below(snapshots.len()));
+            }
+        }
+        for (snapshot, expected) in &snapshots {
+            assert_eq!(snapshot.len(), expected.len());
+            assert_eq!(&snapshot.iter().collect::<Vec<_>>(), expected);
+        }
+    }
 
+    #[test]
+    fn test_reused_nodes_are_read_from_the_copy() {
+        let mut list = list_of(3);
+        let snapshot = list.snapshot();
+        let middle = list.handle_at(1).unwrap();
+        assert_eq!(list.remove(middle), Ok(1));
+        list.push_back(10);
+        assert_eq!(list.pool.pool.len(), 0);
+        // Both ends of the removal and the old tail, whose `next` the push changed.
+        assert_eq!(snapshot.copied(), 3);
+        assert!(snapshot.iter().eq(0..3));
+        assert!(list.snapshot().iter().eq([0, 2, 10]));
+    }
+
+    #[test]
+    fn test_snapshot_outlives_cleared_and_dropped_lists() {
+        let mut list = list_of(100);
+        let before = list.snapshot();
+        list.clear();
         list.push_back(1);
-        let node_to_remove = list.head.clone().unwrap();
-        list.remove(node_to_remove);
+        let after = list.snapshot();
+        drop(list);
+        assert!(before.iter().eq(0..100));
+        assert_eq!(after.iter().collect::<Vec<_>>(), vec![1]);
+    }
+}
+
+#[cfg(test)]
+mod transaction_tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use crate::enums::{HandleError, Side};
+    use crate::linked_list::DoublyLinkedList;
+    use crate::workload::Rng;
+
+    fn list_of(count: u64) -> DoublyLinkedList<u64> {
+        let mut list = DoublyLinkedList::new();
+        for value in 0..count {
+            list.push_back(value);
+        }
+        return list;
+    }
+
+    fn values(list: &DoublyLinkedList<u64>) -> Vec<u64> {
+        return (0..list.len()).map(|index| *list.get_nth(index).unwrap()).collect();
+    }
 
-        assert!(list.pop_first().is_none());
+    #[test]
+    fn test_commit_pools_removed_nodes() {
+        let mut list = list_of(3);
+        let middle = list.handle_at(1).unwrap();
+        let result: Result<(), HandleError> = list.transaction(|tx| {
+            tx.insert(Side::After, 10, middle.clone())?;
+            assert_eq!(tx.remove(middle.clone()), Ok(1));
+            assert_eq!(tx.remove(middle.clone()), Err(HandleError::Stale));
+            assert!(tx.list().pool.pool.is_empty());
+            return Ok(());
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(values(&list), vec![0, 10, 2]);
+        assert_eq!(list.pool.pool.len(), 1);
+        assert!(middle.is_stale());
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_rollback_relinks_removed_nodes_and_pools_inserted_ones() {
+        let mut list = list_of(4);
+        list.init(2, 0);
+        let handles: Vec<_> = (0..4).map(|index| list.handle_at(index).unwrap()).collect();
+        let mut inserted = Vec::new();
+        let result: Result<(), HandleError> = list.transaction(|tx| {
+            tx.remove(handles[1].clone())?;
+            inserted.push(tx.push_first(20));
+            tx.replace(&handles[2], 30)?;
+            tx.remove(handles[2].clone())?;
+            inserted.push(tx.insert(Side::Before, 40, handles[3].clone())?);
+            tx.pop_back();
+            inserted.push(tx.push_back(50));
+            return Err(HandleError::Borrowed);
+        });
+        assert_eq!(result, Err(HandleError::Borrowed));
+        assert_eq!(values(&list), vec![0, 1, 2, 3]);
+        for (index, handle) in handles.iter().enumerate() {
+            assert!(!handle.is_stale());
+            assert!(handle.ptr_eq(&list.handle_at(index).unwrap()));
+        }
+        assert!(inserted.iter().all(|handle| handle.is_stale()));
+        // Two of the inserted nodes came from the pool and one was new; all three are pooled now.
+        assert_eq!(list.pool.pool.len(), 3);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_panic_rolls_back() {
+        let mut list = list_of(4);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _: Result<(), ()> = list.transaction(|tx| {
+                tx.pop_first();
+                tx.push_back(9);
+                panic!("validation blew up");
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(values(&list), vec![0, 1, 2, 3]);
+        assert_eq!(list.pool.pool.len(), 1);
+        assert_eq!(list.validate(), Ok(()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_random_edits_roll_back() {
+        let mut rng = Rng::new(49);
+        let mut list = list_of(30);
+        list.init(10, 0);
+        for round in 0..50 {
+            let before = values(&list);
+            let pooled = list.pool.pool.len();
+            let commit = round % 3 == 0;
+            let result = list.transaction(|tx| {
+                for value in 0..20 {
+                    let len = tx.list().len();
+                    match rng.below(5) {
+                        0 if len > 0 => {
+                            let handle = tx.list().handle_at(rng.below(len)).unwrap();
+                            tx.remove(handle)?;
+                        }
+                        1 if len > 0 => {
+                            let handle = tx.list().handle_at(rng.below(len)).unwrap();
+                            tx.insert(Side::After, value, handle)?;
+                        }
+                        2 if len > 0 => {
+                            let handle = tx.list().handle_at(rng.below(len)).unwrap();
+                            tx.replace(&handle, value)?;
+                        }
+                        3 => {
+                            tx.pop_first();
+                        }
+                        _ => {
+                            tx.push_back(value);
+                        }
+                    }
+                }
+                return if commit { Ok(()) } else { Err(HandleError::Borrowed) };
+            });
+            assert_eq!(result.is_ok(), commit);
+            if !commit {
+                assert_eq!(values(&list), before);
+                assert!(list.pool.pool.len() >= pooled);
+            }
+            assert_eq!(list.validate(), Ok(()));
+        }
+    }
+}
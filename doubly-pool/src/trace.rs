@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+use crate::enums::{HandleError, Side};
+use crate::linked_list::DoublyLinkedList;
+use crate::node::{Handle, Node};
+
+pub const TRACE_HEADER: &str = "# linked-rs trace v1";
+
+// Every node created by a trace gets the next sequential id, starting at 0. Pops carry the id of
+// the node they removed so a replay can release its own handle first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    PushFirst(u64),
+    PushBack(u64),
+    PopFirst(Option<u64>),
+    PopBack(Option<u64>),
+    Insert(Side, u64, u64),
+    Remove(u64),
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::PushFirst(id) => write!(f, "push_first {}", id),
+            Op::PushBack(id) => write!(f, "push_back {}", id),
+            Op::PopFirst(Some(id)) => write!(f, "pop_first {}", id),
+            Op::PopFirst(None) => write!(f, "pop_first"),
+            Op::PopBack(Some(id)) => write!(f, "pop_back {}", id),
+            Op::PopBack(None) => write!(f, "pop_back"),
+            Op::Insert(Side::Before, anchor, id) => write!(f, "insert before {} {}", anchor, id),
+            Op::Insert(Side::After, anchor, id) => write!(f, "insert after {} {}", anchor, id),
+            Op::Remove(id) => write!(f, "remove {}", id),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TraceError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn parse_id(token: Option<&str>) -> Result<u64, String> {
+    let token = token.ok_or("missing node id")?;
+    return token.parse::<u64>().map_err(|_| format!("'{}' is not a node id", token));
+}
+
+fn parse_op(line: &str) -> Result<Op, String> {
+    let mut tokens = line.split_whitespace();
+    let op = match tokens.next() {
+        Some("push_first") => Op::PushFirst(parse_id(tokens.next())?),
+        Some("push_back") => Op::PushBack(parse_id(tokens.next())?),
+        Some("pop_first") => Op::PopFirst(tokens.next().map(|t| parse_id(Some(t))).transpose()?),
+        Some("pop_back") => Op::PopBack(tokens.next().map(|t| parse_id(Some(t))).transpose()?),
+        Some("insert") => {
+            let side = match tokens.next() {
+                Some("before") => Side::Before,
+                Some("after") => Side::After,
+                other => return Err(format!("expected 'before' or 'after', found {:?}", other)),
+            };
+            Op::Insert(side, parse_id(tokens.next())?, parse_id(tokens.next())?)
+        }
+        Some("remove") => Op::Remove(parse_id(tokens.next())?),
+        Some(other) => return Err(format!("unknown operation '{}'", other)),
+        None => return Err("empty line".to_string()),
+    };
+    if let Some(extra) = tokens.next() {
+        return Err(format!("unexpected '{}'", extra));
+    }
+
+    return Ok(op);
+}
+
+// Parses a trace and checks that it is replayable: ids are handed out in order and every
+// operation refers to a node that is still in the list.
+pub fn read_trace<R: BufRead>(reader: R) -> Result<Vec<Op>, TraceError> {
+    let mut ops = Vec::new();
+    let mut live: Vec<bool> = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |message: String| TraceError { line: line_number, message };
+        let line = line.map_err(|e| error(e.to_string()))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let op = parse_op(line).map_err(error)?;
+        let (created, released) = match op {
+            Op::PushFirst(id) | Op::PushBack(id) => (Some(id), None),
+            Op::Insert(_, anchor, id) => {
+                if !live.get(anchor as usize).copied().unwrap_or(false) {
+                    return Err(error(format!("anchor {} is not in the list", anchor)));
+                }
+                (Some(id), None)
+            }
+            Op::PopFirst(id) | Op::PopBack(id) => (None, id),
+            Op::Remove(id) => (None, Some(id)),
+        };
+        if let Some(id) = created {
+            if id != live.len() as u64 {
+                return Err(error(format!("expected new node id {}, found {}", live.len(), id)));
+            }
+            live.push(true);
+        }
+        if let Some(id) = released {
+            match live.get_mut(id as usize) {
+                Some(alive) if *alive => *alive = false,
+                _ => return Err(error(format!("node {} is not in the list", id))),
+            }
+        }
+        ops.push(op);
+    }
+
+    return Ok(ops);
+}
+
+pub fn load_trace(path: &str) -> Result<Vec<Op>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    return read_trace(io::BufReader::new(file)).map_err(|e| format!("{}: {}", path, e));
+}
+
+// The largest number of nodes linked at once, which is the pool size a replay needs.
+pub fn peak_live(ops: &[Op]) -> usize {
+    let mut live: usize = 0;
+    let mut peak = 0;
+    for op in ops {
+        match op {
+            Op::PushFirst(_) | Op::PushBack(_) | Op::Insert(..) => live += 1,
+            Op::PopFirst(Some(_)) | Op::PopBack(Some(_)) | Op::Remove(_) => live -= 1,
+            Op::PopFirst(None) | Op::PopBack(None) => {}
+        }
+        peak = peak.max(live);
+    }
+
+    return peak;
+}
+
+pub fn write_trace<W: Write>(mut out: W, ops: &[Op]) -> io::Result<()> {
+    writeln!(out, "{}", TRACE_HEADER)?;
+    for op in ops {
+        writeln!(out, "{}", op)?;
+    }
+    return out.flush();
+}
+
+// Wraps a list and logs every mutation to `out` in the trace format. Write errors do not
+// interrupt the list operations; the first one is reported by `finish`.
+pub struct TraceRecorder<T: Clone, W: Write> {
+    list: DoublyLinkedList<T>,
+    out: W,
+    ids: HashMap<*const RefCell<Node<T>>, u64>,
+    next_id: u64,
+    error: Option<io::Error>,
+}
+
+impl<T: Clone, W: Write> TraceRecorder<T, W> {
+    pub fn new(mut out: W) -> Self {
+        let error = writeln!(out, "{}", TRACE_HEADER).err();
+        TraceRecorder {
+            list: DoublyLinkedList::new(),
+            out,
+            ids: HashMap::new(),
+            next_id: 0,
+            error,
+        }
+    }
+
+    pub fn list(&self) -> &DoublyLinkedList<T> {
+        return &self.list;
+    }
+
+    fn log(&mut self, op: Op) {
+        if self.error.is_none() {
+            self.error = writeln!(self.out, "{}", op).err();
+        }
+    }
+
+    fn register(&mut self, node: &Rc<RefCell<Node<T>>>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.ids.insert(Rc::as_ptr(node), id);
+        return id;
+    }
+
+    fn release(&mut self, node: &Rc<RefCell<Node<T>>>) -> u64 {
+        return self
+            .ids
+            .remove(&Rc::as_ptr(node))
+            .expect("node does not belong to this recorder");
+    }
+
+    fn anchor_id(&self, node: &Rc<RefCell<Node<T>>>) -> u64 {
+        return *self
+            .ids
+            .get(&Rc::as_ptr(node))
+            .expect("node does not belong to this recorder");
+    }
+
+    pub fn push_first(&mut self, data: T) -> Handle<T> {
+        let node = self.list.push_first(data);
+        let id = self.register(&node.node);
+        self.log(Op::PushFirst(id));
+        return node;
+    }
+
+    pub fn push_back(&mut self, data: T) -> Handle<T> {
+        let node = self.list.push_back(data);
+        let id = self.register(&node.node);
+        self.log(Op::PushBack(id));
+        return node;
+    }
+
+    // Stale handles are refused before they are looked up: their node may have been reused
+    // and registered under a new id.
+    pub fn insert(&mut self, side: Side, data: T, anchor: Handle<T>) -> Result<Handle<T>, HandleError> {
+        if anchor.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        let anchor_id = self.anchor_id(&anchor.node);
+        let node = self.list.insert(side, data, anchor)?;
+        let id = self.register(&node.node);
+        self.log(Op::Insert(side, anchor_id, id));
+        return Ok(node);
+    }
+
+    pub fn remove(&mut self, node: Handle<T>) -> Result<T, HandleError> {
+        if node.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        let id = self.release(&node.node);
+        self.log(Op::Remove(id));
+        return self.list.remove(node);
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        let id = self.list.head.clone().map(|head| self.release(&head));
+        self.log(Op::PopFirst(id));
+        return self.list.pop_first();
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let id = self.list.tail.clone().map(|tail| self.release(&tail));
+        self.log(Op::PopBack(id));
+        return self.list.pop_back();
+    }
+
+    // Flushes the trace and hands back the list and the writer.
+    pub fn finish(mut self) -> io::Result<(DoublyLinkedList<T>, W)> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.out.flush()?;
+        return Ok((self.list, self.out));
+    }
+}
+
+// Drives `list` through `ops`, building payloads with `make` from the node id. The trace is
+// assumed to have been validated by `read_trace`.
+pub fn replay<T: Clone>(list: &mut DoublyLinkedList<T>, ops: &[Op], mut make: impl FnMut(u64) -> T) {
+    let mut handles: Vec<Option<Handle<T>>> = Vec::new();
+
+    for op in ops {
+        match *op {
+            Op::PushFirst(id) => handles.push(Some(list.push_first(make(id)))),
+            Op::PushBack(id) => handles.push(Some(list.push_back(make(id)))),
+            Op::Insert(side, anchor, id) => {
+                let anchor = handles[anchor as usize].clone().expect("anchor is not in the list");
+                handles.push(Some(list.insert(side, make(id), anchor).expect("anchor was removed")));
+            }
+            Op::Remove(id) => {
+                let node = handles[id as usize].take().expect("node is not in the list");
+                list.remove(node).expect("node was removed");
+            }
+            Op::PopFirst(id) => {
+                if let Some(id) = id {
+                    handles[id as usize] = None;
+                }
+                list.pop_first();
+            }
+            Op::PopBack(id) => {
+                if let Some(id) = id {
+                    handles[id as usize] = None;
+                }
+                list.pop_back();
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use crate::enums::{HandleError, Side};
+use crate::linked_list::DoublyLinkedList;
+use crate::node::{Handle, Node};
+
+// The inverse of one edit, applied when the transaction rolls back.
+enum Undo<T> {
+    // Unlinked again and returned to the pool, which makes its handles stale.
+    Inserted(Rc<RefCell<Node<T>>>),
+    // Linked back in between its former neighbours, with its generation restored.
+    Removed {
+        node: Rc<RefCell<Node<T>>>,
+        prev: Option<Rc<RefCell<Node<T>>>>,
+        next: Option<Rc<RefCell<Node<T>>>>,
+    },
+    // Gets its old payload back.
+    Replaced { node: Rc<RefCell<Node<T>>>, data: T },
+}
+
+// The edits available inside `DoublyLinkedList::transaction`. Each one is applied to the list
+// straight away and its inverse is logged.
+//
+// Removed nodes are detached and their generation is bumped, so their handles are stale for
+// the rest of the transaction, but they are kept out of the pool until it commits. A rollback
+// can then link the same nodes back in and undo the bump, which makes the old handles valid
+// again. Nodes inserted by a rolled-back transaction go back to the pool.
+pub struct Transaction<'a, T: Clone> {
+    list: &'a mut DoublyLinkedList<T>,
+    log: Vec<Undo<T>>,
+}
+
+impl<'a, T: Clone> Transaction<'a, T> {
+    pub(crate) fn new(list: &'a mut DoublyLinkedList<T>) -> Self {
+        return Transaction { list, log: Vec::new() };
+    }
+
+    // The list with the edits so far applied, for checks before deciding to commit.
+    pub fn list(&self) -> &DoublyLinkedList<T> {
+        return self.list;
+    }
+
+    pub fn get<'b>(&self, handle: &'b Handle<T>) -> Result<Ref<'b, T>, HandleError> {
+        return self.list.get(handle);
+    }
+
+    pub fn push_first(&mut self, data: T) -> Handle<T> {
+        let handle = self.list.push_first(data);
+        self.log.push(Undo::Inserted(handle.node.clone()));
+        return handle;
+    }
+
+    pub fn push_back(&mut self, data: T) -> Handle<T> {
+        let handle = self.list.push_back(data);
+        self.log.push(Undo::Inserted(handle.node.clone()));
+        return handle;
+    }
+
+    pub fn insert(&mut self, side: Side, data: T, anchor: Handle<T>) -> Result<Handle<T>, HandleError> {
+        let handle = self.list.insert(side, data, anchor)?;
+        self.log.push(Undo::Inserted(handle.node.clone()));
+        return Ok(handle);
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Result<T, HandleError> {
+        if handle.is_stale() {
+            return Err(HandleError::Stale);
+        }
+        return Ok(self.detach(handle.node));
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        let head = self.list.head.clone()?;
+        return Some(self.detach(head));
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.list.tail.clone()?;
+        return Some(self.detach(tail));
+    }
+
+    pub fn replace(&mut self, handle: &Handle<T>, data: T) -> Result<T, HandleError> {
+        let old = self.list.replace(handle, data)?;
+        self.log.push(Undo::Replaced { node: handle.node.clone(), data: old.clone() });
+        return Ok(old);
+    }
+
+    fn detach(&mut self, node: Rc<RefCell<Node<T>>>) -> T {
+        // Clone first, like `DoublyLinkedList::remove`, so a panicking `Clone` changes nothing.
+        let data = node.borrow().data.clone();
+        let (prev, next) = self.list.detach(&node);
+        let mut node_borrow = node.borrow_mut();
+        node_borrow.generation = node_borrow.generation.wrapping_add(1);
+        drop(node_borrow);
+        self.list.debug_validate();
+        self.log.push(Undo::Removed { node, prev, next });
+        return data;
+    }
+
+    // Keeps the edits and hands the removed nodes to the pool.
+    pub(crate) fn commit(mut self) {
+        for undo in self.log.drain(..) {
+            if let Undo::Removed { node, .. } = undo {
+                self.list.pool.return_node(node);
+            }
+        }
+    }
+
+    fn rollback(&mut self) {
+        while let Some(undo) = self.log.pop() {
+            match undo {
+                Undo::Inserted(node) => {
+                    self.list.detach(&node);
+                    self.list.pool.return_node(node);
+                }
+                Undo::Removed { node, prev, next } => {
+                    let mut node_borrow = node.borrow_mut();
+                    node_borrow.generation = node_borrow.generation.wrapping_sub(1);
+                    drop(node_borrow);
+                    self.list.reattach(&node, prev, next);
+                }
+                Undo::Replaced { node, data } => {
+                    // Any removal of the node has been undone by now, so this handle is current.
+                    let _ = self.list.replace(&Handle::new(node), data);
+                }
+            }
+        }
+        // A second panic while unwinding would abort the process.
+        if !std::thread::panicking() {
+            self.list.debug_validate();
+        }
+    }
+}
+
+// Dropping a transaction that was not committed rolls it back. That covers both an `Err` from
+// the closure and a panic unwinding through it.
+impl<'a, T: Clone> Drop for Transaction<'a, T> {
+    fn drop(&mut self) {
+        self.rollback();
+    }
+}
@@ -0,0 +1,270 @@
+use std::cell::{Ref, RefCell, RefMut};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use crate::enums::{HandleError, Side, ValidationError};
+use crate::node::{new_list_id, Node, NodeRef};
+
+// A ring of nodes with no head or tail. Every node's `prev` and `next` are set, a single node
+// links to itself, and `current` marks where rotation, `push` and iteration start. There is no
+// `Debug`, as printing the nodes would follow the links around the ring forever.
+pub struct CircularList<T> {
+    current: Option<Rc<RefCell<Node<T>>>>,
+    len: usize,
+    id: usize,
+}
+
+impl<T> CircularList<T> {
+    pub fn new() -> Self {
+        return CircularList { current: None, len: 0, id: new_list_id() };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    // Inserts just before `current`, so it is visited last when iterating from `current`. The
+    // first node pushed becomes `current`.
+    pub fn push(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        return match self.current.clone() {
+            Some(current) => {
+                let prev = current.borrow().prev.clone().expect("ring nodes are linked");
+                self.link(data, prev, current)
+            }
+            None => {
+                let node = self.new_node(data);
+                node.borrow_mut().prev = Some(node.clone());
+                node.borrow_mut().next = Some(node.clone());
+                self.current = Some(node.clone());
+                self.len = 1;
+                self.debug_validate();
+                node
+            }
+        };
+    }
+
+    // Refuses an anchor that was removed or belongs to another list.
+    pub fn insert(
+        &mut self,
+        side: Side,
+        data: T,
+        anchor: Rc<RefCell<Node<T>>>,
+    ) -> Result<Rc<RefCell<Node<T>>>, HandleError> {
+        self.check_linked(&anchor)?;
+        let (prev, next) = match side {
+            Side::Before => {
+                let prev = anchor.borrow().prev.clone().expect("ring nodes are linked");
+                (prev, anchor)
+            }
+            Side::After => {
+                let next = anchor.borrow().next.clone().expect("ring nodes are linked");
+                (anchor, next)
+            }
+        };
+        return Ok(self.link(data, prev, next));
+    }
+
+    fn new_node(&self, data: T) -> Rc<RefCell<Node<T>>> {
+        let mut node = Node::new(data);
+        node.list = self.id;
+        return Rc::new(RefCell::new(node));
+    }
+
+    // Links a new node between `prev` and `next`, which must be adjacent.
+    fn link(&mut self, data: T, prev: Rc<RefCell<Node<T>>>, next: Rc<RefCell<Node<T>>>) -> Rc<RefCell<Node<T>>> {
+        let node = self.new_node(data);
+        node.borrow_mut().prev = Some(prev.clone());
+        node.borrow_mut().next = Some(next.clone());
+        prev.borrow_mut().next = Some(node.clone());
+        next.borrow_mut().prev = Some(node.clone());
+        self.len += 1;
+        self.debug_validate();
+        return node;
+    }
+
+    // Removes `node` from the ring. If it was `current`, its successor takes over. Like
+    // `DoublyLinkedList::remove`, other handles to the node may stay alive, and it returns `None`
+    // if the node was already removed or belongs to another list.
+    pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
+        if self.check_linked(&node) == Err(HandleError::Stale) {
+            return None;
+        }
+        let (prev, next) = {
+            let mut borrow = node.borrow_mut();
+            (borrow.prev.take()?, borrow.next.take()?)
+        };
+        if self.len == 1 {
+            // `prev` and `next` are the node itself.
+            drop((prev, next));
+            self.current = None;
+        } else {
+            if self.current.as_ref().is_some_and(|current| Rc::ptr_eq(current, &node)) {
+                self.current = Some(next.clone());
+            }
+            prev.borrow_mut().next = Some(next.clone());
+            next.borrow_mut().prev = Some(prev);
+        }
+        self.len -= 1;
+        self.debug_validate();
+
+        return node.borrow_mut().data.take();
+    }
+
+    // Removes `current` and moves it to the next node.
+    pub fn pop_current(&mut self) -> Option<T> {
+        let current = self.current.clone()?;
+        return self.remove(current);
+    }
+
+    pub fn current(&self) -> Option<Rc<RefCell<Node<T>>>> {
+        return self.current.clone();
+    }
+
+    pub fn current_value(&self) -> Option<Ref<'_, T>> {
+        return self.current.as_ref().map(|node| Ref::map(node.borrow(), |node| node.data()));
+    }
+
+    pub fn set_current(&mut self, node: &Rc<RefCell<Node<T>>>) -> Result<(), HandleError> {
+        self.check_linked(node)?;
+        self.current = Some(node.clone());
+        return Ok(());
+    }
+
+    pub fn rotate_forward(&mut self) {
+        self.current = self.current.as_ref().and_then(|current| current.borrow().next.clone());
+    }
+
+    pub fn rotate_backward(&mut self) {
+        self.current = self.current.as_ref().and_then(|current| current.borrow().prev.clone());
+    }
+
+    pub fn get<'a>(&self, node: &'a Rc<RefCell<Node<T>>>) -> Ref<'a, T> {
+        return Ref::map(node.borrow(), |node| node.data());
+    }
+
+    pub fn get_mut<'a>(&mut self, node: &'a Rc<RefCell<Node<T>>>) -> RefMut<'a, T> {
+        return RefMut::map(node.borrow_mut(), |node| node.data_mut());
+    }
+
+    pub fn next_of(&self, node: &Rc<RefCell<Node<T>>>) -> Option<Rc<RefCell<Node<T>>>> {
+        return node.borrow().next.clone();
+    }
+
+    pub fn prev_of(&self, node: &Rc<RefCell<Node<T>>>) -> Option<Rc<RefCell<Node<T>>>> {
+        return node.borrow().prev.clone();
+    }
+
+    // Every element once, starting at `current`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        return Iter { next: self.current.clone(), remaining: self.len, list: PhantomData };
+    }
+
+    // Every element once, starting at `start`.
+    pub fn iter_from(&self, start: &Rc<RefCell<Node<T>>>) -> Result<Iter<'_, T>, HandleError> {
+        self.check_linked(start)?;
+        return Ok(Iter { next: Some(start.clone()), remaining: self.len, list: PhantomData });
+    }
+
+    // The ring is one big reference cycle, so every link is cut before the nodes are dropped.
+    pub fn clear(&mut self) {
+        let mut current = self.current.take();
+        for _ in 0..self.len {
+            let Some(node) = current else {
+                break;
+            };
+            let mut borrow = node.borrow_mut();
+            borrow.prev = None;
+            current = borrow.next.take();
+        }
+        self.len = 0;
+    }
+
+    // Walks `len` steps from `current`, checking each back link and that the walk ends where
+    // it started.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let Some(start) = self.current.clone() else {
+            if self.len != 0 {
+                return Err(ValidationError::CountMismatch { counted: 0, len: self.len });
+            }
+            return Ok(());
+        };
+        let mut node = start.clone();
+        for index in 0..self.len {
+            let next = node.borrow().next.clone().ok_or(ValidationError::BrokenLink { index })?;
+            let linked_back = next.borrow().prev.as_ref().is_some_and(|prev| Rc::ptr_eq(prev, &node));
+            if !linked_back {
+                return Err(ValidationError::BrokenLink { index });
+            }
+            if Rc::ptr_eq(&next, &start) && index + 1 < self.len {
+                return Err(ValidationError::CountMismatch { counted: index + 1, len: self.len });
+            }
+            node = next;
+        }
+        if !Rc::ptr_eq(&node, &start) {
+            return Err(ValidationError::RingNotClosed { len: self.len });
+        }
+        return Ok(());
+    }
+
+    // A node is in the ring if this list linked it and it still has links. `remove` and
+    // `clear` take the links away.
+    fn check_linked(&self, node: &Rc<RefCell<Node<T>>>) -> Result<(), HandleError> {
+        let borrow = node.try_borrow().map_err(|_| HandleError::Borrowed)?;
+        if borrow.list != self.id || borrow.prev.is_none() {
+            return Err(HandleError::Stale);
+        }
+        return Ok(());
+    }
+
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    fn debug_validate(&self) {
+        if let Err(error) = self.validate() {
+            panic!("list is corrupted: {}", error);
+        }
+    }
+
+    #[cfg(not(any(debug_assertions, feature = "validate")))]
+    fn debug_validate(&self) {}
+}
+
+impl<T> Default for CircularList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for CircularList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// Yields guards that hold their node, like `DoublyLinkedList::get_nth`, so an element stays
+// readable however long it is kept.
+pub struct Iter<'a, T> {
+    next: Option<Rc<RefCell<Node<T>>>>,
+    remaining: usize,
+    // Keeps the ring from changing under the walk.
+    list: PhantomData<&'a CircularList<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = NodeRef<T>;
+
+    fn next(&mut self) -> Option<NodeRef<T>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.next.take()?;
+        self.remaining -= 1;
+        self.next = node.borrow().next.clone();
+        return Some(NodeRef::new(node));
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.remaining, Some(self.remaining));
+    }
+}
@@ -1,4 +1,69 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Side {
     Before,
     After,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HandleError {
+    // A node the operation has to change is borrowed through `get` or `get_mut`.
+    Borrowed,
+    // The node is not linked in the list: it was removed, the list was cleared, or the node
+    // belongs to another list.
+    Stale,
+}
+
+impl fmt::Display for HandleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandleError::Borrowed => write!(f, "a node the operation needs is borrowed"),
+            HandleError::Stale => write!(f, "the node is not linked in this list"),
+        }
+    }
+}
+
+// The first broken invariant found by `DoublyLinkedList::validate`. Indexes count nodes from
+// the head.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidationError {
+    HeadHasPrev,
+    TailHasNext,
+    // The prev link of the node after `index` does not point back at it.
+    BrokenLink { index: usize },
+    TailNotLast { count: usize },
+    CountMismatch { counted: usize, len: usize },
+    // An express lane of an `IndexedList` skips the wrong number of nodes, links a node that is
+    // not in the list or misses a tower that reaches it.
+    BrokenLane { lane: usize },
+    // A node of an `OrderedList` has no label or one not above the label of the node before it.
+    LabelOrder { index: usize },
+    // Chunk `index` of an `UnrolledList` is empty, over capacity, or has a handle that does not
+    // point back at it.
+    BadChunk { index: usize },
+    // Walking `len` nodes around a `CircularList` does not lead back to `current`.
+    RingNotClosed { len: usize },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::HeadHasPrev => write!(f, "head has a prev link"),
+            ValidationError::TailHasNext => write!(f, "tail has a next link"),
+            ValidationError::BrokenLink { index } => {
+                write!(f, "node {} links to node {}, whose prev does not point back", index, index + 1)
+            }
+            ValidationError::TailNotLast { count } => {
+                write!(f, "tail is not the last of the {} nodes reachable from head", count)
+            }
+            ValidationError::CountMismatch { counted, len } => {
+                write!(f, "{} nodes reachable from head but the length is {}", counted, len)
+            }
+            ValidationError::BrokenLane { lane } => write!(f, "express lane {} does not match the list", lane),
+            ValidationError::LabelOrder { index } => write!(f, "node {} is not labelled above the node before it", index),
+            ValidationError::BadChunk { index } => write!(f, "chunk {} is empty, overfull or has a stray handle", index),
+            ValidationError::RingNotClosed { len } => write!(f, "{} steps from current do not lead back to it", len),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use crate::enums::{HandleError, Side};
+use crate::linked_list::DoublyLinkedList;
+use crate::node::Node;
+
+// One operation of a fuzz program. Every step is two bytes, an opcode and an operand; operands
+// that pick a node are reduced modulo the list length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    PushFirst,
+    PushBack,
+    PopFirst,
+    PopBack,
+    Insert(Side, u8),
+    Remove(u8),
+    Clear,
+    // Reuse a handle whose node was removed, popped or cleared. The list must refuse these and
+    // stay unchanged.
+    InsertStale(Side, u8),
+    RemoveStale(u8),
+}
+
+pub const STEP_KINDS: u8 = 10;
+
+impl Step {
+    pub fn decode(opcode: u8, operand: u8) -> Step {
+        match opcode % STEP_KINDS {
+            0 => Step::PushFirst,
+            1 => Step::PushBack,
+            2 => Step::PopFirst,
+            3 => Step::PopBack,
+            4 => Step::Insert(Side::Before, operand),
+            5 => Step::Insert(Side::After, operand),
+            6 => Step::Remove(operand),
+            7 => Step::Clear,
+            8 => Step::InsertStale(if operand & 1 == 0 { Side::Before } else { Side::After }, operand >> 1),
+            _ => Step::RemoveStale(operand),
+        }
+    }
+}
+
+pub fn decode(data: &[u8]) -> Vec<Step> {
+    return data.chunks_exact(2).map(|pair| Step::decode(pair[0], pair[1])).collect();
+}
+
+// Runs a program against the list and a VecDeque model, checking the list after every step.
+pub struct Harness {
+    // Handles to the nodes in the list, in list order.
+    handles: Vec<Rc<RefCell<Node<u64>>>>,
+    list: DoublyLinkedList<u64>,
+    model: VecDeque<u64>,
+    // Handles kept after their node left the list.
+    retired: Vec<Rc<RefCell<Node<u64>>>>,
+    next_value: u64,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Harness {
+            handles: Vec::new(),
+            list: DoublyLinkedList::new(),
+            model: VecDeque::new(),
+            retired: Vec::new(),
+            next_value: 0,
+        }
+    }
+
+    fn value(&mut self) -> u64 {
+        self.next_value += 1;
+        return self.next_value;
+    }
+
+    pub fn apply(&mut self, step: Step) {
+        let len = self.handles.len();
+        match step {
+            Step::PushFirst => {
+                let value = self.value();
+                self.handles.insert(0, self.list.push_first(value));
+                self.model.push_front(value);
+            }
+            Step::PushBack => {
+                let value = self.value();
+                self.handles.push(self.list.push_back(value));
+                self.model.push_back(value);
+            }
+            Step::PopFirst => {
+                if len > 0 {
+                    self.retired.push(self.handles.remove(0));
+                }
+                assert_eq!(self.list.pop_first(), self.model.pop_front(), "pop_first returned the wrong value");
+            }
+            Step::PopBack => {
+                self.retired.extend(self.handles.pop());
+                assert_eq!(self.list.pop_back(), self.model.pop_back(), "pop_back returned the wrong value");
+            }
+            Step::Insert(side, operand) => {
+                if len == 0 {
+                    return;
+                }
+                let index = operand as usize % len;
+                let value = self.value();
+                let node = self.list.insert(side, value, self.handles[index].clone());
+                let at = if side == Side::Before { index } else { index + 1 };
+                self.handles.insert(at, node);
+                self.model.insert(at, value);
+            }
+            Step::Remove(operand) => {
+                if len == 0 {
+                    return;
+                }
+                let index = operand as usize % len;
+                let node = self.handles.remove(index);
+                self.retired.push(node.clone());
+                assert_eq!(self.list.remove(node), self.model.remove(index), "remove returned the wrong value");
+            }
+            Step::Clear => {
+                self.retired.append(&mut self.handles);
+                self.model.clear();
+                self.list.clear();
+            }
+            Step::InsertStale(side, operand) => {
+                if self.retired.is_empty() {
+                    return;
+                }
+                let stale = self.retired[operand as usize % self.retired.len()].clone();
+                let value = self.value();
+                assert_eq!(self.list.try_insert(side, value, stale).err(), Some(HandleError::Stale), "insert accepted a stale handle");
+            }
+            Step::RemoveStale(operand) => {
+                if self.retired.is_empty() {
+                    return;
+                }
+                let stale = self.retired[operand as usize % self.retired.len()].clone();
+                assert_eq!(self.list.remove(stale), None, "remove accepted a stale handle");
+            }
+        }
+    }
+
+    // Validates the list, then walks it and compares it with the model and the handles.
+    pub fn check(&self) -> Result<(), String> {
+        self.list.validate().map_err(|e| e.to_string())?;
+        if self.list.len() != self.model.len() {
+            return Err(format!("list has {} nodes, expected {}", self.list.len(), self.model.len()));
+        }
+
+        let mut current = self.list.head.clone();
+        for (index, value) in self.model.iter().enumerate() {
+            let node = current.expect("validate checked the length");
+            if !Rc::ptr_eq(&node, &self.handles[index]) {
+                return Err(format!("node {} is not the node its handle points at", index));
+            }
+            let borrow = node.borrow();
+            if *borrow.data() != *value {
+                return Err(format!("node {} holds {}, expected {}", index, *borrow.data(), value));
+            }
+            current = borrow.next.clone();
+        }
+
+        return Ok(());
+    }
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Entry point for the fuzz targets: panics on the first step that leaves the list inconsistent.
+pub fn run(data: &[u8]) {
+    let mut harness = Harness::new();
+    for (i, step) in decode(data).into_iter().enumerate() {
+        harness.apply(step);
+        if let Err(message) = harness.check() {
+            panic!("step {} ({:?}): {}", i, step, message);
+        }
+    }
+}
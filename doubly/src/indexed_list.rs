@@ -0,0 +1,483 @@
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::enums::{HandleError, Side, ValidationError};
+use crate::linked_list::DoublyLinkedList;
+use crate::node::{Node, NodeRef};
+use crate::workload::Rng;
+
+// Lanes a single tower may reach. With one tower in two nodes reaching each lane, that covers
+// far more nodes than fit in memory.
+const MAX_LANES: usize = 32;
+
+// The tower a lane starts from, or `None` for the head of the lane.
+type TowerLink<T> = Option<Rc<RefCell<Tower<T>>>>;
+
+// Express lanes above one node of the base list. Lane `l` links the towers that reach it;
+// `span[l]` counts base steps to the next tower in that lane, or to one past the tail when
+// there is none.
+struct Tower<T> {
+    node: Rc<RefCell<Node<T>>>,
+    next: Vec<TowerLink<T>>,
+    prev: Vec<TowerLink<T>>,
+    span: Vec<usize>,
+}
+
+// A `DoublyLinkedList` with skip-list lanes over its nodes, giving expected O(log n) positional
+// access. Positions count the head of every lane as 0 and the node at index `i` as `i + 1`.
+//
+// Handle-based `insert` and `remove`, and the pushes and pops, stay O(1): they give the node its
+// tower but leave the lanes alone and mark them stale. The next positional operation relinks
+// every tower in one O(n) pass, after which positional operations cost expected O(log n) again.
+// `insert_at` and `remove_at` keep the lanes current.
+pub struct IndexedList<T> {
+    list: DoublyLinkedList<T>,
+    towers: HashMap<*const RefCell<Node<T>>, Rc<RefCell<Tower<T>>>>,
+    // The first tower of every lane and the position it sits at. Behind cells so that lookups
+    // through `&self` can relink stale lanes.
+    first: RefCell<Vec<TowerLink<T>>>,
+    first_span: RefCell<Vec<usize>>,
+    stale: Cell<bool>,
+    rng: Rng,
+}
+
+impl<T> IndexedList<T> {
+    pub fn new() -> Self {
+        return IndexedList::with_seed(0x5EED);
+    }
+
+    // The seed only decides tower heights, which makes the layout reproducible.
+    pub fn with_seed(seed: u64) -> Self {
+        return IndexedList {
+            list: DoublyLinkedList::new(),
+            towers: HashMap::new(),
+            first: RefCell::new(Vec::new()),
+            first_span: RefCell::new(Vec::new()),
+            stale: Cell::new(false),
+            rng: Rng::new(seed),
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.list.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.list.is_empty();
+    }
+
+    pub fn push_first(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_first(data);
+        self.build(&node);
+        return node;
+    }
+
+    pub fn push_back(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_back(data);
+        self.build(&node);
+        return node;
+    }
+
+    pub fn insert(&mut self, side: Side, data: T, anchor: Rc<RefCell<Node<T>>>) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.insert(side, data, anchor);
+        self.build(&node);
+        return node;
+    }
+
+    // Panics if `index > len`, like `Vec::insert`.
+    pub fn insert_at(&mut self, index: usize, data: T) -> Rc<RefCell<Node<T>>> {
+        assert!(index <= self.len(), "insertion index {} is out of bounds for length {}", index, self.len());
+        // `node_at` skips relinking when appending, but `raise` needs current lanes either way.
+        self.relink();
+        let node = match self.node_at(index) {
+            Some(anchor) => self.list.insert(Side::Before, data, anchor),
+            None => self.list.push_back(data),
+        };
+        self.raise(&node);
+        return node;
+    }
+
+    pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
+        if self.list.check_linked(&node) == Err(HandleError::Stale) {
+            return None;
+        }
+        self.demolish(&node);
+        let data = self.list.remove(node);
+        self.debug_validate();
+        return data;
+    }
+
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let node = self.node_at(index)?;
+        self.lower(&node);
+        let data = self.list.remove(node);
+        self.debug_validate();
+        return data;
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        let node = self.list.head.clone()?;
+        return self.remove(node);
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let node = self.list.tail.clone()?;
+        return self.remove(node);
+    }
+
+    pub fn get<'a>(&self, node: &'a Rc<RefCell<Node<T>>>) -> Ref<'a, T> {
+        return self.list.get(node);
+    }
+
+    pub fn get_mut<'a>(&mut self, node: &'a Rc<RefCell<Node<T>>>) -> RefMut<'a, T> {
+        return self.list.get_mut(node);
+    }
+
+    pub fn get_nth(&self, index: usize) -> Option<NodeRef<T>> {
+        return self.node_at(index).map(NodeRef::new);
+    }
+
+    pub fn handle_at(&self, index: usize) -> Option<Rc<RefCell<Node<T>>>> {
+        return self.node_at(index);
+    }
+
+    // Index of `node`, or `None` if it is not linked in this list.
+    pub fn rank_of(&self, node: &Rc<RefCell<Node<T>>>) -> Option<usize> {
+        if self.list.check_linked(node) == Err(HandleError::Stale) {
+            return None;
+        }
+        self.relink();
+        let (_, position) = self.predecessors(node);
+        return Some(position - 1);
+    }
+
+    pub fn clear(&mut self) {
+        // Towers link to each other both ways, so the cycles have to be cut by hand.
+        for tower in self.towers.values() {
+            let mut tower = tower.borrow_mut();
+            tower.next.clear();
+            tower.prev.clear();
+        }
+        self.towers.clear();
+        self.first.get_mut().clear();
+        self.first_span.get_mut().clear();
+        self.stale.set(false);
+        self.list.clear();
+    }
+
+    // Checks the base list, then every lane against the positions of the nodes it links. Stale
+    // lanes are relinked first.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.list.validate()?;
+        self.relink();
+
+        let mut positions = HashMap::new();
+        let mut current = self.list.head.clone();
+        while let Some(node) = current {
+            positions.insert(Rc::as_ptr(&node), positions.len() + 1);
+            current = node.borrow().next.clone();
+        }
+
+        let mut reaching = vec![0; self.lanes()];
+        for tower in self.towers.values() {
+            let height = tower.borrow().next.len();
+            if height == 0 || height > self.lanes() {
+                return Err(ValidationError::BrokenLane { lane: height });
+            }
+            for count in &mut reaching[..height] {
+                *count += 1;
+            }
+        }
+
+        for (lane, &reach) in reaching.iter().enumerate() {
+            let (mut pred, mut position, mut count): (TowerLink<T>, usize, usize) = (None, 0, 0);
+            loop {
+                let next = self.next(&pred, lane);
+                let expected = match &next {
+                    Some(tower) => {
+                        let node = tower.borrow().node.clone();
+                        let registered = self.towers.get(&Rc::as_ptr(&node)).is_some_and(|t| Rc::ptr_eq(t, tower));
+                        let linked_back = match (tower.borrow().prev.get(lane), &pred) {
+                            (Some(Some(prev)), Some(pred)) => Rc::ptr_eq(prev, pred),
+                            (Some(None), None) => true,
+                            _ => false,
+                        };
+                        match positions.get(&Rc::as_ptr(&node)) {
+                            Some(&at) if registered && linked_back => at,
+                            _ => return Err(ValidationError::BrokenLane { lane }),
+                        }
+                    }
+                    None => self.len() + 1,
+                };
+                if position + self.span(&pred, lane) != expected {
+                    return Err(ValidationError::BrokenLane { lane });
+                }
+                match next {
+                    Some(tower) => {
+                        pred = Some(tower);
+                        position = expected;
+                        count += 1;
+                    }
+                    None => break,
+                }
+            }
+            if count != reach {
+                return Err(ValidationError::BrokenLane { lane });
+            }
+        }
+
+        return Ok(());
+    }
+
+    // The node at `index`: down the lanes from the top, then the last few steps in the base list.
+    fn node_at(&self, index: usize) -> Option<Rc<RefCell<Node<T>>>> {
+        if index >= self.len() {
+            return None;
+        }
+        self.relink();
+        let target = index + 1;
+        let (mut pred, mut position): (TowerLink<T>, usize) = (None, 0);
+        for lane in (0..self.lanes()).rev() {
+            while let Some(next) = self.next(&pred, lane) {
+                let span = self.span(&pred, lane);
+                if position + span > target {
+                    break;
+                }
+                position += span;
+                pred = Some(next);
+            }
+        }
+
+        let mut current = match pred {
+            Some(tower) => tower.borrow().node.clone(),
+            None => {
+                position = 1;
+                self.list.head.clone()?
+            }
+        };
+        for _ in position..target {
+            let next = current.borrow().next.clone()?;
+            current = next;
+        }
+        return Some(current);
+    }
+
+    // For every lane, the last tower before `node` and its distance to `node`, plus the position
+    // of `node`. Walks back through the base list to the nearest tower, then climbs.
+    fn predecessors(&self, node: &Rc<RefCell<Node<T>>>) -> (Vec<(TowerLink<T>, usize)>, usize) {
+        let mut pred: TowerLink<T> = None;
+        let mut distance = 1;
+        let mut current = node.borrow().prev.clone();
+        while let Some(node) = current {
+            if let Some(tower) = self.towers.get(&Rc::as_ptr(&node)) {
+                pred = Some(tower.clone());
+                break;
+            }
+            current = node.borrow().prev.clone();
+            distance += 1;
+        }
+
+        let mut preds = Vec::with_capacity(self.lanes());
+        for lane in 0..self.lanes() {
+            // Follow the lane below back to a tower tall enough for this lane. Every tower
+            // reaches lane 0, so this only runs from lane 1 up.
+            while let Some(tower) = pred.clone() {
+                if tower.borrow().next.len() > lane {
+                    break;
+                }
+                let prev = tower.borrow().prev[lane - 1].clone();
+                distance += self.span(&prev, lane - 1);
+                pred = prev;
+            }
+            preds.push((pred.clone(), distance));
+        }
+
+        let mut position = distance;
+        if let Some(top) = self.lanes().checked_sub(1) {
+            while let Some(tower) = pred {
+                let prev = tower.borrow().prev[top].clone();
+                position += self.span(&prev, top);
+                pred = prev;
+            }
+        }
+        return (preds, position);
+    }
+
+    // A random height for a new node's tower, and lanes enough to hold it. `None` when the node
+    // gets no tower at all.
+    fn new_tower(&mut self, node: &Rc<RefCell<Node<T>>>) -> Option<Rc<RefCell<Tower<T>>>> {
+        let height = (self.rng.next_u64().trailing_ones() as usize).min(MAX_LANES);
+        if height == 0 {
+            return None;
+        }
+        let (first, first_span) = (self.first.get_mut(), self.first_span.get_mut());
+        while first.len() < height {
+            // One past the old tail, as the new node isn't counted in any lane yet.
+            first.push(None);
+            first_span.push(self.list.len());
+        }
+        let tower = Rc::new(RefCell::new(Tower {
+            node: node.clone(),
+            next: vec![None; height],
+            prev: vec![None; height],
+            span: vec![0; height],
+        }));
+        self.towers.insert(Rc::as_ptr(node), tower.clone());
+        return Some(tower);
+    }
+
+    // Gives a node that was just linked into the base list a tower without linking it into the
+    // lanes, which go stale until `relink`.
+    fn build(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        self.new_tower(node);
+        self.stale.set(true);
+        self.debug_validate();
+    }
+
+    // Takes the tower of a node that is about to leave the base list without fixing the lanes,
+    // which go stale until `relink`. Clearing the tower's own links keeps it out of any cycle
+    // until `relink` drops the links to it.
+    fn demolish(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        if let Some(tower) = self.towers.remove(&Rc::as_ptr(node)) {
+            let mut tower = tower.borrow_mut();
+            tower.next.clear();
+            tower.prev.clear();
+        }
+        self.stale.set(true);
+    }
+
+    // Relinks every tower in stale lanes with one walk down the base list.
+    fn relink(&self) {
+        if !self.stale.get() {
+            return;
+        }
+        self.stale.set(false);
+        let lanes = self.lanes();
+        self.first.borrow_mut().iter_mut().for_each(|first| *first = None);
+        let mut last: Vec<(TowerLink<T>, usize)> = vec![(None, 0); lanes];
+        let mut position = 0;
+        let mut current = self.list.head.clone();
+        while let Some(node) = current {
+            position += 1;
+            if let Some(tower) = self.towers.get(&Rc::as_ptr(&node)) {
+                let height = tower.borrow().next.len();
+                for (lane, (pred, at)) in last.iter_mut().enumerate().take(height) {
+                    self.set_next(pred, lane, Some(tower.clone()));
+                    self.set_span(pred, lane, position - *at);
+                    tower.borrow_mut().prev[lane] = pred.clone();
+                    *pred = Some(tower.clone());
+                    *at = position;
+                }
+            }
+            current = node.borrow().next.clone();
+        }
+        for (lane, (pred, at)) in last.iter().enumerate() {
+            self.set_next(pred, lane, None);
+            self.set_span(pred, lane, position + 1 - at);
+        }
+    }
+
+    // Gives a node that was just linked into the base list a random tower and fixes the spans
+    // of the lanes passing over it. The lanes must be current.
+    fn raise(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let tower = self.new_tower(node);
+        let height = tower.as_ref().map_or(0, |tower| tower.borrow().next.len());
+        let (preds, _) = self.predecessors(node);
+        for (lane, (pred, distance)) in preds.into_iter().enumerate() {
+            let span = self.span(&pred, lane);
+            match &tower {
+                Some(tower) if lane < height => {
+                    let next = self.next(&pred, lane);
+                    if let Some(next) = &next {
+                        next.borrow_mut().prev[lane] = Some(tower.clone());
+                    }
+                    let mut new_tower = tower.borrow_mut();
+                    new_tower.next[lane] = next;
+                    new_tower.prev[lane] = pred.clone();
+                    new_tower.span[lane] = span + 1 - distance;
+                    drop(new_tower);
+                    self.set_next(&pred, lane, Some(tower.clone()));
+                    self.set_span(&pred, lane, distance);
+                }
+                _ => self.set_span(&pred, lane, span + 1),
+            }
+        }
+        self.debug_validate();
+    }
+
+    // Takes the tower of a node that is about to leave the base list out of every lane. The
+    // lanes must be current.
+    fn lower(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let (preds, _) = self.predecessors(node);
+        let tower = self.towers.remove(&Rc::as_ptr(node));
+        for (lane, (pred, _)) in preds.into_iter().enumerate() {
+            let span = self.span(&pred, lane);
+            match &tower {
+                Some(tower) if tower.borrow().next.len() > lane => {
+                    let tower = tower.borrow();
+                    let next = tower.next[lane].clone();
+                    if let Some(next) = &next {
+                        next.borrow_mut().prev[lane] = pred.clone();
+                    }
+                    self.set_next(&pred, lane, next);
+                    self.set_span(&pred, lane, span + tower.span[lane] - 1);
+                }
+                _ => self.set_span(&pred, lane, span - 1),
+            }
+        }
+    }
+
+    fn lanes(&self) -> usize {
+        return self.first.borrow().len();
+    }
+
+    fn next(&self, pred: &TowerLink<T>, lane: usize) -> TowerLink<T> {
+        return match pred {
+            Some(tower) => tower.borrow().next[lane].clone(),
+            None => self.first.borrow()[lane].clone(),
+        };
+    }
+
+    fn span(&self, pred: &TowerLink<T>, lane: usize) -> usize {
+        return match pred {
+            Some(tower) => tower.borrow().span[lane],
+            None => self.first_span.borrow()[lane],
+        };
+    }
+
+    fn set_next(&self, pred: &TowerLink<T>, lane: usize, next: TowerLink<T>) {
+        match pred {
+            Some(tower) => tower.borrow_mut().next[lane] = next,
+            None => self.first.borrow_mut()[lane] = next,
+        }
+    }
+
+    fn set_span(&self, pred: &TowerLink<T>, lane: usize, span: usize) {
+        match pred {
+            Some(tower) => tower.borrow_mut().span[lane] = span,
+            None => self.first_span.borrow_mut()[lane] = span,
+        }
+    }
+
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    fn debug_validate(&self) {
+        if let Err(error) = self.validate() {
+            panic!("list is corrupted: {}", error);
+        }
+    }
+
+    #[cfg(not(any(debug_assertions, feature = "validate")))]
+    fn debug_validate(&self) {}
+}
+
+impl<T> Default for IndexedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for IndexedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -0,0 +1,24 @@
+pub mod enums;
+pub mod linked_list;
+pub mod indexed_list;
+pub mod ordered_list;
+pub mod unrolled_list;
+pub mod circular_list;
+pub mod persistent_deque;
+pub mod snapshot;
+pub mod transaction;
+pub mod undoable_list;
+pub mod node;
+#[path = "../../bench/stats.rs"]
+pub mod stats;
+#[path = "../../bench/compare.rs"]
+pub mod compare;
+#[path = "../../bench/report.rs"]
+pub mod report;
+pub mod trace;
+#[path = "../../bench/workload.rs"]
+pub mod workload;
+#[path = "../../bench/metadata.rs"]
+pub mod metadata;
+pub mod fuzz;
+mod tests;
@@ -1,12 +1,20 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-use crate::enums::Side;
-use crate::node::Node;
+use std::cell::{Ref, RefCell, RefMut};
+use std::rc::{Rc, Weak};
+use crate::enums::{HandleError, Side, ValidationError};
+use crate::node::{new_list_id, Node, NodeRef, NodeRefMut};
+use crate::snapshot::{Snapshot, SnapshotState};
+use crate::transaction::Transaction;
+
+// The nodes on either side of a detached node.
+pub(crate) type Neighbours<T> = (Option<Rc<RefCell<Node<T>>>>, Option<Rc<RefCell<Node<T>>>>);
 
 #[derive(Debug)]
 pub struct DoublyLinkedList<T> {
     pub(crate) head: Option<Rc<RefCell<Node<T>>>>,
     pub(crate) tail: Option<Rc<RefCell<Node<T>>>>,
+    len: usize,
+    snapshots: RefCell<Vec<Weak<SnapshotState<T>>>>,
+    id: usize,
 }
 
 impl<T> DoublyLinkedList<T> {
@@ -14,11 +22,28 @@ impl<T> DoublyLinkedList<T> {
         DoublyLinkedList {
             head: None,
             tail: None,
+            len: 0,
+            snapshots: RefCell::new(Vec::new()),
+            id: new_list_id(),
         }
     }
 
-    pub fn insert(&mut self, side: Side, data: T, anchor: Rc<RefCell<Node<T>>>) {
-        let new_node = Rc::new(RefCell::new(Node::new(data)));
+    fn new_node(&self, data: T) -> Rc<RefCell<Node<T>>> {
+        let mut node = Node::new(data);
+        node.list = self.id;
+        return Rc::new(RefCell::new(node));
+    }
+
+    // Panics if `anchor` is no longer linked, e.g. because it was removed or the list cleared.
+    pub fn insert(&mut self, side: Side, data: T, anchor: Rc<RefCell<Node<T>>>) -> Rc<RefCell<Node<T>>> {
+        if self.check_linked(&anchor) == Err(HandleError::Stale) {
+            panic!("the anchor is not linked in the list");
+        }
+        match side {
+            Side::Before => self.touch_prev(&anchor),
+            Side::After => self.touch(&anchor),
+        }
+        let new_node = self.new_node(data);
 
         match side {
             Side::Before => {
@@ -33,7 +58,7 @@ impl<T> DoublyLinkedList<T> {
                 } else {
                     self.head = Some(new_node.clone());                 }
 
-                                anchor_borrow.prev = Some(new_node);
+                                anchor_borrow.prev = Some(new_node.clone());
             }
             Side::After => {
                 let mut anchor_borrow = anchor.borrow_mut();
@@ -47,53 +72,224 @@ impl<T> DoublyLinkedList<T> {
                 } else {
                     self.tail = Some(new_node.clone());                 }
 
-                                anchor_borrow.next = Some(new_node);
+                                anchor_borrow.next = Some(new_node.clone());
             }
         }
+
+        self.len += 1;
+        self.debug_validate();
+        return new_node;
     }
 
-    pub fn push_front(&mut self, data: T) {
+    pub fn push_front(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
         if let Some(head) = self.head.clone() {
-            self.insert(Side::Before, data, head);
+            return self.insert(Side::Before, data, head);
         } else {
-            let new_node = Rc::new(RefCell::new(Node::new(data)));
+            let new_node = self.new_node(data);
             self.head = Some(new_node.clone());
-            self.tail = Some(new_node);
+            self.tail = Some(new_node.clone());
+            self.len += 1;
+            self.debug_validate();
+            return new_node;
         }
     }
 
-    pub fn push_back(&mut self, data: T) {
+    // Same as `push_front`, under the name the pool crate and `pop_first` use.
+    pub fn push_first(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        return self.push_front(data);
+    }
+
+    pub fn push_back(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
         if let Some(tail) = self.tail.clone() {
-            self.insert(Side::After, data, tail);
+            return self.insert(Side::After, data, tail);
         } else {
-            let new_node = Rc::new(RefCell::new(Node::new(data)));
+            let new_node = self.new_node(data);
             self.head = Some(new_node.clone());
-            self.tail = Some(new_node);
+            self.tail = Some(new_node.clone());
+            self.len += 1;
+            self.debug_validate();
+            return new_node;
         }
     }
 
-        pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
-        let node_borrow = node.borrow();
-        let (_prev, next) = (node_borrow.prev.clone(), node_borrow.next.clone());
+    // Returns `None` if the node is no longer linked.
+    pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
+        if self.check_linked(&node) == Err(HandleError::Stale) {
+            return None;
+        }
+        self.detach(&node);
+        self.debug_validate();
 
-                if let Some(prev) = _prev.clone() {
-            prev.borrow_mut().next = next.clone();
-        } else {
-                        self.head = next.clone();
+        // Other handles to the node may still be alive, so the payload is taken out rather than
+        // the node unwrapped. Reading it through one of them afterwards panics.
+        return node.borrow_mut().data.take();
+    }
+
+    // A node is linked if this list linked it last and its prev link points back at it or it is
+    // the head. Removed nodes and nodes left over from `clear` fail the second test, nodes of
+    // another list the first.
+    pub(crate) fn check_linked(&self, node: &Rc<RefCell<Node<T>>>) -> Result<(), HandleError> {
+        let node_borrow = node.try_borrow().map_err(|_| HandleError::Borrowed)?;
+        if node_borrow.list != self.id {
+            return Err(HandleError::Stale);
         }
-        if let Some(next) = next {
-            next.borrow_mut().prev = _prev.clone();
-        } else {
-                        self.tail = _prev.clone();
+        let prev = node_borrow.prev.clone();
+        drop(node_borrow);
+        let linked = match &prev {
+            Some(prev) => {
+                let prev_borrow = prev.try_borrow().map_err(|_| HandleError::Borrowed)?;
+                prev_borrow.next.as_ref().is_some_and(|next| Rc::ptr_eq(next, node))
+            }
+            None => self.head.as_ref().is_some_and(|head| Rc::ptr_eq(head, node)),
+        };
+        if !linked {
+            return Err(HandleError::Stale);
+        }
+        return Ok(());
+    }
+
+    // Unlinks `node` and clears its own links, but leaves the node to the caller. Returns its
+    // former neighbours, which `reattach` takes to put it back.
+    pub(crate) fn detach(&mut self, node: &Rc<RefCell<Node<T>>>) -> Neighbours<T> {
+        self.touch_prev(node);
+        self.touch(node);
+        let (prev, next) = {
+            let mut node_borrow = node.borrow_mut();
+            (node_borrow.prev.take(), node_borrow.next.take())
+        };
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = next.clone(),
+            None => self.head = next.clone(),
+        }
+        match &next {
+            Some(next) => next.borrow_mut().prev = prev.clone(),
+            None => self.tail = prev.clone(),
+        }
+        self.len -= 1;
+        return (prev, next);
+    }
+
+    // Links a detached node back in between `prev` and `next`, which must be adjacent, as they
+    // are right after `detach` returned them.
+    pub(crate) fn reattach(
+        &mut self,
+        node: &Rc<RefCell<Node<T>>>,
+        prev: Option<Rc<RefCell<Node<T>>>>,
+        next: Option<Rc<RefCell<Node<T>>>>,
+    ) {
+        if let Some(prev) = &prev {
+            self.touch(prev);
+        }
+        self.touch(node);
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = Some(node.clone()),
+            None => self.head = Some(node.clone()),
+        }
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(node.clone()),
+            None => self.tail = Some(node.clone()),
+        }
+        let mut node_borrow = node.borrow_mut();
+        node_borrow.prev = prev;
+        node_borrow.next = next;
+        drop(node_borrow);
+        self.len += 1;
+    }
+
+    // Borrow rules: `get` and `get_mut` borrow the payload of one node until the guard is
+    // dropped. Meanwhile every operation that has to relink that node panics: inserting next to
+    // it, removing it or a neighbour, or popping it. The `try_` variants return
+    // `HandleError::Borrowed` instead and leave the list unchanged. `get` panics if the node is
+    // mutably borrowed and `get_mut` if it is borrowed at all.
+    pub fn get<'a>(&self, node: &'a Rc<RefCell<Node<T>>>) -> Ref<'a, T> {
+        return Ref::map(node.borrow(), |node| node.data());
+    }
+
+    pub fn get_mut<'a>(&mut self, node: &'a Rc<RefCell<Node<T>>>) -> RefMut<'a, T> {
+        self.touch(node);
+        return RefMut::map(node.borrow_mut(), |node| node.data_mut());
+    }
+
+    pub fn front(&self) -> Option<Ref<'_, T>> {
+        return self.head.as_ref().map(|node| Ref::map(node.borrow(), |node| node.data()));
+    }
+
+    pub fn back(&self) -> Option<Ref<'_, T>> {
+        return self.tail.as_ref().map(|node| Ref::map(node.borrow(), |node| node.data()));
+    }
+
+    pub fn front_mut(&mut self) -> Option<RefMut<'_, T>> {
+        if let Some(head) = self.head.clone() {
+            self.touch(&head);
         }
+        return self.head.as_ref().map(|node| RefMut::map(node.borrow_mut(), |node| node.data_mut()));
+    }
+
+    pub fn back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        if let Some(tail) = self.tail.clone() {
+            self.touch(&tail);
+        }
+        return self.tail.as_ref().map(|node| RefMut::map(node.borrow_mut(), |node| node.data_mut()));
+    }
+
+    // Puts `data` in the node and returns what it held. The node stays where it is.
+    pub fn replace(&mut self, node: &Rc<RefCell<Node<T>>>, data: T) -> T {
+        self.touch(node);
+        return std::mem::replace(node.borrow_mut().data_mut(), data);
+    }
+
+    // Exchanges the payloads of two nodes without relinking either.
+    pub fn swap_data(&mut self, a: &Rc<RefCell<Node<T>>>, b: &Rc<RefCell<Node<T>>>) {
+        if !Rc::ptr_eq(a, b) {
+            self.touch(a);
+            self.touch(b);
+            std::mem::swap(a.borrow_mut().data_mut(), b.borrow_mut().data_mut());
+        }
+    }
+
+    pub fn next_of(&self, node: &Rc<RefCell<Node<T>>>) -> Option<Rc<RefCell<Node<T>>>> {
+        return node.borrow().next.clone();
+    }
+
+    pub fn prev_of(&self, node: &Rc<RefCell<Node<T>>>) -> Option<Rc<RefCell<Node<T>>>> {
+        return node.borrow().prev.clone();
+    }
+
+    pub fn try_get<'a>(&self, node: &'a Rc<RefCell<Node<T>>>) -> Result<Ref<'a, T>, HandleError> {
+        let node_borrow = node.try_borrow().map_err(|_| HandleError::Borrowed)?;
+        return Ok(Ref::map(node_borrow, |node| node.data()));
+    }
 
-                drop(node_borrow);
+    pub fn try_insert(
+        &mut self,
+        side: Side,
+        data: T,
+        anchor: Rc<RefCell<Node<T>>>,
+    ) -> Result<Rc<RefCell<Node<T>>>, HandleError> {
+        self.check_linked(&anchor)?;
+        let neighbour = {
+            let anchor_borrow = anchor.try_borrow_mut().map_err(|_| HandleError::Borrowed)?;
+            match side {
+                Side::Before => anchor_borrow.prev.clone(),
+                Side::After => anchor_borrow.next.clone(),
+            }
+        };
+        if let Some(neighbour) = &neighbour {
+            unborrowed(neighbour)?;
+        }
+        return Ok(self.insert(side, data, anchor));
+    }
 
-        return Some(Rc::try_unwrap(node)
-            .ok()
-            .expect("More than one strong reference exists")
-            .into_inner()
-            .data);
+    pub fn try_remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Result<T, HandleError> {
+        self.check_linked(&node)?;
+        let (prev, next) = {
+            let node_borrow = node.try_borrow_mut().map_err(|_| HandleError::Borrowed)?;
+            (node_borrow.prev.clone(), node_borrow.next.clone())
+        };
+        for neighbour in prev.iter().chain(next.iter()) {
+            unborrowed(neighbour)?;
+        }
+        return Ok(self.remove(node).expect("remove always returns the payload"));
     }
 
         pub fn pop_back(&mut self) -> Option<T> {
@@ -103,11 +299,266 @@ impl<T> DoublyLinkedList<T> {
     pub fn pop_first(&mut self) -> Option<T> {
         return self.head.clone().map(|head| self.remove(head)).flatten();
     }
+
+    // The node at `index`, walking from whichever end is closer.
+    fn node_at(&self, index: usize) -> Option<Rc<RefCell<Node<T>>>> {
+        if index >= self.len {
+            return None;
+        }
+        let mut current;
+        if index < self.len / 2 {
+            current = self.head.clone()?;
+            for _ in 0..index {
+                let next = current.borrow().next.clone()?;
+                current = next;
+            }
+        } else {
+            current = self.tail.clone()?;
+            for _ in index + 1..self.len {
+                let prev = current.borrow().prev.clone()?;
+                current = prev;
+            }
+        }
+        return Some(current);
+    }
+
+    pub fn handle_at(&self, index: usize) -> Option<Rc<RefCell<Node<T>>>> {
+        return self.node_at(index);
+    }
+
+    // The guards hold on to the node rather than borrow the list, so the list stays usable
+    // while they are alive. Like `get`/`get_mut`, they block relinking the node until dropped.
+    pub fn get_nth(&self, index: usize) -> Option<NodeRef<T>> {
+        return self.node_at(index).map(NodeRef::new);
+    }
+
+    pub fn get_nth_mut(&mut self, index: usize) -> Option<NodeRefMut<T>> {
+        let node = self.node_at(index)?;
+        self.touch(&node);
+        return Some(NodeRefMut::new(node));
+    }
+
+    // Inserts so that the new element ends up at `index`; `index == len` appends. Panics if
+    // `index > len`, like `Vec::insert`.
+    pub fn insert_at(&mut self, index: usize, data: T) -> Rc<RefCell<Node<T>>> {
+        assert!(index <= self.len, "insertion index {} is out of bounds for length {}", index, self.len);
+        return match self.node_at(index) {
+            Some(anchor) => self.insert(Side::Before, data, anchor),
+            None => self.push_back(data),
+        };
+    }
+
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let node = self.node_at(index)?;
+        return self.remove(node);
+    }
+
+    // Position of `node`, found by walking towards both ends at once and stopping at whichever
+    // is reached first. `node` must belong to this list.
+    pub fn index_of(&self, node: &Rc<RefCell<Node<T>>>) -> usize {
+        let (mut backward, mut forward) = (node.clone(), node.clone());
+        let mut steps = 0;
+        loop {
+            let prev = backward.borrow().prev.clone();
+            match prev {
+                Some(prev) => backward = prev,
+                None => return steps,
+            }
+            let next = forward.borrow().next.clone();
+            match next {
+                Some(next) => forward = next,
+                None => return self.len - 1 - steps,
+            }
+            steps += 1;
+        }
+    }
+
+    // A read-only view of the list as it is now. See `Snapshot` for what it costs the list.
+    pub fn snapshot(&self) -> Snapshot<T>
+    where
+        T: Clone,
+    {
+        let state = Rc::new(SnapshotState::new(&self.head, self.len, T::clone));
+        self.snapshots.borrow_mut().push(Rc::downgrade(&state));
+        return Snapshot::new(state);
+    }
+
+    // Runs `edits` against the list and keeps them only if it returns `Ok`. On `Err`, or if it
+    // panics, every edit is undone in reverse order and the list is back in its old state, with
+    // the same nodes in the same places.
+    pub fn transaction<R, E>(&mut self, edits: impl FnOnce(&mut Transaction<'_, T>) -> Result<R, E>) -> Result<R, E>
+    where
+        T: Clone,
+    {
+        let mut transaction = Transaction::new(self);
+        let result = edits(&mut transaction);
+        if result.is_ok() {
+            transaction.commit();
+        }
+        return result;
+    }
+
+    // Lets every live snapshot copy `node` before it changes. Snapshots that were dropped are
+    // forgotten on the way.
+    fn touch(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let snapshots = self.snapshots.get_mut();
+        if snapshots.is_empty() {
+            return;
+        }
+        snapshots.retain(|state| state.strong_count() > 0);
+        for state in snapshots.iter().filter_map(Weak::upgrade) {
+            state.save(node);
+        }
+    }
+
+    // Snapshots only follow `next` links, so relinking copies just the node whose `next`
+    // changes: the one before the gap.
+    fn touch_prev(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        if self.snapshots.get_mut().is_empty() {
+            return;
+        }
+        let prev = node.borrow().prev.clone();
+        if let Some(prev) = prev {
+            self.touch(&prev);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    // Unlinks node by node instead of popping: that neither recurses down the `next` chain nor
+    // panics on a node the caller still holds a handle to. Such a node just outlives the list.
+    pub fn clear(&mut self) {
+        if !self.snapshots.get_mut().is_empty() {
+            let mut current = self.head.clone();
+            while let Some(node) = current {
+                self.touch(&node);
+                current = node.borrow().next.clone();
+            }
+        }
+        let head = self.head.take();
+        self.tail = None;
+        self.len = 0;
+        free_chain(head);
+    }
+
+    // Walks the list from head and reports the first broken invariant: links at either end,
+    // next/prev pairs that do not agree, and a length that does not match the count.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.head.as_ref().is_some_and(|head| head.borrow().prev.is_some()) {
+            return Err(ValidationError::HeadHasPrev);
+        }
+        if self.tail.as_ref().is_some_and(|tail| tail.borrow().next.is_some()) {
+            return Err(ValidationError::TailHasNext);
+        }
+        // With no prev link on head and every back link checked, the walk cannot reach a node
+        // twice, so this also rules out cycles.
+        let mut last: Option<Rc<RefCell<Node<T>>>> = None;
+        let mut current = self.head.clone();
+        let mut index = 0;
+        while let Some(node) = current {
+            let next = node.borrow().next.clone();
+            if let Some(next) = &next {
+                let points_back = next.borrow().prev.as_ref().is_some_and(|prev| Rc::ptr_eq(prev, &node));
+                if !points_back {
+                    return Err(ValidationError::BrokenLink { index });
+                }
+            }
+            last = Some(node);
+            current = next;
+            index += 1;
+        }
+
+        let tail_is_last = match (&self.tail, &last) {
+            (None, None) => true,
+            (Some(tail), Some(last)) => Rc::ptr_eq(tail, last),
+            _ => false,
+        };
+        if !tail_is_last {
+            return Err(ValidationError::TailNotLast { count: index });
+        }
+        if index != self.len {
+            return Err(ValidationError::CountMismatch { counted: index, len: self.len });
+        }
+        return Ok(());
+    }
+
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    pub(crate) fn debug_validate(&self) {
+        if let Err(error) = self.validate() {
+            panic!("list is corrupted: {}", error);
+        }
+    }
+
+    #[cfg(not(any(debug_assertions, feature = "validate")))]
+    pub(crate) fn debug_validate(&self) {}
+}
+
+fn unborrowed<T>(node: &Rc<RefCell<Node<T>>>) -> Result<(), HandleError> {
+    return node.try_borrow_mut().map(|_| ()).map_err(|_| HandleError::Borrowed);
+}
+
+// Each node is unlinked from both neighbours before it is dropped. If a payload's `Drop`
+// panics, `Rest` frees the remainder of the chain while unwinding, so nothing is left behind
+// in a prev/next cycle.
+fn free_chain<T>(head: Option<Rc<RefCell<Node<T>>>>) {
+    struct Rest<T>(Option<Rc<RefCell<Node<T>>>>);
+
+    impl<T> Drop for Rest<T> {
+        fn drop(&mut self) {
+            free_chain(self.0.take());
+        }
+    }
+
+    let mut current = head;
+    while let Some(node) = current {
+        let next = node.borrow_mut().next.take();
+        if let Some(next) = &next {
+            next.borrow_mut().prev = None;
+        }
+        let mut rest = Rest(next);
+        drop(node);
+        current = rest.0.take();
+    }
 }
 
 impl<T> Drop for DoublyLinkedList<T> {
     fn drop(&mut self) {
-        while self.pop_back().is_some() {}
+        self.clear();
+    }
+}
+
+pub struct IntoIter<T>(DoublyLinkedList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        return self.0.pop_first();
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.0.len(), Some(self.0.len()));
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        return self.0.pop_back();
+    }
+}
+
+impl<T> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        return IntoIter(self);
     }
 }
 
@@ -1,10 +1,8 @@
-mod enums;
-mod linked_list;
-mod node;
-mod tests;
-
 use std::time::SystemTime;
+use doubly::{compare, linked_list, metadata, report, trace, unrolled_list, workload};
 
+const ELEMENT_COUNT: usize = 20_000_000;
+const RUNS: usize = 10;
 
 #[derive(Debug, Clone, Copy)]
 struct Complex {
@@ -23,7 +21,7 @@ struct User {
 fn test_i32_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
     let start_custom = SystemTime::now();
-    for i in 0..20_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(346);
     }
     let end_custom = SystemTime::now();
@@ -35,7 +33,7 @@ fn test_i32_doubly_linked_list() -> u128 {
 fn test_f64_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
     let start_custom = SystemTime::now();
-    for i in 0..20_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(364.3433);
     }
     let end_custom = SystemTime::now();
@@ -47,7 +45,7 @@ fn test_f64_doubly_linked_list() -> u128 {
 fn test_complex_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
     let start_custom = SystemTime::now();
-    for i in 0..20_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(Complex {
             real: 999.999,
             imag: 999.999,
@@ -62,7 +60,7 @@ fn test_complex_doubly_linked_list() -> u128 {
 fn test_user_doubly_linked_list() -> u128 {
     let mut custom_list = linked_list::DoublyLinkedList::new();
     let start_custom = SystemTime::now();
-    for i in 0..20_000_000 {
+    for i in 0..ELEMENT_COUNT {
         custom_list.push_back(User {
             id: 1,
             name: "John Doe".to_string(),
@@ -87,13 +85,13 @@ fn run_test() -> (u128, u128, u128, u128)
     return (i32_time, f64_time, complex_time, user_time);
 }
 
-fn main() {
+fn run_benchmark() {
         let mut i32_times = Vec::new();
     let mut f64_times = Vec::new();
     let mut complex_times = Vec::new();
     let mut user_times = Vec::new();
 
-    for i in 0..10 {
+    for i in 0..RUNS {
         let (i32_time, f64_time, complex_time, user_time) = run_test();
         i32_times.push(i32_time);
         f64_times.push(f64_time);
@@ -105,8 +103,375 @@ fn main() {
 
         let mut writer = csv::Writer::from_path("doubly_linked_list.csv").unwrap();
     writer.write_record(&["i32", "f64", "complex", "user"]).unwrap();
-    for i in 0..10 {
+    for i in 0..RUNS {
+        writer.write_record(&[i32_times[i].to_string(), f64_times[i].to_string(), complex_times[i].to_string(), user_times[i].to_string()]).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut metadata = metadata::Metadata::capture("benchmark");
+    metadata.set("element_count", ELEMENT_COUNT);
+    metadata.set("pool_size", 0);
+    metadata.set("runs", RUNS);
+    metadata.write_sidecar("doubly_linked_list.csv").unwrap();
+}
+
+fn time_replay<T>(ops: &[trace::Op], make: impl FnMut(u64) -> T) -> u128 {
+    let mut list = linked_list::DoublyLinkedList::new();
+    let start = SystemTime::now();
+    trace::replay(&mut list, ops, make);
+    let end = SystemTime::now();
+
+    return end.duration_since(start).unwrap().as_millis();
+}
+
+fn replay_usage() -> i32 {
+    eprintln!("usage: replay <trace> [--runs <n>] [--out <results.csv>]");
+    return 2;
+}
+
+// Replays a recorded trace once per payload type and run, writing timings in the same CSV
+// layout as the synthetic benchmark so `compare` and `report` can consume them.
+fn run_replay(args: &[String]) -> i32 {
+    let mut path = None;
+    let mut runs = 10;
+    let mut out = "replay.csv".to_string();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => match args.next().and_then(|v| v.parse::<usize>().ok()) {
+                Some(value) => runs = value,
+                None => return replay_usage(),
+            },
+            "--out" => match args.next() {
+                Some(value) => out = value.clone(),
+                None => return replay_usage(),
+            },
+            _ => path = Some(arg.clone()),
+        }
+    }
+    let Some(path) = path else {
+        return replay_usage();
+    };
+
+    let ops = match trace::load_trace(&path) {
+        Ok(ops) => ops,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 2;
+        }
+    };
+
+    let mut i32_times = Vec::new();
+    let mut f64_times = Vec::new();
+    let mut complex_times = Vec::new();
+    let mut user_times = Vec::new();
+
+    for i in 0..runs {
+        i32_times.push(time_replay(&ops, |id| id as i32));
+        f64_times.push(time_replay(&ops, |id| id as f64));
+        complex_times.push(time_replay(&ops, |id| Complex {
+            real: id as f64,
+            imag: id as f64,
+        }));
+        user_times.push(time_replay(&ops, |id| User {
+            id: id as i32,
+            name: "John Doe".to_string(),
+            age: 30,
+            email: "john@gmail.com".to_string(),
+        }));
+
+        println!("Replay {} done...", i + 1);
+    }
+
+    let mut writer = csv::Writer::from_path(&out).unwrap();
+    writer.write_record(["i32", "f64", "complex", "user"]).unwrap();
+    for i in 0..runs {
         writer.write_record(&[i32_times[i].to_string(), f64_times[i].to_string(), complex_times[i].to_string(), user_times[i].to_string()]).unwrap();
     }
     writer.flush().unwrap();
-}
\ No newline at end of file
+
+    let mut metadata = metadata::Metadata::capture("replay");
+    metadata.set("trace", &path);
+    metadata.set("ops", ops.len());
+    metadata.set("pool_size", 0);
+    metadata.set("runs", runs);
+    metadata.write_sidecar(&out).unwrap();
+
+    return 0;
+}
+
+// Times one workload against this crate's list and the std baselines. Each structure is
+// dropped after its timer stops, as in the synthetic benchmark.
+fn time_workload<T>(workload: &workload::Workload, mut make: impl FnMut(u64) -> T) -> [u128; 3] {
+    let mut list = linked_list::DoublyLinkedList::new();
+    let start = SystemTime::now();
+    trace::replay(&mut list, &workload.ops, &mut make);
+    let list_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+
+    let start = SystemTime::now();
+    let deque = workload::run_vec_deque(workload, &mut make);
+    let deque_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+    drop(deque);
+
+    let start = SystemTime::now();
+    let std_list = workload::run_std_linked_list(workload, &mut make);
+    let std_list_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+    drop(std_list);
+
+    return [list_time, deque_time, std_list_time];
+}
+
+fn workload_usage() -> i32 {
+    eprintln!(concat!(
+        "usage: workload [--seed <n>] [--initial <n>] [--ops <n>] [--mix <op=weight,...>]\n",
+        "                [--anchors uniform|head|tail|hot-spot[:center[:width]]] [--runs <n>]\n",
+        "                [--out <results.csv>] [--save-trace <trace>]"
+    ));
+    return 2;
+}
+
+// Generates a seeded mixed workload and runs it against every backend. The same seed and
+// options produce the same workload in both crates, so their CSVs are directly comparable.
+fn run_workload(args: &[String]) -> i32 {
+    let mut config = workload::WorkloadConfig {
+        seed: 42,
+        initial: 10_000,
+        ops: 100_000,
+        mix: workload::OpMix::default(),
+        anchors: workload::AnchorDistribution::Uniform,
+    };
+    let mut runs = 10;
+    let mut out = "workload.csv".to_string();
+    let mut save_trace = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            return workload_usage();
+        };
+        let parsed = match arg.as_str() {
+            "--seed" => value.parse().map(|v| config.seed = v).map_err(|_| value.clone()),
+            "--initial" => value.parse().map(|v| config.initial = v).map_err(|_| value.clone()),
+            "--ops" => value.parse().map(|v| config.ops = v).map_err(|_| value.clone()),
+            "--runs" => value.parse().map(|v| runs = v).map_err(|_| value.clone()),
+            "--mix" => workload::OpMix::parse(value).map(|v| config.mix = v),
+            "--anchors" => workload::AnchorDistribution::parse(value).map(|v| config.anchors = v),
+            "--out" => {
+                out = value.clone();
+                Ok(())
+            }
+            "--save-trace" => {
+                save_trace = Some(value.clone());
+                Ok(())
+            }
+            _ => return workload_usage(),
+        };
+        if let Err(e) = parsed {
+            eprintln!("error: {}: {}", arg, e);
+            return workload_usage();
+        }
+    }
+
+    let workload = workload::generate(&config);
+    if let Some(path) = save_trace {
+        let written = std::fs::File::create(&path)
+            .and_then(|file| trace::write_trace(std::io::BufWriter::new(file), &workload.ops));
+        if let Err(e) = written {
+            eprintln!("error: {}: {}", path, e);
+            return 2;
+        }
+    }
+
+    let mut rows = Vec::new();
+    for i in 0..runs {
+        let times = [
+            time_workload(&workload, |id| id as i32),
+            time_workload(&workload, |id| id as f64),
+            time_workload(&workload, |id| Complex {
+                real: id as f64,
+                imag: id as f64,
+            }),
+            time_workload(&workload, |id| User {
+                id: id as i32,
+                name: "John Doe".to_string(),
+                age: 30,
+                email: "john@gmail.com".to_string(),
+            }),
+        ];
+        rows.push(times.concat());
+
+        println!("Workload {} done...", i + 1);
+    }
+
+    let mut writer = csv::Writer::from_path(&out).unwrap();
+    let mut header = Vec::new();
+    for payload in ["i32", "f64", "complex", "user"] {
+        for backend in ["list", "vec_deque", "std_linked_list"] {
+            header.push(format!("{}_{}", backend, payload));
+        }
+    }
+    writer.write_record(&header).unwrap();
+    for row in rows {
+        writer.write_record(row.iter().map(|t| t.to_string())).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut metadata = metadata::Metadata::capture("workload");
+    metadata.set("seed", config.seed);
+    metadata.set("initial", config.initial);
+    metadata.set("ops", config.ops);
+    metadata.set("mix", config.mix);
+    metadata.set("anchors", config.anchors);
+    metadata.set("pool_size", 0);
+    metadata.set("runs", runs);
+    metadata.write_sidecar(&out).unwrap();
+
+    return 0;
+}
+
+// Times appending `count` elements, visiting every element in order, and `edits` insert_at/
+// remove_at pairs in the middle, first on the per-element list and then on the unrolled list.
+fn time_unrolled<T>(count: usize, edits: usize, mut make: impl FnMut(u64) -> T) -> [u128; 6] {
+    let mut list = linked_list::DoublyLinkedList::new();
+    let start = SystemTime::now();
+    for i in 0..count {
+        list.push_back(make(i as u64));
+    }
+    let list_push_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+
+    let start = SystemTime::now();
+    let mut current = list.handle_at(0);
+    while let Some(node) = current {
+        std::hint::black_box(&*list.get(&node));
+        current = list.next_of(&node);
+    }
+    let list_iter_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+
+    let start = SystemTime::now();
+    for i in 0..edits {
+        list.insert_at(count / 2, make(i as u64));
+        list.remove_at(count / 2 + 1);
+    }
+    let list_edit_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+    drop(list);
+
+    let mut unrolled = unrolled_list::UnrolledList::new();
+    let start = SystemTime::now();
+    for i in 0..count {
+        unrolled.push_back(make(i as u64));
+    }
+    let unrolled_push_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+
+    let start = SystemTime::now();
+    for value in unrolled.iter() {
+        std::hint::black_box(value);
+    }
+    let unrolled_iter_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+
+    let start = SystemTime::now();
+    for i in 0..edits {
+        unrolled.insert_at(count / 2, make(i as u64));
+        unrolled.remove_at(count / 2 + 1);
+    }
+    let unrolled_edit_time = SystemTime::now().duration_since(start).unwrap().as_millis();
+    drop(unrolled);
+
+    return [list_push_time, unrolled_push_time, list_iter_time, unrolled_iter_time, list_edit_time, unrolled_edit_time];
+}
+
+fn unrolled_usage() -> i32 {
+    eprintln!("usage: unrolled [--elements <n>] [--edits <n>] [--runs <n>] [--out <results.csv>]");
+    return 2;
+}
+
+// Compares the unrolled list with the per-element list for every payload type.
+fn run_unrolled(args: &[String]) -> i32 {
+    let mut count = 1_000_000;
+    let mut edits = 1_000;
+    let mut runs = 10;
+    let mut out = "unrolled.csv".to_string();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            return unrolled_usage();
+        };
+        let parsed = match arg.as_str() {
+            "--elements" => value.parse().map(|v| count = v).is_ok(),
+            "--edits" => value.parse().map(|v| edits = v).is_ok(),
+            "--runs" => value.parse().map(|v| runs = v).is_ok(),
+            "--out" => {
+                out = value.clone();
+                true
+            }
+            _ => return unrolled_usage(),
+        };
+        if !parsed {
+            eprintln!("error: {}: {}", arg, value);
+            return unrolled_usage();
+        }
+    }
+    if count == 0 {
+        eprintln!("error: --elements must be positive");
+        return unrolled_usage();
+    }
+
+    let mut rows = Vec::new();
+    for i in 0..runs {
+        let times = [
+            time_unrolled(count, edits, |id| id as i32),
+            time_unrolled(count, edits, |id| id as f64),
+            time_unrolled(count, edits, |id| Complex {
+                real: id as f64,
+                imag: id as f64,
+            }),
+            time_unrolled(count, edits, |id| User {
+                id: id as i32,
+                name: "John Doe".to_string(),
+                age: 30,
+                email: "john@gmail.com".to_string(),
+            }),
+        ];
+        rows.push(times.concat());
+
+        println!("Unrolled {} done...", i + 1);
+    }
+
+    let mut writer = csv::Writer::from_path(&out).unwrap();
+    let mut header = Vec::new();
+    for payload in ["i32", "f64", "complex", "user"] {
+        for phase in ["push", "iter", "edit"] {
+            for backend in ["list", "unrolled"] {
+                header.push(format!("{}_{}_{}", backend, phase, payload));
+            }
+        }
+    }
+    writer.write_record(&header).unwrap();
+    for row in rows {
+        writer.write_record(row.iter().map(|t| t.to_string())).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut metadata = metadata::Metadata::capture("unrolled");
+    metadata.set("element_count", count);
+    metadata.set("edits", edits);
+    metadata.set("chunk_capacity", unrolled_list::CHUNK_CAPACITY);
+    metadata.set("runs", runs);
+    metadata.write_sidecar(&out).unwrap();
+
+    return 0;
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("compare") => std::process::exit(compare::run(&args[1..])),
+        Some("report") => std::process::exit(report::run(&args[1..])),
+        Some("replay") => std::process::exit(run_replay(&args[1..])),
+        Some("workload") => std::process::exit(run_workload(&args[1..])),
+        Some("unrolled") => std::process::exit(run_unrolled(&args[1..])),
+        _ => run_benchmark(),
+    }
+}
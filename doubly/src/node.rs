@@ -1,20 +1,129 @@
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell, RefMut};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Ids start at 1, so 0 marks a node that was never linked.
+static NEXT_LIST_ID: AtomicUsize = AtomicUsize::new(1);
+
+// A fresh id for a list that links `Node`s, unique across every kind of such list.
+pub(crate) fn new_list_id() -> usize {
+    return NEXT_LIST_ID.fetch_add(1, Ordering::Relaxed);
+}
 
 #[derive(Debug)]
 pub struct Node<T> {
-    pub(crate) data: T,
+    // `None` once `remove` has handed the payload back, as other handles may keep the node.
+    pub(crate) data: Option<T>,
     pub(crate) prev: Option<Rc<RefCell<Node<T>>>>,
     pub(crate) next: Option<Rc<RefCell<Node<T>>>>,
+    // The id of the list that last linked the node, so a handle passed to another list is caught.
+    pub(crate) list: usize,
 }
 
 impl<T> Node<T> {
     pub fn new(data: T) -> Node<T> {
         return Node {
-            data,
+            data: Some(data),
             prev: None,
             next: None,
+            list: 0,
         };
     }
+
+    pub(crate) fn data(&self) -> &T {
+        return self.data.as_ref().expect("the node was removed from its list");
+    }
+
+    pub(crate) fn data_mut(&mut self) -> &mut T {
+        return self.data.as_mut().expect("the node was removed from its list");
+    }
 }
 
+// A borrow of a node's payload that owns a handle to the node, so it does not depend on the
+// list: removing the node while the guard is alive panics, like it does during `get`.
+pub struct NodeRef<T> {
+    // Declared before `node` so the borrow ends before the handle is released.
+    _borrow: Ref<'static, ()>,
+    data: *const T,
+    node: Rc<RefCell<Node<T>>>,
+}
+
+impl<T> NodeRef<T> {
+    pub(crate) fn new(node: Rc<RefCell<Node<T>>>) -> Self {
+        let borrow = node.borrow();
+        let data: *const T = borrow.data();
+        let borrow = Ref::map(borrow, |_| &());
+        // SAFETY: `node` keeps the cell alive and in place for as long as the guard exists, and
+        // `borrow` is dropped first. Until then the cell counts as borrowed, so nothing can get
+        // `data` mutably or take it out of the node.
+        let borrow = unsafe { std::mem::transmute::<Ref<'_, ()>, Ref<'static, ()>>(borrow) };
+        return NodeRef { _borrow: borrow, data, node };
+    }
+
+    pub fn handle(&self) -> &Rc<RefCell<Node<T>>> {
+        return &self.node;
+    }
+}
+
+impl<T> Deref for NodeRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: see `NodeRef::new`.
+        return unsafe { &*self.data };
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NodeRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Debug::fmt(&**self, f);
+    }
+}
+
+// The mutable counterpart of `NodeRef`.
+pub struct NodeRefMut<T> {
+    // Declared before `node` so the borrow ends before the handle is released.
+    _borrow: RefMut<'static, ()>,
+    data: *mut T,
+    node: Rc<RefCell<Node<T>>>,
+}
+
+impl<T> NodeRefMut<T> {
+    pub(crate) fn new(node: Rc<RefCell<Node<T>>>) -> Self {
+        let mut borrow = node.borrow_mut();
+        let data: *mut T = borrow.data_mut();
+        let borrow = RefMut::map(borrow, |_| Box::leak(Box::new(())));
+        // SAFETY: as for `NodeRef::new`. The cell counts as mutably borrowed, so `data` is the
+        // only way to reach the payload.
+        let borrow = unsafe { std::mem::transmute::<RefMut<'_, ()>, RefMut<'static, ()>>(borrow) };
+        return NodeRefMut { _borrow: borrow, data, node };
+    }
+
+    pub fn handle(&self) -> &Rc<RefCell<Node<T>>> {
+        return &self.node;
+    }
+}
+
+impl<T> Deref for NodeRefMut<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: see `NodeRefMut::new`.
+        return unsafe { &*self.data };
+    }
+}
+
+impl<T> DerefMut for NodeRefMut<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: see `NodeRefMut::new`.
+        return unsafe { &mut *self.data };
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for NodeRefMut<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Debug::fmt(&**self, f);
+    }
+}
@@ -0,0 +1,204 @@
+use std::cell::{Ref, RefCell, RefMut};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::enums::{Side, ValidationError};
+use crate::linked_list::DoublyLinkedList;
+use crate::node::Node;
+
+// Labels are taken from [0, LABELS).
+const LABELS: u64 = 1 << 63;
+
+// Density threshold of the relabelling: a block of 2^i labels may hold up to (2 / DENSITY)^i nodes.
+// Anything in (1, 2) works; lower values relabel less often but run out of labels sooner.
+const DENSITY: f64 = 1.5;
+
+// A `DoublyLinkedList` whose nodes carry increasing integer labels, so `compare` can order two
+// handles without walking the list.
+//
+// New nodes get the label halfway between their neighbours. When there is no room left, the
+// smallest aligned block of labels around the insertion point that is sparse enough is
+// relabelled evenly (Bender et al., "Two Simplified Algorithms for Maintaining Order in a
+// List"). `compare` is O(1); inserts are amortized O(log n).
+pub struct OrderedList<T> {
+    list: DoublyLinkedList<T>,
+    labels: HashMap<*const RefCell<Node<T>>, u64>,
+    relabelled: usize,
+}
+
+impl<T> OrderedList<T> {
+    pub fn new() -> Self {
+        return OrderedList {
+            list: DoublyLinkedList::new(),
+            labels: HashMap::new(),
+            relabelled: 0,
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.list.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.list.is_empty();
+    }
+
+    // Labels rewritten by relabelling so far, for checking the amortized cost.
+    pub fn relabelled(&self) -> usize {
+        return self.relabelled;
+    }
+
+    pub fn push_first(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_first(data);
+        self.label(&node);
+        return node;
+    }
+
+    pub fn push_back(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_back(data);
+        self.label(&node);
+        return node;
+    }
+
+    pub fn insert(&mut self, side: Side, data: T, anchor: Rc<RefCell<Node<T>>>) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.insert(side, data, anchor);
+        self.label(&node);
+        return node;
+    }
+
+    pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
+        // The label goes only once the node is out, so a removal that panics or is refused
+        // leaves the node labelled.
+        let key = Rc::as_ptr(&node);
+        let data = self.list.remove(node)?;
+        self.labels.remove(&key);
+        self.debug_validate();
+        return Some(data);
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        let node = self.list.head.clone()?;
+        return self.remove(node);
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let node = self.list.tail.clone()?;
+        return self.remove(node);
+    }
+
+    pub fn get<'a>(&self, node: &'a Rc<RefCell<Node<T>>>) -> Ref<'a, T> {
+        return self.list.get(node);
+    }
+
+    pub fn get_mut<'a>(&mut self, node: &'a Rc<RefCell<Node<T>>>) -> RefMut<'a, T> {
+        return self.list.get_mut(node);
+    }
+
+    // Orders two nodes of this list by their position. Panics if either is not in the list.
+    pub fn compare(&self, a: &Rc<RefCell<Node<T>>>, b: &Rc<RefCell<Node<T>>>) -> Ordering {
+        return self.label_of(a).cmp(&self.label_of(b));
+    }
+
+    pub fn clear(&mut self) {
+        self.labels.clear();
+        self.list.clear();
+    }
+
+    // Checks the base list, then that every node has a label greater than the one before it.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.list.validate()?;
+        if self.labels.len() != self.len() {
+            return Err(ValidationError::CountMismatch { counted: self.labels.len(), len: self.len() });
+        }
+        let mut previous = None;
+        let mut current = self.list.head.clone();
+        let mut index = 0;
+        while let Some(node) = current {
+            let label = self.labels.get(&Rc::as_ptr(&node)).copied();
+            if label.is_none() || label <= previous {
+                return Err(ValidationError::LabelOrder { index });
+            }
+            previous = label;
+            current = node.borrow().next.clone();
+            index += 1;
+        }
+        return Ok(());
+    }
+
+    fn label_of(&self, node: &Rc<RefCell<Node<T>>>) -> u64 {
+        return *self.labels.get(&Rc::as_ptr(node)).expect("node does not belong to this list");
+    }
+
+    fn neighbour_label(&self, node: &Option<Rc<RefCell<Node<T>>>>) -> Option<u64> {
+        return node.as_ref().map(|node| self.label_of(node));
+    }
+
+    // Labels a node that was just linked into the base list.
+    fn label(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let (prev, next) = {
+            let borrow = node.borrow();
+            (borrow.prev.clone(), borrow.next.clone())
+        };
+        let low = self.neighbour_label(&prev).map_or(0, |label| label + 1);
+        let high = self.neighbour_label(&next).unwrap_or(LABELS);
+        if low < high {
+            self.labels.insert(Rc::as_ptr(node), low + (high - low) / 2);
+        } else {
+            self.relabel(node, prev, next);
+        }
+        self.debug_validate();
+    }
+
+    // Finds the smallest aligned block of labels around `node` that stays under the density
+    // threshold with `node` added, and spreads the block's nodes evenly over it.
+    fn relabel(&mut self, node: &Rc<RefCell<Node<T>>>, prev: Option<Rc<RefCell<Node<T>>>>, next: Option<Rc<RefCell<Node<T>>>>) {
+        // A full gap means at least one neighbour exists.
+        let anchor = self.neighbour_label(&prev).or(self.neighbour_label(&next)).unwrap_or(0);
+        // The block grows outwards from `node`, so the nodes it covers so far stay covered.
+        let (mut first, mut before, mut after) = (node.clone(), prev, next);
+        let mut count = 1;
+        let mut bits = 0;
+        let (start, size) = loop {
+            bits += 1;
+            let size = 1u64 << bits;
+            let start = anchor & !(size - 1);
+            while let Some(neighbour) = before.clone().filter(|n| self.label_of(n) >= start) {
+                before = neighbour.borrow().prev.clone();
+                first = neighbour;
+                count += 1;
+            }
+            while let Some(neighbour) = after.clone().filter(|n| self.label_of(n) - start < size) {
+                after = neighbour.borrow().next.clone();
+                count += 1;
+            }
+            if size == LABELS || (count as f64) < (2.0 / DENSITY).powi(bits) {
+                break (start, size);
+            }
+        };
+
+        let mut current = Some(first);
+        for k in 0..count {
+            let node = current.expect("the block holds `count` nodes");
+            let label = start + (k as u128 * size as u128 / count as u128) as u64;
+            self.labels.insert(Rc::as_ptr(&node), label);
+            current = node.borrow().next.clone();
+        }
+        self.relabelled += count;
+    }
+
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    fn debug_validate(&self) {
+        if let Err(error) = self.validate() {
+            panic!("list is corrupted: {}", error);
+        }
+    }
+
+    #[cfg(not(any(debug_assertions, feature = "validate")))]
+    fn debug_validate(&self) {}
+}
+
+impl<T> Default for OrderedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
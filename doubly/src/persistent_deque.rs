@@ -0,0 +1,230 @@
+use std::fmt;
+use std::rc::Rc;
+use crate::enums::ValidationError;
+use crate::linked_list::DoublyLinkedList;
+
+// Each side may hold up to BALANCE times the other side's length, plus one.
+const BALANCE: usize = 3;
+
+type Link<T> = Option<Rc<Cons<T>>>;
+
+struct Cons<T> {
+    data: T,
+    next: Link<T>,
+}
+
+// An immutable deque. `push_*` and `pop_*` leave `self` untouched and return a new version that
+// shares every node it can with the old one, so keeping old versions around is cheap and each
+// one can still be read and iterated.
+//
+// The elements sit in two singly linked stacks: `front` in order and `back` reversed. When one
+// side grows past `BALANCE` times the other, both are rebuilt around the middle. That costs
+// O(n) but happens at most once every O(n) operations along a single line of versions, so the
+// ends cost amortized O(1). Repeatedly popping the same old version can hit the rebuild every
+// time.
+pub struct PersistentDeque<T> {
+    front: Link<T>,
+    front_len: usize,
+    back: Link<T>,
+    back_len: usize,
+}
+
+impl<T: Clone> PersistentDeque<T> {
+    pub fn new() -> Self {
+        return PersistentDeque { front: None, front_len: 0, back: None, back_len: 0 };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.front_len + self.back_len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    pub fn push_first(&self, data: T) -> Self {
+        let front = Some(Rc::new(Cons { data, next: self.front.clone() }));
+        return Self::balanced(front, self.front_len + 1, self.back.clone(), self.back_len);
+    }
+
+    pub fn push_back(&self, data: T) -> Self {
+        let back = Some(Rc::new(Cons { data, next: self.back.clone() }));
+        return Self::balanced(self.front.clone(), self.front_len, back, self.back_len + 1);
+    }
+
+    // The first element and the version without it. The element is cloned, as the old version
+    // still holds it.
+    pub fn pop_first(&self) -> Option<(T, Self)> {
+        // Balance leaves at most one element in `back` when `front` is empty.
+        let Some(front) = &self.front else {
+            let back = self.back.as_ref()?;
+            return Some((back.data.clone(), Self::new()));
+        };
+        let rest = Self::balanced(front.next.clone(), self.front_len - 1, self.back.clone(), self.back_len);
+        return Some((front.data.clone(), rest));
+    }
+
+    pub fn pop_back(&self) -> Option<(T, Self)> {
+        let Some(back) = &self.back else {
+            let front = self.front.as_ref()?;
+            return Some((front.data.clone(), Self::new()));
+        };
+        let rest = Self::balanced(self.front.clone(), self.front_len, back.next.clone(), self.back_len - 1);
+        return Some((back.data.clone(), rest));
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        return self.front.as_ref().or(self.back.as_ref()).map(|node| &node.data);
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        return self.back.as_ref().or(self.front.as_ref()).map(|node| &node.data);
+    }
+
+    // Copies this version into a new mutable list.
+    pub fn to_list(&self) -> DoublyLinkedList<T> {
+        let mut list = DoublyLinkedList::new();
+        for data in self.iter() {
+            list.push_back(data.clone());
+        }
+        return list;
+    }
+
+    // Copies a mutable list. The result does not change when the list does.
+    pub fn from_list(list: &DoublyLinkedList<T>) -> Self {
+        let mut elements = Vec::with_capacity(list.len());
+        let mut current = list.head.clone();
+        while let Some(node) = current {
+            elements.push(node.borrow().data().clone());
+            current = node.borrow().next.clone();
+        }
+        return Self::from_vec(elements);
+    }
+
+    // Counts both stacks against the stored lengths.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for (link, len) in [(&self.front, self.front_len), (&self.back, self.back_len)] {
+            let mut counted = 0;
+            let mut current = link.as_deref();
+            while let Some(node) = current {
+                counted += 1;
+                current = node.next.as_deref();
+            }
+            if counted != len {
+                return Err(ValidationError::CountMismatch { counted, len });
+            }
+        }
+        return Ok(());
+    }
+
+    fn balanced(front: Link<T>, front_len: usize, back: Link<T>, back_len: usize) -> Self {
+        let deque = PersistentDeque { front, front_len, back, back_len };
+        if deque.front_len > BALANCE * deque.back_len + 1 || deque.back_len > BALANCE * deque.front_len + 1 {
+            return Self::from_vec(deque.iter().cloned().collect());
+        }
+        return deque;
+    }
+
+    // Splits `elements` evenly between the two stacks.
+    fn from_vec(mut elements: Vec<T>) -> Self {
+        let front_len = elements.len() / 2;
+        let back_len = elements.len() - front_len;
+        let mut back = None;
+        for data in elements.drain(front_len..) {
+            back = Some(Rc::new(Cons { data, next: back }));
+        }
+        let mut front = None;
+        for data in elements.into_iter().rev() {
+            front = Some(Rc::new(Cons { data, next: front }));
+        }
+        return PersistentDeque { front, front_len, back, back_len };
+    }
+}
+
+impl<T: Clone> Default for PersistentDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PersistentDeque<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        // `back` is reversed, so its elements are collected and handed out last first.
+        let mut back = Vec::with_capacity(self.back_len);
+        let mut current = self.back.as_deref();
+        while let Some(node) = current {
+            back.push(&node.data);
+            current = node.next.as_deref();
+        }
+        return Iter { front: self.front.as_deref(), back };
+    }
+}
+
+// Versions share nodes, so cloning one is O(1).
+impl<T> Clone for PersistentDeque<T> {
+    fn clone(&self) -> Self {
+        return PersistentDeque {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.clone(),
+            back_len: self.back_len,
+        };
+    }
+}
+
+// Dropping a long stack would recurse once per node, so the nodes no other version shares are
+// unlinked one at a time.
+impl<T> Drop for PersistentDeque<T> {
+    fn drop(&mut self) {
+        for link in [self.front.take(), self.back.take()] {
+            let mut current = link;
+            while let Some(node) = current {
+                current = match Rc::try_unwrap(node) {
+                    Ok(mut node) => node.next.take(),
+                    Err(_) => None,
+                };
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_list().entries(self.iter()).finish();
+    }
+}
+
+impl<T: Clone> FromIterator<T> for PersistentDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        return Self::from_vec(iter.into_iter().collect());
+    }
+}
+
+impl<T: Clone> From<&DoublyLinkedList<T>> for PersistentDeque<T> {
+    fn from(list: &DoublyLinkedList<T>) -> Self {
+        return Self::from_list(list);
+    }
+}
+
+impl<T: Clone> From<&PersistentDeque<T>> for DoublyLinkedList<T> {
+    fn from(deque: &PersistentDeque<T>) -> Self {
+        return deque.to_list();
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: Option<&'a Cons<T>>,
+    back: Vec<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if let Some(node) = self.front {
+            self.front = node.next.as_deref();
+            return Some(&node.data);
+        }
+        return self.back.pop();
+    }
+}
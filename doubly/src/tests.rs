@@ -1,248 +1,2081 @@
+#[cfg(test)]
+mod conformance_tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::linked_list::DoublyLinkedList;
+    use crate::node::Node;
+
+    include!("../../conformance/suite.rs");
+
+    impl<T: Clone> ListBackend<T> for DoublyLinkedList<T> {
+        type Handle = Rc<RefCell<Node<T>>>;
+
+        fn create() -> Self {
+            DoublyLinkedList::new()
+        }
+
+        fn push_first(&mut self, data: T) -> Self::Handle {
+            DoublyLinkedList::push_first(self, data)
+        }
+
+        fn push_back(&mut self, data: T) -> Self::Handle {
+            DoublyLinkedList::push_back(self, data)
+        }
+
+        fn pop_first(&mut self) -> Option<T> {
+            DoublyLinkedList::pop_first(self)
+        }
+
+        fn pop_back(&mut self) -> Option<T> {
+            DoublyLinkedList::pop_back(self)
+        }
+
+        fn insert(&mut self, side: Side, data: T, anchor: &Self::Handle) -> Self::Handle {
+            DoublyLinkedList::insert(self, side, data, anchor.clone())
+        }
+
+        fn remove(&mut self, node: Self::Handle) -> Option<T> {
+            DoublyLinkedList::remove(self, node)
+        }
+
+        fn head(&self) -> Option<Self::Handle> {
+            self.head.clone()
+        }
+
+        fn tail(&self) -> Option<Self::Handle> {
+            self.tail.clone()
+        }
+
+        fn next(&self, node: &Self::Handle) -> Option<Self::Handle> {
+            self.next_of(node)
+        }
+
+        fn prev(&self, node: &Self::Handle) -> Option<Self::Handle> {
+            self.prev_of(node)
+        }
+
+        fn value(&self, node: &Self::Handle) -> T {
+            self.get(node).clone()
+        }
+
+        fn same(a: &Self::Handle, b: &Self::Handle) -> bool {
+            Rc::ptr_eq(a, b)
+        }
+    }
+
+    conformance_suite!(DoublyLinkedList);
+}
+
+
+
+
+
+#[cfg(test)]
+mod compare_tests {
+    use crate::compare::{compare_column, Verdict};
+    use crate::stats::welch_t_test;
+
+    #[test]
+    fn test_welch_t_test_known_value() {
+        let result = welch_t_test(&[1.0, 2.0, 3.0, 4.0, 5.0], &[3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert!((result.t - 2.0).abs() < 1e-9);
+        assert!((result.df - 8.0).abs() < 1e-9);
+        assert!((result.p_value - 0.0805).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_identical_samples_are_no_change() {
+        let samples = [100.0, 102.0, 98.0, 101.0, 99.0];
+        let result = compare_column("i32", &samples, &samples, 5.0, 0.05);
+        assert_eq!(result.verdict, Verdict::NoChange);
+        assert!((result.p_value - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_regression_beyond_threshold() {
+        let baseline = [100.0, 102.0, 98.0, 101.0, 99.0];
+        let candidate = [120.0, 122.0, 118.0, 121.0, 119.0];
+        let result = compare_column("i32", &baseline, &candidate, 5.0, 0.05);
+        assert_eq!(result.verdict, Verdict::Regression);
+        assert!((result.change_percent - 20.0).abs() < 1e-9);
+
+        let result = compare_column("i32", &baseline, &candidate, 25.0, 0.05);
+        assert_eq!(result.verdict, Verdict::Slower);
+    }
+
+    #[test]
+    fn test_speedup_is_not_a_regression() {
+        let baseline = [120.0, 122.0, 118.0, 121.0, 119.0];
+        let candidate = [100.0, 102.0, 98.0, 101.0, 99.0];
+        let result = compare_column("user", &baseline, &candidate, 5.0, 0.05);
+        assert_eq!(result.verdict, Verdict::Faster);
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    use crate::compare::Samples;
+    use crate::report::{render, BoxStats, Series};
+
+    fn series(label: &str, i32_times: Vec<f64>) -> Series {
+        Series {
+            label: label.to_string(),
+            samples: Samples {
+                columns: vec!["i32".to_string()],
+                values: vec![i32_times],
+            },
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_box_stats_quartiles() {
+        let stats = BoxStats::of(&[5.0, 1.0, 4.0, 2.0, 3.0]);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.q1, 2.0);
+        assert_eq!(stats.median, 3.0);
+        assert_eq!(stats.q3, 4.0);
+        assert_eq!(stats.max, 5.0);
+    }
+
+    #[test]
+    fn test_render_plain_versus_pooled() {
+        let html = render(&[
+            series("plain", vec![100.0, 110.0, 105.0]),
+            series("<pooled>", vec![50.0, 55.0, 60.0]),
+        ]);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html.matches("<svg").count(), 2);
+        assert!(html.contains("<polyline"));
+        assert!(html.contains("&lt;pooled&gt;"));
+        assert!(!html.contains("<pooled>"));
+        assert!(html.contains("-47.6%"));
+    }
+}
+
+#[cfg(test)]
+mod trace_tests {
+    use crate::enums::Side;
+    use crate::linked_list::DoublyLinkedList;
+    use crate::trace::{read_trace, replay, write_trace, Op, TraceRecorder};
+
+    fn contents(list: &DoublyLinkedList<u64>) -> Vec<u64> {
+        let mut values = Vec::new();
+        let mut current = list.head.clone();
+        while let Some(node) = current {
+            values.push(*node.borrow().data());
+            current = node.borrow().next.clone();
+        }
+        values
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut recorder = TraceRecorder::new(Vec::new());
+        let first = recorder.push_back(0);
+        let second = recorder.push_back(1);
+        let third = recorder.insert(Side::Before, 2, second.clone());
+        recorder.push_first(3);
+        recorder.remove(third);
+        recorder.insert(Side::After, 5, first);
+        drop(second);
+        recorder.pop_back();
+        recorder.pop_first();
+
+        let (list, out) = recorder.finish().unwrap();
+        let ops = read_trace(out.as_slice()).unwrap();
+        assert_eq!(
+            ops,
+            vec![
+                Op::PushBack(0),
+                Op::PushBack(1),
+                Op::Insert(Side::Before, 1, 2),
+                Op::PushFirst(3),
+                Op::Remove(2),
+                Op::Insert(Side::After, 0, 4),
+                Op::PopBack(Some(1)),
+                Op::PopFirst(Some(3)),
+            ]
+        );
+
+        let mut replayed = DoublyLinkedList::new();
+        replay(&mut replayed, &ops, |id| id);
+        assert_eq!(contents(&replayed), vec![0, 4]);
+        assert_eq!(contents(&list), vec![0, 5]);
+    }
+
+    #[test]
+    fn test_recorder_skips_operations_that_panic() {
+        let mut recorder = TraceRecorder::new(Vec::new());
+        let first = recorder.push_back(0);
+        recorder.push_back(1);
+
+        // `first` is borrowed, so the list panics before unlinking it.
+        let peek = recorder.list().get(&first);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            recorder.remove(first.clone());
+        }));
+        assert!(result.is_err());
+        drop(peek);
+
+        recorder.remove(first);
+        let (list, out) = recorder.finish().unwrap();
+        let ops = read_trace(out.as_slice()).unwrap();
+        assert_eq!(ops, vec![Op::PushBack(0), Op::PushBack(1), Op::Remove(0)]);
+        assert_eq!(contents(&list), vec![1]);
+    }
+
+    #[test]
+    fn test_write_and_read_round_trip() {
+        let ops = vec![
+            Op::PushFirst(0),
+            Op::Insert(Side::After, 0, 1),
+            Op::PopBack(Some(1)),
+            Op::PopFirst(Some(0)),
+            Op::PopFirst(None),
+        ];
+        let mut out = Vec::new();
+        write_trace(&mut out, &ops).unwrap();
+        assert_eq!(read_trace(out.as_slice()).unwrap(), ops);
+    }
+
+    #[test]
+    fn test_rejects_invalid_traces() {
+        let error = read_trace("push_back 0\ninsert after 3 1\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 2);
+
+        let error = read_trace("push_back 1\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 1);
+
+        let error = read_trace("push_back 0\nremove 0\nremove 0\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 3);
+
+        let error = read_trace("# comment\n\nshuffle 0\n".as_bytes()).unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+}
 
 #[cfg(test)]
-mod general_tests {
+mod workload_tests {
     use crate::linked_list::DoublyLinkedList;
-    use super::*;
+    use crate::trace::{read_trace, replay, write_trace};
+    use crate::workload::{generate, run_std_linked_list, run_vec_deque, AnchorDistribution, OpMix, Rng, WorkloadConfig};
+
+    fn config(seed: u64, anchors: AnchorDistribution) -> WorkloadConfig {
+        WorkloadConfig {
+            seed,
+            initial: 100,
+            ops: 2_000,
+            mix: OpMix::default(),
+            anchors,
+        }
+    }
 
     #[test]
-    fn test_push_and_pop_first() {
-        let mut list = DoublyLinkedList::new();
-        assert!(list.head.is_none());
-        assert!(list.tail.is_none());
+    fn test_same_seed_same_workload() {
+        let a = generate(&config(7, AnchorDistribution::Uniform));
+        let b = generate(&config(7, AnchorDistribution::Uniform));
+        let c = generate(&config(8, AnchorDistribution::Uniform));
+        assert_eq!(a.ops, b.ops);
+        assert_eq!(a.positions, b.positions);
+        assert_ne!(a.ops, c.ops);
+    }
 
-                list.push_first(1);
-        assert_eq!(list.head.as_ref().unwrap().borrow().data, 1);
+    #[test]
+    fn test_workload_is_a_valid_trace() {
+        let workload = generate(&config(1, AnchorDistribution::HotSpot { center: 0.2, width: 0.1 }));
+        let mut out = Vec::new();
+        write_trace(&mut out, &workload.ops).unwrap();
+        assert_eq!(read_trace(out.as_slice()).unwrap(), workload.ops);
+    }
 
-                assert_eq!(list.pop_first(), Some(1));
-        assert!(list.head.is_none());
-        assert!(list.tail.is_none());
+    #[test]
+    fn test_backends_agree() {
+        for anchors in [
+            AnchorDistribution::Uniform,
+            AnchorDistribution::HeadBiased,
+            AnchorDistribution::TailBiased,
+            AnchorDistribution::HotSpot { center: 0.5, width: 0.05 },
+        ] {
+            let workload = generate(&config(3, anchors));
+            let mut list = DoublyLinkedList::new();
+            replay(&mut list, &workload.ops, |id| id);
 
-                list.push_first(2);
-        list.push_first(3);
-        assert_eq!(list.pop_first(), Some(3));
-        assert_eq!(list.head.as_ref().unwrap().borrow().data, 2);
+            let mut values = Vec::new();
+            let mut current = list.head.clone();
+            while let Some(node) = current {
+                values.push(*node.borrow().data());
+                current = node.borrow().next.clone();
+            }
+
+            let deque: Vec<u64> = run_vec_deque(&workload, |id| id).into_iter().collect();
+            let std_list: Vec<u64> = run_std_linked_list(&workload, |id| id).into_iter().collect();
+            assert_eq!(values, deque);
+            assert_eq!(values, std_list);
+        }
     }
 
     #[test]
-    fn test_push_and_pop_back() {
-        let mut list = DoublyLinkedList::new();
+    fn test_anchor_bias() {
+        let mut rng = Rng::new(11);
+        let head: usize = (0..1_000).map(|_| AnchorDistribution::HeadBiased.pick(&mut rng, 1_000)).sum();
+        let tail: usize = (0..1_000).map(|_| AnchorDistribution::TailBiased.pick(&mut rng, 1_000)).sum();
+        assert!(head / 1_000 < 300);
+        assert!(tail / 1_000 > 700);
 
-                list.push_back(1);
-        list.push_back(2);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, 2);
+        let hot = AnchorDistribution::HotSpot { center: 0.5, width: 0.1 };
+        assert!((0..1_000).map(|_| hot.pick(&mut rng, 1_000)).all(|p| (400..=600).contains(&p)));
+    }
 
-                assert_eq!(list.pop_back(), Some(2));
-        assert_eq!(list.pop_back(), Some(1));
-        assert!(list.head.is_none());
-        assert!(list.tail.is_none());
+    #[test]
+    fn test_parse_options() {
+        let mix = OpMix::parse("push_back=4,remove=1").unwrap();
+        assert_eq!(mix.push_back, 4);
+        assert_eq!(mix.remove, 1);
+        assert_eq!(mix.insert, 0);
+        assert!(OpMix::parse("push_back=0").is_err());
+        assert!(OpMix::parse("shuffle=1").is_err());
 
-                list.push_back(3);
-        list.push_back(4);
-        assert_eq!(list.pop_back(), Some(4));
-        assert_eq!(list.head.as_ref().unwrap().borrow().data, 3);
+        assert_eq!(
+            AnchorDistribution::parse("hot-spot:0.25").unwrap(),
+            AnchorDistribution::HotSpot { center: 0.25, width: 0.05 }
+        );
+        assert!(AnchorDistribution::parse("hot-spot:2").is_err());
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use crate::compare::Samples;
+    use crate::metadata::Metadata;
+    use crate::report::{warnings, Series};
+
+    fn run(element_count: usize, host: &str) -> Metadata {
+        let mut metadata = Metadata::new();
+        metadata.set("mode", "benchmark");
+        metadata.set("profile", "release");
+        metadata.set("element_count", element_count);
+        metadata.set("hostname", host);
+        metadata.set("git_commit", "abc123");
+        metadata
     }
 
     #[test]
-    fn test_remove_middle() {
-        let mut list = DoublyLinkedList::new();
+    fn test_json_round_trip() {
+        let mut metadata = run(20_000_000, "bench-01");
+        metadata.set("cpu", "Quirky \"CPU\" \\ model\twith\ncontrol \u{1}");
+        let parsed = Metadata::parse_json(&metadata.to_json()).unwrap();
+        assert_eq!(parsed, metadata);
+
+        assert_eq!(Metadata::parse_json(" { } ").unwrap(), Metadata::new());
+        assert!(Metadata::parse_json("{\"a\": \"b\",}").is_err());
+        assert!(Metadata::parse_json("{\"a\": 1}").is_err());
+        assert!(Metadata::parse_json("{\"a\": \"b\"} trailing").is_err());
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(Metadata::sidecar_path("out/doubly_linked_list.csv"), "out/doubly_linked_list.meta.json");
+        assert_eq!(Metadata::sidecar_path("results"), "results.meta.json");
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let baseline = run(20_000_000, "bench-01");
+        assert!(baseline.mismatches(&baseline).is_empty());
+
+        let mut candidate = run(10_000_000, "bench-02");
+        candidate.set("git_commit", "def456");
+        let mismatches = candidate.mismatches(&baseline);
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].key, "element_count");
+        assert!(!mismatches[0].config);
+        assert_eq!(mismatches[1].key, "hostname");
+        assert!(!mismatches[1].config);
+        assert_eq!(mismatches[0].describe(), "element_count: 10000000 vs 20000000");
+
+        candidate.set("mode", "replay");
+        let mismatches = candidate.mismatches(&baseline);
+        assert_eq!(mismatches.len(), 3);
+        assert_eq!(mismatches[0].key, "mode");
+        assert!(mismatches[0].config);
+    }
+
+    #[test]
+    fn test_report_warnings() {
+        let series = |label: &str, metadata: Option<Metadata>| Series {
+            label: label.to_string(),
+            samples: Samples { columns: Vec::new(), values: Vec::new() },
+            metadata,
+        };
+
+        let same = [series("a", Some(run(10, "h"))), series("b", Some(run(10, "h")))];
+        assert!(warnings(&same).is_empty());
+
+        let different = [series("plain", Some(run(20, "h"))), series("pooled", Some(run(10, "h"))), series("old", None)];
+        let warnings = warnings(&different);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("old has no metadata"));
+        assert!(warnings[1].contains("element_count: 20 vs 10"));
+    }
+}
+
+#[cfg(test)]
+mod fuzz_tests {
+    use crate::fuzz::{decode, run, Harness, Step, STEP_KINDS};
+    use crate::enums::Side;
+    use crate::workload::Rng;
+
+    fn program(seed: u64, steps: usize, kinds: u8) -> Vec<u8> {
+        let mut rng = Rng::new(seed);
+        let mut data = Vec::with_capacity(2 * steps);
+        for _ in 0..steps {
+            data.push(rng.below(kinds as usize) as u8);
+            data.push(rng.next_u64() as u8);
+        }
+        return data;
+    }
+
+    #[test]
+    fn test_decode_pairs_of_bytes() {
+        assert_eq!(decode(&[]), vec![]);
+        assert_eq!(decode(&[1, 9, 4]), vec![Step::PushBack]);
+        assert_eq!(decode(&[4, 3, 5, 200]), vec![Step::Insert(Side::Before, 3), Step::Insert(Side::After, 200)]);
+        assert_eq!(decode(&[STEP_KINDS, 0]), vec![Step::PushFirst]);
+    }
+
+    #[test]
+    fn test_harness_tracks_the_list() {
+        let mut harness = Harness::new();
+        for step in [Step::PushBack, Step::PushFirst, Step::Insert(Side::After, 0), Step::Remove(2), Step::PopBack] {
+            harness.apply(step);
+            harness.check().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_harness_reuses_retired_handles() {
+        let mut harness = Harness::new();
+        let steps = [
+            Step::PushBack,
+            Step::PushBack,
+            Step::Remove(0),
+            Step::RemoveStale(0),
+            Step::InsertStale(Side::After, 0),
+            Step::Clear,
+            Step::PushFirst,
+            Step::InsertStale(Side::Before, 1),
+            Step::RemoveStale(1),
+        ];
+        for step in steps {
+            harness.apply(step);
+            harness.check().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_random_programs() {
+        for seed in 0..300 {
+            run(&program(seed, 400, STEP_KINDS));
+        }
+    }
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use std::mem::ManuallyDrop;
+    use crate::enums::{Side, ValidationError};
+    use crate::linked_list::DoublyLinkedList;
+
+    // Corrupted lists are leaked: dropping one would walk the broken links.
+    fn three() -> ManuallyDrop<DoublyLinkedList<i32>> {
+        let mut list = ManuallyDrop::new(DoublyLinkedList::new());
         list.push_back(1);
         list.push_back(2);
         list.push_back(3);
+        return list;
+    }
+
+    #[test]
+    fn test_validate_accepts_well_formed_lists() {
+        let mut list = DoublyLinkedList::new();
+        assert_eq!(list.validate(), Ok(()));
+        let middle = list.push_back(2);
+        list.push_first(1);
+        list.insert(Side::After, 3, middle.clone());
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.validate(), Ok(()));
+        list.remove(middle);
+        list.pop_first();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_reports_head_with_prev() {
+        let list = three();
+        let tail = list.tail.clone();
+        list.head.as_ref().unwrap().borrow_mut().prev = tail;
+        assert_eq!(list.validate(), Err(ValidationError::HeadHasPrev));
+    }
 
-                let middle_node = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
-        list.remove(middle_node);
+    #[test]
+    fn test_validate_reports_broken_link() {
+        let list = three();
+        let second = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
+        second.borrow_mut().prev = None;
+        assert_eq!(list.validate(), Err(ValidationError::BrokenLink { index: 0 }));
+        assert_eq!(
+            list.validate().unwrap_err().to_string(),
+            "node 0 links to node 1, whose prev does not point back"
+        );
+    }
 
-                assert_eq!(list.head.as_ref().unwrap().borrow().data, 1);
-        assert_eq!(list.head.as_ref().unwrap().borrow().next.as_ref().unwrap().borrow().data, 3);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, 3);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().prev.as_ref().unwrap().borrow().data, 1);
+    #[test]
+    fn test_validate_reports_tail_not_last() {
+        let list = three();
+        let second = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
+        second.borrow_mut().next = None;
+        assert_eq!(list.validate(), Err(ValidationError::TailNotLast { count: 2 }));
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    #[should_panic(expected = "list is corrupted: head has a prev link")]
+    fn test_mutations_check_the_list_under_validation() {
+        let mut list = three();
+        let tail = list.tail.clone();
+        list.head.as_ref().unwrap().borrow_mut().prev = tail;
+        list.push_back(4);
     }
 }
 
 #[cfg(test)]
-mod insert_tests {
+mod drop_tests {
+    use std::cell::RefCell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
     use crate::enums::Side;
     use crate::linked_list::DoublyLinkedList;
-    use super::*;
+
+    // Counts live instances per id. Every construction or clone adds one and every drop takes
+    // one away, so a count that goes negative is a double drop and one left over is a leak.
+    #[derive(Debug)]
+    struct Tracked {
+        id: usize,
+        live: Rc<RefCell<Vec<isize>>>,
+    }
+
+    impl Tracked {
+        fn new(id: usize, live: &Rc<RefCell<Vec<isize>>>) -> Self {
+            let mut counts = live.borrow_mut();
+            if counts.len() <= id {
+                counts.resize(id + 1, 0);
+            }
+            counts[id] += 1;
+            Tracked { id, live: live.clone() }
+        }
+    }
+
+    impl Clone for Tracked {
+        fn clone(&self) -> Self {
+            Tracked::new(self.id, &self.live)
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            let mut counts = self.live.borrow_mut();
+            counts[self.id] -= 1;
+            assert!(counts[self.id] >= 0, "payload {} dropped more often than created", self.id);
+        }
+    }
+
+    fn live(counts: &Rc<RefCell<Vec<isize>>>) -> isize {
+        counts.borrow().iter().sum()
+    }
+
+    fn filled(n: usize, counts: &Rc<RefCell<Vec<isize>>>) -> DoublyLinkedList<Tracked> {
+        let mut list = DoublyLinkedList::new();
+        for id in 0..n {
+            if id % 3 == 0 {
+                list.push_first(Tracked::new(id, counts));
+            } else {
+                list.push_back(Tracked::new(id, counts));
+            }
+        }
+        list
+    }
+
+    #[test]
+    fn test_dropping_the_list_drops_every_element_once() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = filled(50, &counts);
+        let anchor = list.push_back(Tracked::new(50, &counts));
+        list.insert(Side::Before, Tracked::new(51, &counts), anchor);
+        assert_eq!(live(&counts), 52);
+        drop(list);
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_pop_and_remove_hand_over_the_element() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = filled(3, &counts);
+        let middle = list.push_back(Tracked::new(3, &counts));
+        list.push_back(Tracked::new(4, &counts));
+
+        let first = list.pop_first().unwrap();
+        let last = list.pop_back().unwrap();
+        let removed = list.remove(middle).unwrap();
+        assert_eq!(live(&counts), 5);
+        drop((first, last, removed));
+        assert_eq!(live(&counts), 2);
+        drop(list);
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_clear_drops_elements_and_keeps_the_list_usable() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = filled(20, &counts);
+        list.clear();
+        assert_eq!(live(&counts), 0);
+        assert!(list.is_empty());
+        assert!(list.head.is_none() && list.tail.is_none());
+        list.push_back(Tracked::new(20, &counts));
+        assert_eq!(list.len(), 1);
+        drop(list);
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_panic_during_iteration_drops_the_rest() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let list = filled(10, &counts);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            for element in list {
+                if element.id == 4 {
+                    panic!("payload {}", element.id);
+                }
+            }
+        }));
+        assert!(result.is_err());
+        assert_eq!(live(&counts), 0);
+    }
 
     #[test]
-    fn test_insert_before() {
+    fn test_iteration_from_both_ends() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut iter = filled(6, &counts).into_iter();
+        assert_eq!(iter.size_hint(), (6, Some(6)));
+        assert_eq!(iter.next().map(|e| e.id), Some(3));
+        assert_eq!(iter.next_back().map(|e| e.id), Some(5));
+        assert_eq!(iter.map(|e| e.id).collect::<Vec<_>>(), vec![0, 1, 2, 4]);
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_dropping_the_list_while_handles_are_alive() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut list = filled(4, &counts);
+        let held = list.push_back(Tracked::new(4, &counts));
+        list.push_back(Tracked::new(5, &counts));
+        let head = list.head.clone().unwrap();
+
+        drop(list);
+        // Only the two held elements survive, and they no longer keep their neighbours alive.
+        assert_eq!(live(&counts), 2);
+        assert!(held.borrow().prev.is_none() && held.borrow().next.is_none());
+        assert!(head.borrow().next.is_none());
+        drop((held, head));
+        assert_eq!(live(&counts), 0);
+    }
+
+    #[test]
+    fn test_removing_through_another_list_is_refused() {
+        let counts = Rc::new(RefCell::new(Vec::new()));
+        let mut a = filled(3, &counts);
+        let mut b = filled(3, &counts);
+        let of_a = a.push_back(Tracked::new(3, &counts));
+        let head_of_a = a.head.clone().unwrap();
+
+        assert!(b.remove(of_a.clone()).is_none());
+        assert!(b.remove(head_of_a).is_none());
+        assert!(b.try_insert(Side::After, Tracked::new(4, &counts), of_a.clone()).is_err());
+        assert_eq!((a.len(), b.len()), (4, 3));
+        assert_eq!(a.validate(), Ok(()));
+        assert_eq!(b.validate(), Ok(()));
+        assert_eq!(live(&counts), 7);
+
+        assert_eq!(a.remove(of_a).map(|e| e.id), Some(3));
+        drop((a, b));
+        assert_eq!(live(&counts), 0);
+    }
+}
+
+#[cfg(test)]
+mod panic_tests {
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+    use crate::linked_list::DoublyLinkedList;
+
+    // A payload whose `Clone` or `Drop` panics for one chosen id, the clone only after
+    // `clones_before_panic` successful ones. `live` counts instances so the tests can tell that
+    // nothing leaked or was dropped twice.
+    #[derive(Debug, Default)]
+    struct Faults {
+        clone_panics_on: Cell<Option<usize>>,
+        clones_before_panic: Cell<usize>,
+        drop_panics_on: Cell<Option<usize>>,
+        live: Cell<isize>,
+    }
+
+    #[derive(Debug)]
+    struct Fragile {
+        id: usize,
+        faults: Rc<Faults>,
+    }
+
+    impl Fragile {
+        fn new(id: usize, faults: &Rc<Faults>) -> Self {
+            faults.live.set(faults.live.get() + 1);
+            Fragile { id, faults: faults.clone() }
+        }
+    }
+
+    impl Clone for Fragile {
+        fn clone(&self) -> Self {
+            if self.faults.clone_panics_on.get() == Some(self.id) {
+                match self.faults.clones_before_panic.get() {
+                    0 => panic!("clone of payload {}", self.id),
+                    n => self.faults.clones_before_panic.set(n - 1),
+                }
+            }
+            Fragile::new(self.id, &self.faults)
+        }
+    }
+
+    impl Drop for Fragile {
+        fn drop(&mut self) {
+            self.faults.live.set(self.faults.live.get() - 1);
+            // Panic once, so that dropping what is left while unwinding does not abort.
+            if self.faults.drop_panics_on.get() == Some(self.id) {
+                self.faults.drop_panics_on.set(None);
+                panic!("drop of payload {}", self.id);
+            }
+        }
+    }
+
+    fn filled(n: usize, faults: &Rc<Faults>) -> DoublyLinkedList<Fragile> {
         let mut list = DoublyLinkedList::new();
-        let first_item = 1;
-        let second_item = 2;
+        for id in 0..n {
+            list.push_back(Fragile::new(id, faults));
+        }
+        list
+    }
 
-                list.push_back(first_item);
+    #[test]
+    fn test_panicking_drop_during_clear() {
+        let faults = Rc::new(Faults::default());
+        let mut list = filled(10, &faults);
+        faults.drop_panics_on.set(Some(4));
+        assert!(catch_unwind(AssertUnwindSafe(|| list.clear())).is_err());
+        assert_eq!(list.validate(), Ok(()));
+        assert!(list.is_empty());
+        // The nodes after the panicking one were freed too.
+        assert_eq!(faults.live.get(), 0);
+        list.push_back(Fragile::new(10, &faults));
+        assert_eq!(list.len(), 1);
+    }
 
-                let head = list.head.clone().unwrap();
-        list.insert(Side::Before, second_item, head);
+    #[test]
+    fn test_panicking_drop_while_dropping_the_list() {
+        let faults = Rc::new(Faults::default());
+        let list = filled(10, &faults);
+        faults.drop_panics_on.set(Some(0));
+        assert!(catch_unwind(AssertUnwindSafe(move || drop(list))).is_err());
+        assert_eq!(faults.live.get(), 0);
+    }
 
-                assert_eq!(list.head.as_ref().unwrap().borrow().data, second_item);
-        assert_eq!(list.head.as_ref().unwrap().borrow().next.as_ref().unwrap().borrow().data, first_item);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, first_item);
+    #[test]
+    fn test_panicking_drop_of_popped_value() {
+        let faults = Rc::new(Faults::default());
+        let mut list = filled(3, &faults);
+        faults.drop_panics_on.set(Some(1));
+        assert!(catch_unwind(AssertUnwindSafe(|| {
+            list.pop_first();
+            list.pop_first();
+        }))
+        .is_err());
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), 1);
+        drop(list);
+        assert_eq!(faults.live.get(), 0);
+    }
+}
+
+#[cfg(test)]
+mod borrow_tests {
+    use crate::enums::{HandleError, Side};
+    use crate::linked_list::DoublyLinkedList;
+
+    #[test]
+    fn test_get_and_get_mut() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(String::from("a"));
+        list.get_mut(&node).push('b');
+        assert_eq!(*list.get(&node), "ab");
+        assert_eq!(*list.try_get(&node).unwrap(), "ab");
     }
 
     #[test]
-    fn test_insert_after() {
+    fn test_try_get_reports_a_mutable_borrow() {
         let mut list = DoublyLinkedList::new();
-        let first_item = 1;
-        let second_item = 2;
+        let node = list.push_back(1);
+        let guard = list.get_mut(&node);
+        assert_eq!(list.try_get(&node).err(), Some(HandleError::Borrowed));
+        drop(guard);
+        assert_eq!(*list.try_get(&node).unwrap(), 1);
+    }
 
-                list.push_back(first_item);
+    #[test]
+    fn test_try_insert_next_to_a_borrowed_node() {
+        let mut list = DoublyLinkedList::new();
+        let first = list.push_back(1);
+        let second = list.push_back(2);
 
-                let tail = list.tail.clone().unwrap();
-        list.insert(Side::After, second_item, tail);
+        let peek = list.get(&first);
+        assert_eq!(list.try_insert(Side::Before, 0, first.clone()).err(), Some(HandleError::Borrowed));
+        // Inserting before `second` has to relink `first` as well.
+        assert_eq!(list.try_insert(Side::Before, 9, second.clone()).err(), Some(HandleError::Borrowed));
+        // Inserting after `second` does not touch `first`.
+        assert!(list.try_insert(Side::After, 3, second.clone()).is_ok());
+        drop(peek);
 
-                assert_eq!(list.head.as_ref().unwrap().borrow().data, first_item);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, second_item);
-        assert_eq!(list.head.as_ref().unwrap().borrow().next.as_ref().unwrap().borrow().data, second_item);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), 3);
+        assert!(list.try_insert(Side::Before, 0, first.clone()).is_ok());
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
-    fn test_insert_empty_list() {
+    fn test_try_remove_reports_borrows() {
         let mut list = DoublyLinkedList::new();
-        let first_item = 1;
+        let first = list.push_back(1);
+        let second = list.push_back(2);
+        list.push_back(3);
+
+        let peek = list.get(&first);
+        assert_eq!(list.try_remove(second.clone()), Err(HandleError::Borrowed));
+        drop(peek);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), 3);
+
+        assert_eq!(list.try_remove(second), Ok(2));
+        assert_eq!(list.len(), 2);
+    }
 
-                assert!(list.head.is_none() && list.tail.is_none());
+    #[test]
+    fn test_remove_and_pops_while_handles_are_held() {
+        let mut list = DoublyLinkedList::new();
+        let first = list.push_back(1);
+        let second = list.push_back(2);
+        let third = list.push_back(3);
 
-                list.push_back(first_item);
-        assert_eq!(list.head.as_ref().unwrap().borrow().data, first_item);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, first_item);
+        assert_eq!(list.remove(second.clone()), Some(2));
+        assert_eq!(list.pop_first(), Some(1));
+        assert_eq!(list.pop_back(), Some(3));
+        assert!(list.is_empty());
+        assert_eq!(list.validate(), Ok(()));
+        drop((first, third));
     }
 
     #[test]
-    fn test_insert_between_nodes() {
+    fn test_handles_left_over_from_clear_or_remove_are_rejected() {
         let mut list = DoublyLinkedList::new();
-        let first_item = 1;
-        let second_item = 2;
-        let third_item = 3;
+        let first = list.push_back(1);
+        let second = list.push_back(2);
+        list.clear();
+        list.push_back(3);
 
-                list.push_back(first_item);
-        list.push_back(third_item);
+        assert_eq!(list.try_insert(Side::After, 4, first.clone()).err(), Some(HandleError::Stale));
+        assert_eq!(list.try_remove(second.clone()), Err(HandleError::Stale));
+        assert_eq!(list.remove(second), None);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.validate(), Ok(()));
 
-                let first_node = list.head.clone().unwrap();
-        list.insert(Side::After, second_item, first_node);
+        let third = list.push_back(5);
+        assert_eq!(list.remove(third.clone()), Some(5));
+        assert_eq!(list.remove(third), None);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.validate(), Ok(()));
+    }
 
-                assert_eq!(list.head.as_ref().unwrap().borrow().data, first_item);
-        assert_eq!(list.head.as_ref().unwrap().borrow().next.as_ref().unwrap().borrow().data, second_item);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, third_item);
-        assert_eq!(list.head.as_ref().unwrap().borrow().next.as_ref().unwrap().borrow().next.as_ref().unwrap().borrow().data, third_item);
+    #[test]
+    #[should_panic(expected = "the anchor is not linked in the list")]
+    fn test_insert_panics_on_a_cleared_anchor() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(1);
+        list.clear();
+        list.insert(Side::Before, 2, node);
+    }
+
+    #[test]
+    #[should_panic(expected = "the node was removed from its list")]
+    fn test_get_panics_on_a_removed_node() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(1);
+        list.remove(node.clone());
+        list.get(&node);
+    }
+
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn test_insert_panics_next_to_a_borrowed_node() {
+        let mut list = DoublyLinkedList::new();
+        let node = list.push_back(1);
+        let _peek = list.get(&node);
+        list.insert(Side::After, 2, node.clone());
     }
 }
 
 #[cfg(test)]
-mod remove_tests {
+mod access_tests {
     use crate::linked_list::DoublyLinkedList;
-    use super::*;
 
     #[test]
-    fn test_remove_single_element() {
+    fn test_front_and_back() {
         let mut list = DoublyLinkedList::new();
-        list.push_first(10);
-
-        let head = list.head.clone().unwrap();
-        assert_eq!(list.remove(head), Some(10));
-        assert!(list.head.is_none());
-        assert!(list.tail.is_none());
+        assert!(list.front().is_none() && list.back().is_none());
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(*list.front().unwrap(), 1);
+        assert_eq!(*list.back().unwrap(), 2);
+        *list.front_mut().unwrap() += 10;
+        *list.back_mut().unwrap() += 20;
+        assert_eq!(list.pop_first(), Some(11));
+        assert_eq!(list.pop_first(), Some(22));
+        assert!(list.front_mut().is_none() && list.back_mut().is_none());
     }
 
     #[test]
-    fn test_pop_first_single_element() {
+    fn test_replace_keeps_the_node_in_place() {
         let mut list = DoublyLinkedList::new();
-        list.push_first(10);
-        assert_eq!(list.pop_first(), Some(10));
-        assert!(list.head.is_none());
-        assert!(list.tail.is_none());
+        list.push_back("a");
+        let middle = list.push_back("b");
+        list.push_back("c");
+        assert_eq!(list.replace(&middle, "x"), "b");
+        assert_eq!(*list.get(&middle), "x");
+        assert_eq!(list.validate(), Ok(()));
+        drop(middle);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec!["a", "x", "c"]);
     }
 
     #[test]
-    fn test_pop_back_single_element() {
+    fn test_swap_data() {
         let mut list = DoublyLinkedList::new();
-        list.push_back(10);
-        assert_eq!(list.pop_back(), Some(10));
-        assert!(list.head.is_none());
-        assert!(list.tail.is_none());
+        let first = list.push_back(1);
+        let last = list.push_back(2);
+        list.swap_data(&first, &last);
+        list.swap_data(&first, &first);
+        assert_eq!(*list.get(&first), 2);
+        assert_eq!(*list.get(&last), 1);
+        assert!(list.next_of(&first).is_some_and(|next| std::rc::Rc::ptr_eq(&next, &last)));
     }
 
     #[test]
-    fn test_remove_multiple_elements() {
+    fn test_walk_with_next_of_and_prev_of() {
         let mut list = DoublyLinkedList::new();
-        list.push_back(10);
-        list.push_back(20);
-        list.push_back(30);
+        for value in 0..5 {
+            list.push_back(value);
+        }
 
-                let middle_node = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
-        assert_eq!(list.remove(middle_node), Some(20));
+        let mut forward = Vec::new();
+        let mut current = list.head.clone();
+        while let Some(node) = current {
+            forward.push(*list.get(&node));
+            current = list.next_of(&node);
+        }
+        assert_eq!(forward, vec![0, 1, 2, 3, 4]);
 
-                assert_eq!(list.head.as_ref().unwrap().borrow().data, 10);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, 30);
-        assert_eq!(list.head.as_ref().unwrap().borrow().next.as_ref().unwrap().borrow().data, 30);
-        assert_eq!(list.tail.as_ref().unwrap().borrow().prev.as_ref().unwrap().borrow().data, 10);
+        let mut backward = Vec::new();
+        let mut current = list.tail.clone();
+        while let Some(node) = current {
+            backward.push(*list.get(&node));
+            current = list.prev_of(&node);
+        }
+        assert_eq!(backward, vec![4, 3, 2, 1, 0]);
     }
+}
 
-    #[test]
-    fn test_pop_first_multiple_elements() {
+#[cfg(test)]
+mod index_tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use crate::linked_list::DoublyLinkedList;
+
+    fn numbers(count: usize) -> DoublyLinkedList<usize> {
         let mut list = DoublyLinkedList::new();
-        list.push_back(10);
-        list.push_back(20);
-        list.push_back(30);
+        for value in 0..count {
+            list.push_back(value);
+        }
+        return list;
+    }
 
-        assert_eq!(list.pop_first(), Some(10));
-                assert_eq!(list.head.as_ref().unwrap().borrow().data, 20);
-        assert!(list.head.as_ref().unwrap().borrow().prev.is_none());
+    #[test]
+    fn test_get_nth_from_both_ends() {
+        for count in [0, 1, 2, 7, 8] {
+            let list = numbers(count);
+            for index in 0..count {
+                assert_eq!(*list.get_nth(index).unwrap(), index);
+                let node = list.handle_at(index).unwrap();
+                assert_eq!(list.index_of(&node), index);
+            }
+            assert!(list.get_nth(count).is_none());
+            assert!(list.handle_at(count).is_none());
+        }
     }
 
     #[test]
-    fn test_pop_back_multiple_elements() {
+    fn test_insert_at_and_remove_at_match_vec() {
         let mut list = DoublyLinkedList::new();
-        list.push_back(10);
-        list.push_back(20);
-        list.push_back(30);
+        let mut model = Vec::new();
+        for value in 0..20usize {
+            let index = value * 7 % (model.len() + 1);
+            list.insert_at(index, value);
+            model.insert(index, value);
+        }
+        for step in 0..10usize {
+            let index = step * 5 % model.len();
+            assert_eq!(list.remove_at(index), Some(model.remove(index)));
+        }
+        assert_eq!(list.remove_at(model.len()), None);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), model);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_insert_at_past_the_end_panics() {
+        numbers(3).insert_at(4, 0);
+    }
+
+    #[test]
+    fn test_get_nth_mut() {
+        let mut list = numbers(5);
+        *list.get_nth_mut(3).unwrap() *= 10;
+        assert_eq!(*list.get_nth(3).unwrap(), 30);
+        assert!(list.get_nth_mut(5).is_none());
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 30, 4]);
+    }
+
+    #[test]
+    fn test_get_nth_guard_outlives_the_removal_attempt() {
+        let mut list = numbers(5);
+        let guard = list.get_nth(2).unwrap();
+        let handle = guard.handle().clone();
+        // The guard does not borrow the list, so the node can be targeted while it is read.
+        // Relinking it panics before anything changes, as it does while `get` is held.
+        let removed = catch_unwind(AssertUnwindSafe(|| list.remove(handle.clone())));
+        assert!(removed.is_err());
+        assert_eq!(*guard, 2);
+        assert!(list.try_remove(handle.clone()).is_err());
+        drop(guard);
+        assert_eq!(list.remove(handle), Some(2));
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 1, 3, 4]);
+    }
+}
+
+#[cfg(test)]
+mod indexed_tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::enums::Side;
+    use crate::indexed_list::IndexedList;
+    use crate::node::Node;
+    use crate::workload::Rng;
+
+    #[test]
+    fn test_positional_ops_match_vec() {
+        let mut rng = Rng::new(41);
+        let mut list = IndexedList::new();
+        let mut model: Vec<u64> = Vec::new();
+        for value in 0..2000 {
+            match rng.below(4) {
+                0 | 1 => {
+                    let index = rng.below(model.len() + 1);
+                    list.insert_at(index, value);
+                    model.insert(index, value);
+                }
+                2 if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    assert_eq!(list.remove_at(index), Some(model.remove(index)));
+                }
+                _ if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    assert_eq!(*list.get_nth(index).unwrap(), model[index]);
+                    let node = list.handle_at(index).unwrap();
+                    assert_eq!(list.rank_of(&node), Some(index));
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(list.len(), model.len());
+        assert!(list.get_nth(model.len()).is_none());
+        assert_eq!(list.validate(), Ok(()));
+        for (index, value) in model.iter().enumerate() {
+            assert_eq!(*list.get_nth(index).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn test_handle_ops_keep_ranks() {
+        let mut list = IndexedList::with_seed(7);
+        let middle = list.push_back(50);
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        for value in 0..50 {
+            before.push(list.insert(Side::Before, value, middle.clone()));
+            after.push(list.insert(Side::After, 100 - value, middle.clone()));
+        }
+        assert_eq!(list.rank_of(&middle), Some(50));
+        assert_eq!(list.rank_of(&before[0]), Some(0));
+        assert_eq!(list.rank_of(&after[0]), Some(100));
+
+        for node in before.drain(..25) {
+            assert!(list.remove(node).is_some());
+        }
+        assert_eq!(list.rank_of(&middle), Some(25));
+        assert_eq!(*list.get_nth(25).unwrap(), 50);
+        drop((before, after));
+        assert_eq!(list.pop_first(), Some(25));
+        assert_eq!(list.pop_back(), Some(100));
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_rank_of_refuses_removed_and_foreign_nodes() {
+        let mut list = IndexedList::new();
+        let mut other = IndexedList::new();
+        let nodes: Vec<_> = (0..20).map(|value| list.push_back(value)).collect();
+        let foreign = other.push_back(0);
+        list.remove(nodes[5].clone());
+        assert_eq!(list.rank_of(&nodes[5]), None);
+        assert_eq!(list.rank_of(&foreign), None);
+        assert_eq!(list.rank_of(&nodes[6]), Some(5));
+        list.clear();
+        assert_eq!(list.rank_of(&nodes[6]), None);
+    }
+
+    #[test]
+    fn test_handle_ops_between_positional_ops_match_vec() {
+        let mut rng = Rng::new(43);
+        let mut list = IndexedList::with_seed(3);
+        let mut handles: Vec<Rc<RefCell<Node<u64>>>> = Vec::new();
+        let mut model: Vec<u64> = Vec::new();
+        for value in 0..1000 {
+            match rng.below(5) {
+                0 if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    let side = if value % 2 == 0 { Side::Before } else { Side::After };
+                    let at = if side == Side::Before { index } else { index + 1 };
+                    handles.insert(at, list.insert(side, value, handles[index].clone()));
+                    model.insert(at, value);
+                }
+                1 if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    assert_eq!(list.remove(handles.remove(index)), Some(model.remove(index)));
+                }
+                2 => {
+                    let index = rng.below(model.len() + 1);
+                    handles.insert(index, list.insert_at(index, value));
+                    model.insert(index, value);
+                }
+                _ if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    assert_eq!(*list.get_nth(index).unwrap(), model[index]);
+                    assert_eq!(list.rank_of(&handles[index]), Some(index));
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.len(), model.len());
+    }
+
+    #[test]
+    fn test_insert_at_end_and_front() {
+        let mut list = IndexedList::new();
+        list.insert_at(0, 2);
+        list.insert_at(1, 3);
+        list.insert_at(0, 1);
+        assert_eq!((0..3).map(|i| *list.get_nth(i).unwrap()).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_insert_at_past_the_end_panics() {
+        let mut list = IndexedList::new();
+        list.push_back(1);
+        list.insert_at(2, 0);
+    }
+
+    #[test]
+    fn test_drop_frees_nodes_and_towers() {
+        let mut list = IndexedList::new();
+        let nodes: Vec<_> = (0..200).map(|value| Rc::downgrade(&list.push_back(value))).collect();
+        list.remove_at(10);
+        drop(list);
+        assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+    }
+}
+
+#[cfg(test)]
+mod ordered_tests {
+    use std::cmp::Ordering;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use crate::enums::Side;
+    use crate::ordered_list::OrderedList;
+    use crate::workload::Rng;
+
+    #[test]
+    fn test_compare_matches_positions() {
+        let mut rng = Rng::new(42);
+        let mut list = OrderedList::new();
+        let mut handles = vec![list.push_back(0)];
+        for value in 1..600 {
+            let index = rng.below(handles.len());
+            let side = if rng.below(2) == 0 { Side::Before } else { Side::After };
+            let node = list.insert(side, value, handles[index].clone());
+            handles.insert(if side == Side::Before { index } else { index + 1 }, node);
+        }
+        for _ in 0..2000 {
+            let (a, b) = (rng.below(handles.len()), rng.below(handles.len()));
+            assert_eq!(list.compare(&handles[a], &handles[b]), a.cmp(&b));
+        }
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_dense_inserts_relabel_in_amortized_log_time() {
+        // Inserting right after the same node halves its gap every time, which forces a
+        // relabel roughly every 60 inserts.
+        let count = 1000;
+        let mut list = OrderedList::new();
+        let anchor = list.push_back(0);
+        let mut last = list.push_back(count);
+        for value in 1..count {
+            let node = list.insert(Side::After, value, anchor.clone());
+            assert_eq!(list.compare(&anchor, &node), Ordering::Less);
+            assert_eq!(list.compare(&node, &last), Ordering::Less);
+            last = node;
+        }
+        for _ in 0..count {
+            list.push_first(0);
+        }
+        let n = list.len();
+        assert!(list.relabelled() > 0);
+        assert!(list.relabelled() <= 4 * n * (usize::BITS - n.leading_zeros()) as usize);
+    }
+
+    #[test]
+    fn test_remove_keeps_order() {
+        let mut list = OrderedList::new();
+        let first = list.push_back(1);
+        let middle = list.push_back(2);
+        let last = list.push_back(3);
+        assert_eq!(list.remove(middle), Some(2));
+        assert_eq!(list.compare(&last, &first), Ordering::Greater);
+        assert_eq!(list.compare(&first, &first), Ordering::Equal);
+        drop(first);
+        assert_eq!(list.pop_first(), Some(1));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_failed_remove_keeps_the_label() {
+        let mut list = OrderedList::new();
+        let first = list.push_back(1);
+        let middle = list.push_back(2);
+        let guard = list.get(&middle);
+        let removed = catch_unwind(AssertUnwindSafe(|| list.remove(middle.clone())));
+        assert!(removed.is_err());
+        drop(guard);
+        assert_eq!(list.compare(&first, &middle), Ordering::Less);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.remove(middle), Some(2));
+    }
+}
+
+#[cfg(test)]
+mod unrolled_tests {
+    use crate::unrolled_list::{UnrolledList, CHUNK_CAPACITY};
+    use crate::workload::Rng;
+
+    #[test]
+    fn test_positional_ops_match_vec() {
+        let mut rng = Rng::new(44);
+        let mut list = UnrolledList::new();
+        let mut model = Vec::new();
+        for value in 0..3000u64 {
+            match rng.below(5) {
+                0 => {
+                    list.push_back(value);
+                    model.push(value);
+                }
+                1 | 2 => {
+                    let index = rng.below(model.len() + 1);
+                    list.insert_at(index, value);
+                    model.insert(index, value);
+                }
+                _ if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    assert_eq!(list.remove_at(index), Some(model.remove(index)));
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(list.len(), model.len());
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), model);
+        for (index, value) in model.iter().enumerate() {
+            assert_eq!(list.get_nth(index), Some(value));
+        }
+        assert_eq!(list.get_nth(model.len()), None);
+    }
+
+    #[test]
+    fn test_handles_follow_elements_across_splits_and_merges() {
+        let mut list = UnrolledList::new();
+        for value in 0..CHUNK_CAPACITY * 4 {
+            list.push_back(value);
+        }
+        let handles: Vec<_> = (0..list.len()).step_by(7).map(|index| list.handle_at(index).unwrap()).collect();
+
+        // Splits: fill the front chunk past capacity.
+        for value in 0..CHUNK_CAPACITY * 2 {
+            list.insert_at(3, 1000 + value);
+        }
+        for handle in &handles {
+            let index = list.index_of(handle).unwrap();
+            assert_eq!(list.get_nth(index), list.get(handle));
+        }
+
+        // Merges: thin out everything that has no handle.
+        let mut index = 0;
+        while index < list.len() {
+            let handled = handles.iter().any(|handle| list.index_of(handle) == Some(index));
+            if handled {
+                index += 1;
+            } else {
+                list.remove_at(index);
+            }
+        }
+        assert_eq!(list.len(), handles.len());
+        for (index, handle) in handles.iter().enumerate() {
+            assert_eq!(list.index_of(handle), Some(index));
+            assert_eq!(list.get(handle), Some(&(index * 7)));
+        }
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_removed_handle_is_invalid() {
+        let mut list = UnrolledList::new();
+        list.push_back("a");
+        list.push_back("b");
+        let handle = list.handle_at(1).unwrap();
+        assert!(list.handle_at(1).unwrap().ptr_eq(&handle));
+        *list.get_mut(&handle).unwrap() = "c";
+        assert_eq!(list.remove(handle.clone()), Some("c"));
+        assert!(handle.is_removed());
+        assert_eq!(list.get(&handle), None);
+        assert_eq!(list.index_of(&handle), None);
+        assert_eq!(list.remove(handle), None);
+
+        let first = list.handle_at(0).unwrap();
+        list.clear();
+        assert!(first.is_removed());
+        assert!(list.is_empty() && list.iter().next().is_none());
+    }
+
+    #[test]
+    fn test_handles_from_another_list_are_refused() {
+        let mut list = UnrolledList::new();
+        list.push_back(1);
+        let mut other = UnrolledList::new();
+        other.push_back(2);
+        let foreign = other.handle_at(0).unwrap();
+
+        assert_eq!(list.get(&foreign), None);
+        assert_eq!(list.get_mut(&foreign), None);
+        assert_eq!(list.index_of(&foreign), None);
+        assert_eq!(list.remove(foreign.clone()), None);
+        assert_eq!(list.len(), 1);
+        assert_eq!(other.get(&foreign), Some(&2));
+    }
+
+    // References from `get`, `get_nth` and `iter` skip the chunk's borrow flag. Everything that
+    // can run while they are alive only shares the chunk, and another list cannot be aimed at it.
+    #[test]
+    fn test_references_live_across_shared_calls_and_foreign_edits() {
+        let mut list = UnrolledList::new();
+        let mut other = UnrolledList::new();
+        for value in 0..CHUNK_CAPACITY * 2 {
+            list.push_back(value);
+            other.push_back(value + 1000);
+        }
+        let handle = list.handle_at(5).unwrap();
+        let foreign = other.handle_at(5).unwrap();
+
+        let by_index = list.get_nth(5).unwrap();
+        let by_handle = list.get(&handle).unwrap();
+        let mut iter = list.iter();
+        let walked = iter.nth(CHUNK_CAPACITY).unwrap();
 
-        assert_eq!(list.pop_back(), Some(30));
-                assert_eq!(list.tail.as_ref().unwrap().borrow().data, 20);
-        assert!(list.tail.as_ref().unwrap().borrow().next.is_none());
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.index_of(&handle), Some(5));
+        assert_eq!(list.iter().nth(5), Some(by_index));
+        assert_eq!(other.remove(handle.clone()), None);
+        assert_eq!(other.get_mut(&handle), None);
+        assert_eq!(other.remove(foreign), Some(1005));
+        drop(handle.clone());
+        assert!(!handle.is_removed());
+        assert_eq!((*by_index, *by_handle, *walked), (5, 5, CHUNK_CAPACITY));
+        assert_eq!(iter.next(), Some(&(CHUNK_CAPACITY + 1)));
+
+        assert_eq!(list.remove(handle.clone()), Some(5));
+        assert!(handle.is_removed());
+        assert_eq!(list.get_nth(5), Some(&6));
+    }
+
+    #[test]
+    fn test_appends_keep_chunks_full() {
+        let mut list = UnrolledList::new();
+        for value in 0..CHUNK_CAPACITY * 3 {
+            list.push_back(value);
+        }
+        assert_eq!(list.chunk_count(), 3);
+        assert_eq!(list.pop_back(), Some(CHUNK_CAPACITY * 3 - 1));
+        assert_eq!(list.pop_first(), Some(0));
+        *list.get_nth_mut(0).unwrap() = 7;
+        assert_eq!(list.iter().next(), Some(&7));
+    }
+}
+
+#[cfg(test)]
+mod circular_tests {
+    use std::rc::Rc;
+    use crate::circular_list::CircularList;
+    use crate::enums::{HandleError, Side};
+
+    fn ring(count: usize) -> CircularList<usize> {
+        let mut list = CircularList::new();
+        for value in 1..=count {
+            list.push(value);
+        }
+        return list;
+    }
+
+    fn values(list: &CircularList<usize>) -> Vec<usize> {
+        return list.iter().map(|value| *value).collect();
+    }
+
+    // Eliminates every `step`-th person around the circle, returning them in order.
+    fn josephus(count: usize, step: usize) -> Vec<usize> {
+        let mut list = ring(count);
+        let mut order = Vec::new();
+        while !list.is_empty() {
+            for _ in 1..step {
+                list.rotate_forward();
+            }
+            order.push(list.pop_current().unwrap());
+        }
+        return order;
+    }
+
+    #[test]
+    fn test_josephus() {
+        assert_eq!(josephus(7, 3), vec![3, 6, 2, 7, 5, 1, 4]);
+        assert_eq!(josephus(1, 5), vec![1]);
+        for count in 1..40 {
+            for step in 1..8 {
+                // J(1) = 0, J(n) = (J(n - 1) + k) mod n, counting from zero.
+                let survivor = (2..=count).fold(0, |j, n| (j + step) % n) + 1;
+                assert_eq!(josephus(count, step).last(), Some(&survivor));
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotation_wraps_both_ways() {
+        let mut list = ring(4);
+        list.rotate_backward();
+        assert_eq!(*list.current_value().unwrap(), 4);
+        assert_eq!(values(&list), vec![4, 1, 2, 3]);
+        for _ in 0..6 {
+            list.rotate_forward();
+        }
+        assert_eq!(*list.current_value().unwrap(), 2);
+        assert_eq!(values(&list), vec![2, 3, 4, 1]);
+    }
+
+    #[test]
+    fn test_insert_remove_and_iter_from() {
+        let mut list = ring(3);
+        let first = list.current().unwrap();
+        let after = list.insert(Side::After, 10, first.clone()).unwrap();
+        let before = list.insert(Side::Before, 20, first.clone()).unwrap();
+        assert_eq!(values(&list), vec![1, 10, 2, 3, 20]);
+        assert_eq!(list.iter_from(&after).unwrap().map(|value| *value).collect::<Vec<_>>(), vec![10, 2, 3, 20, 1]);
+        assert!(Rc::ptr_eq(&list.next_of(&before).unwrap(), &first));
+        assert!(Rc::ptr_eq(&list.prev_of(&after).unwrap(), &first));
+
+        // Removing the current node hands over to its successor.
+        drop(first);
+        assert_eq!(list.pop_current(), Some(1));
+        assert_eq!(*list.current_value().unwrap(), 10);
+        assert_eq!(list.remove(before), Some(20));
+        list.set_current(&after).unwrap();
+        *list.get_mut(&after) += 1;
+        drop(after);
+        assert_eq!(values(&list), vec![11, 2, 3]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_remove_while_handles_are_held() {
+        let mut list = ring(3);
+        let first = list.current().unwrap();
+        assert_eq!(list.remove(first.clone()), Some(1));
+        assert_eq!(list.remove(first.clone()), None);
+        assert_eq!(list.pop_current(), Some(2));
+        assert_eq!(values(&list), vec![3]);
+        assert_eq!(list.validate(), Ok(()));
+        drop(first);
+    }
+
+    #[test]
+    fn test_removed_and_foreign_nodes_are_refused() {
+        let mut list = ring(3);
+        let mut other = ring(2);
+        let foreign = other.current().unwrap();
+        let removed = list.current().unwrap();
+        assert_eq!(list.remove(removed.clone()), Some(1));
+
+        for node in [&removed, &foreign] {
+            assert_eq!(list.set_current(node), Err(HandleError::Stale));
+            assert!(matches!(list.iter_from(node), Err(HandleError::Stale)));
+            assert_eq!(list.insert(Side::After, 9, node.clone()).err(), Some(HandleError::Stale));
+            assert_eq!(list.remove(node.clone()), None);
+        }
+        assert_eq!(values(&list), vec![2, 3]);
+        assert_eq!(values(&other), vec![1, 2]);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(other.validate(), Ok(()));
+
+        let kept = list.current().unwrap();
+        list.clear();
+        assert_eq!(list.set_current(&kept), Err(HandleError::Stale));
+    }
+
+    #[test]
+    fn test_iterated_values_outlive_the_walk() {
+        let list = ring(3);
+        let held: Vec<_> = list.iter().collect();
+        assert_eq!(held.iter().map(|value| **value).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_empty_and_single_node() {
+        let mut list = CircularList::new();
+        list.rotate_forward();
+        assert!(list.current().is_none() && list.iter().next().is_none());
+        assert_eq!(list.pop_current(), None);
+        list.push(5);
+        list.rotate_backward();
+        assert_eq!(list.iter().map(|value| *value).collect::<Vec<_>>(), vec![5]);
+        assert_eq!(list.pop_current(), Some(5));
+        assert!(list.is_empty() && list.current().is_none());
+    }
+
+    #[test]
+    fn test_drop_breaks_the_ring() {
+        let mut list = ring(5);
+        let weak = Rc::downgrade(&list.current().unwrap());
+        list.rotate_forward();
+        drop(list);
+        assert!(weak.upgrade().is_none());
     }
 }
 
 #[cfg(test)]
-mod edge_case_tests {
+mod persistent_tests {
+    use std::collections::VecDeque;
     use crate::linked_list::DoublyLinkedList;
-    use super::*;
+    use crate::persistent_deque::PersistentDeque;
+    use crate::workload::Rng;
+
+    fn values(deque: &PersistentDeque<u64>) -> Vec<u64> {
+        return deque.iter().copied().collect();
+    }
+
+    #[test]
+    fn test_every_version_keeps_its_contents() {
+        let mut rng = Rng::new(47);
+        let mut versions = vec![PersistentDeque::new()];
+        let mut models = vec![VecDeque::new()];
+        for value in 0..2000u64 {
+            let base = rng.below(versions.len());
+            let (deque, mut model) = (&versions[base], models[base].clone());
+            let next = match rng.below(4) {
+                0 => {
+                    model.push_front(value);
+                    deque.push_first(value)
+                }
+                1 => {
+                    model.push_back(value);
+                    deque.push_back(value)
+                }
+                2 => match deque.pop_first() {
+                    Some((data, rest)) => {
+                        assert_eq!(Some(data), model.pop_front());
+                        rest
+                    }
+                    None => deque.clone(),
+                },
+                _ => match deque.pop_back() {
+                    Some((data, rest)) => {
+                        assert_eq!(Some(data), model.pop_back());
+                        rest
+                    }
+                    None => deque.clone(),
+                },
+            };
+            assert_eq!(next.validate(), Ok(()));
+            assert_eq!(next.front(), model.front());
+            assert_eq!(next.back(), model.back());
+            versions.push(next);
+            models.push(model);
+        }
+        for (deque, model) in versions.iter().zip(&models) {
+            assert_eq!(deque.len(), model.len());
+            assert_eq!(values(deque), model.iter().copied().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_pops_drain_in_order() {
+        let mut deque: PersistentDeque<u64> = (0..10).collect();
+        let full = deque.clone();
+        for expected in 0..10 {
+            let (data, rest) = deque.pop_first().unwrap();
+            assert_eq!(data, expected);
+            deque = rest;
+        }
+        assert!(deque.is_empty() && deque.pop_back().is_none());
+        assert_eq!(values(&full), (0..10).collect::<Vec<_>>());
+    }
 
     #[test]
-    fn test_remove_head() {
+    fn test_converts_to_and_from_a_mutable_list() {
         let mut list = DoublyLinkedList::new();
-        list.push_back(10);
-        list.push_back(20);
+        for value in 0..5u64 {
+            list.push_back(value);
+        }
+        let snapshot = PersistentDeque::from(&list);
+        list.push_first(99);
+        assert_eq!(values(&snapshot), vec![0, 1, 2, 3, 4]);
 
-        let head = list.head.clone().unwrap();
-        assert_eq!(list.remove(head), Some(10));
-        assert_eq!(list.head.as_ref().unwrap().borrow().data, 20);
-        assert!(list.head.as_ref().unwrap().borrow().prev.is_none());
+        let grown = snapshot.push_back(5);
+        let copy = DoublyLinkedList::from(&grown);
+        assert_eq!(copy.len(), 6);
+        assert_eq!(copy.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(format!("{:?}", snapshot), "[0, 1, 2, 3, 4]");
+    }
+
+    #[test]
+    fn test_dropping_a_long_deque_does_not_recurse() {
+        let mut deque = PersistentDeque::new();
+        for value in 0..200_000u64 {
+            deque = deque.push_first(value);
+        }
+        let (_, shorter) = deque.pop_first().unwrap();
+        drop(deque);
+        assert_eq!(shorter.len(), 199_999);
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use crate::linked_list::DoublyLinkedList;
+    use crate::workload::Rng;
+
+    fn list_of(count: u64) -> DoublyLinkedList<u64> {
+        let mut list = DoublyLinkedList::new();
+        for value in 0..count {
+            list.push_back(value);
+        }
+        return list;
+    }
+
+    #[test]
+    fn test_snapshots_keep_their_contents_while_the_list_changes() {
+        let mut rng = Rng::new(48);
+        let mut list = list_of(50);
+        let mut snapshots = Vec::new();
+        for value in 100..1500u64 {
+            if rng.below(20) == 0 {
+                let expected = list.snapshot().iter().collect::<Vec<_>>();
+                snapshots.push((list.snapshot(), expected));
+            }
+            let len = list.len();
+            match rng.below(7) {
+                0 => {
+                    list.insert_at(rng.below(len + 1), value);
+                }
+                1 if len > 0 => {
+                    list.remove_at(rng.below(len));
+                }
+                2 if len > 0 => *list.get_nth_mut(rng.below(len)).unwrap() = value,
+                3 if len > 0 => {
+                    let node = list.handle_at(rng.below(len)).unwrap();
+                    list.replace(&node, value);
+                }
+                4 if len > 1 => {
+                    let a = list.handle_at(rng.below(len)).unwrap();
+                    let b = list.handle_at(rng.below(len)).unwrap();
+                    list.swap_data(&a, &b);
+                }
+                5 => {
+                    list.pop_first();
+                }
+                _ => {
+                    list.push_first(value);
+                }
+            }
+            if rng.below(50) == 0 && !snapshots.is_empty() {
+                snapshots.swap_remove(rng.below(snapshots.len()));
+            }
+        }
+        for (snapshot, expected) in &snapshots {
+            assert_eq!(snapshot.len(), expected.len());
+            assert_eq!(&snapshot.iter().collect::<Vec<_>>(), expected);
+        }
     }
 
     #[test]
-    fn test_remove_tail() {
+    fn test_only_touched_nodes_are_copied() {
+        let mut list = list_of(1000);
+        let snapshot = list.snapshot();
+        assert_eq!(snapshot.copied(), 0);
+        *list.front_mut().unwrap() = 7;
+        *list.back_mut().unwrap() = 8;
+        list.insert_at(500, 9);
+        assert_eq!(list.remove_at(500), Some(9));
+        // Head, tail, the node before the insertion, and the new node when it is removed.
+        assert_eq!(snapshot.copied(), 4);
+        assert!(snapshot.iter().eq(0..1000));
+
+        drop(snapshot);
+        *list.get_nth_mut(10).unwrap() = 0;
+        assert_eq!(list.snapshot().copied(), 0);
+    }
+
+    #[test]
+    fn test_snapshot_outlives_cleared_and_dropped_lists() {
+        let mut list = list_of(100);
+        let before = list.snapshot();
+        list.clear();
+        list.push_back(1);
+        let after = list.snapshot();
+        drop(list);
+        assert!(before.iter().eq(0..100));
+        assert_eq!(after.iter().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_iterating_while_the_list_changes() {
+        let mut list = list_of(10);
+        let snapshot = list.snapshot();
+        let mut seen = Vec::new();
+        for value in snapshot.iter() {
+            seen.push(value);
+            list.pop_first();
+            list.push_back(value + 100);
+        }
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+        assert!(list.snapshot().iter().eq(100..110));
+    }
+}
+
+#[cfg(test)]
+mod transaction_tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+    use crate::enums::{HandleError, Side};
+    use crate::linked_list::DoublyLinkedList;
+    use crate::workload::Rng;
+
+    fn list_of(count: u64) -> DoublyLinkedList<u64> {
         let mut list = DoublyLinkedList::new();
-        list.push_back(10);
-        list.push_back(20);
+        for value in 0..count {
+            list.push_back(value);
+        }
+        return list;
+    }
+
+    fn values(list: &DoublyLinkedList<u64>) -> Vec<u64> {
+        return (0..list.len()).map(|index| *list.get_nth(index).unwrap()).collect();
+    }
+
+    #[test]
+    fn test_commit_keeps_the_edits() {
+        let mut list = list_of(3);
+        let middle = list.handle_at(1).unwrap();
+        let result: Result<u64, ()> = list.transaction(|tx| {
+            tx.insert(Side::After, 10, middle.clone()).unwrap();
+            tx.push_first(20);
+            let removed = tx.remove(middle.clone()).unwrap();
+            tx.pop_back();
+            return Ok(removed);
+        });
+        assert_eq!(result, Ok(1));
+        assert_eq!(values(&list), vec![20, 0, 10]);
+        assert_eq!(list.validate(), Ok(()));
+    }
 
-        let tail = list.tail.clone().unwrap();
-        assert_eq!(list.remove(tail), Some(20));
-        assert_eq!(list.tail.as_ref().unwrap().borrow().data, 10);
-        assert!(list.tail.as_ref().unwrap().borrow().next.is_none());
+    #[test]
+    fn test_error_restores_the_same_nodes() {
+        let mut list = list_of(5);
+        let nodes: Vec<_> = (0..5).map(|index| list.handle_at(index).unwrap()).collect();
+        let result = list.transaction(|tx| {
+            tx.remove(nodes[1].clone());
+            tx.replace(&nodes[3], 30);
+            tx.insert(Side::Before, 40, nodes[0].clone()).unwrap();
+            tx.pop_back();
+            tx.remove(nodes[2].clone());
+            if tx.list().len() < 5 {
+                return Err("too short");
+            }
+            return Ok(());
+        });
+        assert_eq!(result, Err("too short"));
+        assert_eq!(values(&list), vec![0, 1, 2, 3, 4]);
+        for (index, node) in nodes.iter().enumerate() {
+            assert!(Rc::ptr_eq(node, &list.handle_at(index).unwrap()));
+        }
+        assert_eq!(list.validate(), Ok(()));
     }
 
     #[test]
-    fn test_pop_first_empty_list() {
-        let mut list: DoublyLinkedList<i32> = DoublyLinkedList::new();
-        assert_eq!(list.pop_first(), None);
+    fn test_panic_rolls_back() {
+        let mut list = list_of(4);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _: Result<(), ()> = list.transaction(|tx| {
+                tx.pop_first();
+                tx.push_back(9);
+                panic!("validation blew up");
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(values(&list), vec![0, 1, 2, 3]);
+        assert_eq!(list.validate(), Ok(()));
     }
 
     #[test]
-    fn test_pop_back_empty_list() {
-        let mut list: DoublyLinkedList<i32> = DoublyLinkedList::new();
-        assert_eq!(list.pop_back(), None);
+    fn test_detached_nodes_are_refused() {
+        let mut list = list_of(3);
+        let middle = list.handle_at(1).unwrap();
+        let result: Result<(), ()> = list.transaction(|tx| {
+            assert_eq!(tx.remove(middle.clone()), Some(1));
+            assert_eq!(tx.remove(middle.clone()), None);
+            assert_eq!(tx.insert(Side::After, 5, middle.clone()).err(), Some(HandleError::Stale));
+            assert_eq!(tx.list().len(), 2);
+            return Ok(());
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(values(&list), vec![0, 2]);
+
+        let mut inserted = None;
+        let result: Result<(), ()> = list.transaction(|tx| {
+            inserted = Some(tx.push_back(7));
+            return Err(());
+        });
+        assert_eq!(result, Err(()));
+        let inserted = inserted.unwrap();
+        assert_eq!(list.remove(inserted.clone()), None);
+        assert_eq!(list.try_insert(Side::Before, 8, inserted).err(), Some(HandleError::Stale));
+        assert_eq!(values(&list), vec![0, 2]);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_random_edits_roll_back() {
+        let mut rng = Rng::new(49);
+        let mut list = list_of(30);
+        for round in 0..50 {
+            let before = values(&list);
+            let commit = round % 3 == 0;
+            let result = list.transaction(|tx| {
+                for value in 0..20 {
+                    let len = tx.list().len();
+                    match rng.below(5) {
+                        0 if len > 0 => {
+                            let node = tx.list().handle_at(rng.below(len)).unwrap();
+                            tx.remove(node);
+                        }
+                        1 if len > 0 => {
+                            let node = tx.list().handle_at(rng.below(len)).unwrap();
+                            tx.insert(Side::After, value, node).unwrap();
+                        }
+                        2 if len > 0 => {
+                            let node = tx.list().handle_at(rng.below(len)).unwrap();
+                            tx.replace(&node, value);
+                        }
+                        3 => {
+                            tx.pop_first();
+                        }
+                        _ => {
+                            tx.push_back(value);
+                        }
+                    }
+                }
+                return if commit { Ok(()) } else { Err(()) };
+            });
+            assert_eq!(result.is_ok(), commit);
+            if !commit {
+                assert_eq!(values(&list), before);
+            }
+            assert_eq!(list.validate(), Ok(()));
+        }
     }
 }
 
+#[cfg(test)]
+mod undoable_tests {
+    use std::rc::Rc;
+    use crate::enums::{HandleError, Side};
+    use crate::linked_list::DoublyLinkedList;
+    use crate::undoable_list::UndoableList;
+    use crate::workload::Rng;
+
+    fn values(list: &DoublyLinkedList<u64>) -> Vec<u64> {
+        return (0..list.len()).map(|index| *list.get_nth(index).unwrap()).collect();
+    }
+
+    #[test]
+    fn test_undo_and_redo_walk_the_history() {
+        let mut rng = Rng::new(50);
+        let mut list = UndoableList::with_history_limit(1000);
+        let mut states = vec![values(list.list())];
+        for value in 0..300u64 {
+            let len = list.len();
+            let node = (len > 0).then(|| list.list().handle_at(rng.below(len)).unwrap());
+            let side = if rng.below(2) == 0 { Side::Before } else { Side::After };
+            match (rng.below(5), node) {
+                (0, Some(node)) => {
+                    list.insert(side, value, node).unwrap();
+                }
+                (1, Some(node)) => {
+                    list.remove(node).unwrap();
+                }
+                (2, Some(node)) => {
+                    let anchor = list.list().handle_at(rng.below(len)).unwrap();
+                    if Rc::ptr_eq(&node, &anchor) {
+                        continue;
+                    }
+                    list.move_to(node, side, &anchor).unwrap();
+                }
+                (3, Some(node)) => {
+                    list.replace(&node, value);
+                }
+                _ => {
+                    list.push_back(value);
+                }
+            }
+            states.push(values(list.list()));
+        }
+        assert_eq!(list.undo_depth(), states.len() - 1);
+        for state in states.iter().rev().skip(1) {
+            assert!(list.undo());
+            assert_eq!(&values(list.list()), state);
+        }
+        assert!(!list.undo());
+        for state in states.iter().skip(1) {
+            assert!(list.redo());
+            assert_eq!(&values(list.list()), state);
+        }
+        assert!(!list.redo());
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_handles_survive_undo_and_redo() {
+        let mut list = UndoableList::new();
+        let a = list.push_back(1);
+        let b = list.push_back(2);
+        let c = list.push_back(3);
+        assert_eq!(list.remove(b.clone()), Some(2));
+        list.move_to(c.clone(), Side::Before, &a).unwrap();
+        assert_eq!(values(list.list()), vec![3, 1]);
+
+        assert!(list.undo() && list.undo());
+        assert_eq!(values(list.list()), vec![1, 2, 3]);
+        assert!(Rc::ptr_eq(&list.list().handle_at(1).unwrap(), &b));
+        assert_eq!(list.replace(&b, 20), 2);
+        assert_eq!(*list.get(&b), 20);
+        // A new edit clears the redo history.
+        assert!(!list.redo());
+
+        assert!(list.undo() && list.undo());
+        assert_eq!(values(list.list()), vec![1, 2]);
+        assert!(list.redo());
+        assert!(Rc::ptr_eq(&list.list().handle_at(2).unwrap(), &c));
+    }
+
+    #[test]
+    fn test_removed_nodes_are_refused() {
+        let mut list = UndoableList::new();
+        let a = list.push_back(1);
+        let b = list.push_back(2);
+        assert_eq!(list.remove(b.clone()), Some(2));
+        assert_eq!(list.remove(b.clone()), None);
+        assert_eq!(list.insert(Side::After, 3, b.clone()).err(), Some(HandleError::Stale));
+        assert_eq!(list.move_to(b.clone(), Side::Before, &a), Err(HandleError::Stale));
+        assert_eq!(list.move_to(a.clone(), Side::After, &b), Err(HandleError::Stale));
+        assert_eq!(list.undo_depth(), 3);
+
+        // Undoing every edit unlinks `a` again.
+        assert!(list.undo() && list.undo() && list.undo());
+        assert_eq!(list.remove(a.clone()), None);
+        assert_eq!(values(list.list()), Vec::<u64>::new());
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_history_limit_drops_the_oldest_edits() {
+        let mut list = UndoableList::with_history_limit(3);
+        for value in 0..5u64 {
+            list.push_back(value);
+        }
+        assert_eq!(list.undo_depth(), 3);
+        while list.undo() {}
+        assert_eq!(values(list.list()), vec![0, 1]);
+
+        list.set_history_limit(1);
+        assert_eq!((list.undo_depth(), list.redo_depth()), (0, 3));
+        assert!(list.redo() && list.redo());
+        assert_eq!(list.history_limit(), 1);
+        assert_eq!((list.undo_depth(), list.redo_depth()), (1, 1));
+        list.push_first(9);
+        assert_eq!((list.undo_depth(), list.redo_depth()), (1, 0));
+        list.clear_history();
+        assert!(!list.undo());
+        assert_eq!(values(list.list()), vec![9, 0, 1, 2, 3]);
+    }
+}
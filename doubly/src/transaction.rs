@@ -0,0 +1,127 @@
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use crate::enums::{HandleError, Side};
+use crate::linked_list::DoublyLinkedList;
+use crate::node::Node;
+
+// The inverse of one edit, applied when the transaction rolls back.
+enum Undo<T> {
+    // Unlinked again and dropped.
+    Inserted(Rc<RefCell<Node<T>>>),
+    // Linked back in between its former neighbours, so handles to it stay valid.
+    Removed {
+        node: Rc<RefCell<Node<T>>>,
+        prev: Option<Rc<RefCell<Node<T>>>>,
+        next: Option<Rc<RefCell<Node<T>>>>,
+    },
+    // Gets its old payload back.
+    Replaced { node: Rc<RefCell<Node<T>>>, data: T },
+}
+
+// The edits available inside `DoublyLinkedList::transaction`. Each one is applied to the list
+// straight away and its inverse is logged. Removed nodes are only detached, and payloads are
+// cloned for the caller, so a rollback restores the very same nodes.
+pub struct Transaction<'a, T: Clone> {
+    list: &'a mut DoublyLinkedList<T>,
+    log: Vec<Undo<T>>,
+}
+
+impl<'a, T: Clone> Transaction<'a, T> {
+    pub(crate) fn new(list: &'a mut DoublyLinkedList<T>) -> Self {
+        return Transaction { list, log: Vec::new() };
+    }
+
+    // The list with the edits so far applied, for checks before deciding to commit.
+    pub fn list(&self) -> &DoublyLinkedList<T> {
+        return self.list;
+    }
+
+    pub fn get<'b>(&self, node: &'b Rc<RefCell<Node<T>>>) -> Ref<'b, T> {
+        return self.list.get(node);
+    }
+
+    pub fn push_first(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_first(data);
+        self.log.push(Undo::Inserted(node.clone()));
+        return node;
+    }
+
+    pub fn push_back(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_back(data);
+        self.log.push(Undo::Inserted(node.clone()));
+        return node;
+    }
+
+    // Like `DoublyLinkedList::try_insert`, this refuses an anchor that is borrowed or no longer
+    // linked, e.g. one removed earlier in the transaction.
+    pub fn insert(
+        &mut self,
+        side: Side,
+        data: T,
+        anchor: Rc<RefCell<Node<T>>>,
+    ) -> Result<Rc<RefCell<Node<T>>>, HandleError> {
+        let node = self.list.try_insert(side, data, anchor)?;
+        self.log.push(Undo::Inserted(node.clone()));
+        return Ok(node);
+    }
+
+    // Clones the payload, as the node keeps it in case of a rollback. Returns `None` if the node
+    // is no longer linked.
+    pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
+        if self.list.check_linked(&node) == Err(HandleError::Stale) {
+            return None;
+        }
+        let data = node.borrow().data().clone();
+        let (prev, next) = self.list.detach(&node);
+        self.list.debug_validate();
+        self.log.push(Undo::Removed { node, prev, next });
+        return Some(data);
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        let head = self.list.head.clone()?;
+        return self.remove(head);
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.list.tail.clone()?;
+        return self.remove(tail);
+    }
+
+    pub fn replace(&mut self, node: &Rc<RefCell<Node<T>>>, data: T) -> T {
+        let old = self.list.replace(node, data);
+        self.log.push(Undo::Replaced { node: node.clone(), data: old.clone() });
+        return old;
+    }
+
+    // Keeps the edits. Nodes removed by the transaction are dropped here.
+    pub(crate) fn commit(mut self) {
+        self.log.clear();
+    }
+
+    fn rollback(&mut self) {
+        while let Some(undo) = self.log.pop() {
+            match undo {
+                Undo::Inserted(node) => {
+                    self.list.detach(&node);
+                }
+                Undo::Removed { node, prev, next } => self.list.reattach(&node, prev, next),
+                Undo::Replaced { node, data } => {
+                    self.list.replace(&node, data);
+                }
+            }
+        }
+        // A second panic while unwinding would abort the process.
+        if !std::thread::panicking() {
+            self.list.debug_validate();
+        }
+    }
+}
+
+// Dropping a transaction that was not committed rolls it back. That covers both an `Err` from
+// the closure and a panic unwinding through it.
+impl<'a, T: Clone> Drop for Transaction<'a, T> {
+    fn drop(&mut self) {
+        self.rollback();
+    }
+}
@@ -0,0 +1,244 @@
+use std::cell::{Ref, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+use crate::enums::{HandleError, Side, ValidationError};
+use crate::linked_list::{DoublyLinkedList, Neighbours};
+use crate::node::Node;
+
+// Edits kept by `UndoableList::new`.
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+// One journaled edit. Each command keeps the node it acts on and the neighbours it needs, so
+// undoing and redoing relink the same node instead of building a new one.
+enum Command<T> {
+    // `prev` and `next` are the node's neighbours right after it was inserted.
+    Insert {
+        node: Rc<RefCell<Node<T>>>,
+        prev: Option<Rc<RefCell<Node<T>>>>,
+        next: Option<Rc<RefCell<Node<T>>>>,
+    },
+    // `prev` and `next` are the node's neighbours right before it was removed.
+    Remove {
+        node: Rc<RefCell<Node<T>>>,
+        prev: Option<Rc<RefCell<Node<T>>>>,
+        next: Option<Rc<RefCell<Node<T>>>>,
+    },
+    Move { node: Rc<RefCell<Node<T>>>, from: Neighbours<T>, to: Neighbours<T> },
+    // Holds the payload the node does not have. Undo and redo both swap it back in.
+    Replace { node: Rc<RefCell<Node<T>>>, data: T },
+}
+
+// A `DoublyLinkedList` that journals its edits so they can be undone and redone.
+//
+// Removed nodes are detached rather than dropped while a command still refers to them. Undo and
+// redo link the same nodes back in, so a handle stays valid whenever its node is restored. A new
+// edit clears the redo history, and only the latest `history_limit` edits can be undone; older
+// commands, and the removed nodes only they kept, are dropped.
+pub struct UndoableList<T: Clone> {
+    list: DoublyLinkedList<T>,
+    undo: VecDeque<Command<T>>,
+    redo: Vec<Command<T>>,
+    history_limit: usize,
+}
+
+impl<T: Clone> UndoableList<T> {
+    pub fn new() -> Self {
+        return Self::with_history_limit(DEFAULT_HISTORY_LIMIT);
+    }
+
+    pub fn with_history_limit(history_limit: usize) -> Self {
+        return UndoableList {
+            list: DoublyLinkedList::new(),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            history_limit,
+        };
+    }
+
+    // The list as it stands, for reading.
+    pub fn list(&self) -> &DoublyLinkedList<T> {
+        return &self.list;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.list.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.list.is_empty();
+    }
+
+    pub fn get<'a>(&self, node: &'a Rc<RefCell<Node<T>>>) -> Ref<'a, T> {
+        return self.list.get(node);
+    }
+
+    pub fn history_limit(&self) -> usize {
+        return self.history_limit;
+    }
+
+    // Lowering the limit drops the oldest edits straight away.
+    pub fn set_history_limit(&mut self, history_limit: usize) {
+        self.history_limit = history_limit;
+        self.trim();
+    }
+
+    pub fn undo_depth(&self) -> usize {
+        return self.undo.len();
+    }
+
+    pub fn redo_depth(&self) -> usize {
+        return self.redo.len();
+    }
+
+    pub fn push_first(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_first(data);
+        self.record_insert(&node);
+        return node;
+    }
+
+    pub fn push_back(&mut self, data: T) -> Rc<RefCell<Node<T>>> {
+        let node = self.list.push_back(data);
+        self.record_insert(&node);
+        return node;
+    }
+
+    // Handles to nodes that are currently removed, e.g. by an edit or by undoing their insert,
+    // are refused: `insert` and `move_to` return `HandleError::Stale` and `remove` returns `None`.
+    pub fn insert(
+        &mut self,
+        side: Side,
+        data: T,
+        anchor: Rc<RefCell<Node<T>>>,
+    ) -> Result<Rc<RefCell<Node<T>>>, HandleError> {
+        let node = self.list.try_insert(side, data, anchor)?;
+        self.record_insert(&node);
+        return Ok(node);
+    }
+
+    // Returns a clone of the payload, as the node keeps it for `undo`.
+    pub fn remove(&mut self, node: Rc<RefCell<Node<T>>>) -> Option<T> {
+        if self.list.check_linked(&node) == Err(HandleError::Stale) {
+            return None;
+        }
+        let data = node.borrow().data().clone();
+        let (prev, next) = self.list.detach(&node);
+        self.list.debug_validate();
+        self.record(Command::Remove { node, prev, next });
+        return Some(data);
+    }
+
+    // Relinks `node` on `side` of `anchor`. Moving a node next to itself does nothing.
+    pub fn move_to(
+        &mut self,
+        node: Rc<RefCell<Node<T>>>,
+        side: Side,
+        anchor: &Rc<RefCell<Node<T>>>,
+    ) -> Result<(), HandleError> {
+        self.list.check_linked(&node)?;
+        self.list.check_linked(anchor)?;
+        if Rc::ptr_eq(&node, anchor) {
+            return Ok(());
+        }
+        let from = self.list.detach(&node);
+        let to = match side {
+            Side::Before => (anchor.borrow().prev.clone(), Some(anchor.clone())),
+            Side::After => (Some(anchor.clone()), anchor.borrow().next.clone()),
+        };
+        self.list.reattach(&node, to.0.clone(), to.1.clone());
+        self.list.debug_validate();
+        self.record(Command::Move { node, from, to });
+        return Ok(());
+    }
+
+    pub fn replace(&mut self, node: &Rc<RefCell<Node<T>>>, data: T) -> T {
+        let old = self.list.replace(node, data);
+        self.record(Command::Replace { node: node.clone(), data: old.clone() });
+        return old;
+    }
+
+    // Reverts the latest edit. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(command) = self.undo.pop_back() else {
+            return false;
+        };
+        let command = self.apply(command, true);
+        self.redo.push(command);
+        return true;
+    }
+
+    // Re-applies the latest undone edit. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(command) = self.redo.pop() else {
+            return false;
+        };
+        let command = self.apply(command, false);
+        self.undo.push_back(command);
+        self.trim();
+        return true;
+    }
+
+    // Forgets every edit without changing the list.
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        return self.list.validate();
+    }
+
+    fn record_insert(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let (prev, next) = {
+            let borrow = node.borrow();
+            (borrow.prev.clone(), borrow.next.clone())
+        };
+        self.record(Command::Insert { node: node.clone(), prev, next });
+    }
+
+    fn record(&mut self, command: Command<T>) {
+        self.redo.clear();
+        self.undo.push_back(command);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.history_limit {
+            self.undo.pop_front();
+        }
+    }
+
+    // Undoes or redoes `command` and hands it back for the opposite stack.
+    fn apply(&mut self, command: Command<T>, undo: bool) -> Command<T> {
+        match &command {
+            Command::Insert { node, prev, next } | Command::Remove { node, prev, next } => {
+                let inserting = matches!(command, Command::Insert { .. });
+                if inserting == undo {
+                    self.list.detach(node);
+                } else {
+                    self.list.reattach(node, prev.clone(), next.clone());
+                }
+            }
+            Command::Move { node, from, to } => {
+                let (prev, next) = if undo { from.clone() } else { to.clone() };
+                self.list.detach(node);
+                self.list.reattach(node, prev, next);
+            }
+            Command::Replace { .. } => {}
+        }
+        let command = match command {
+            Command::Replace { node, data } => {
+                let data = self.list.replace(&node, data);
+                Command::Replace { node, data }
+            }
+            command => command,
+        };
+        self.list.debug_validate();
+        return command;
+    }
+}
+
+impl<T: Clone> Default for UndoableList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,429 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::enums::{Side, ValidationError};
+use crate::linked_list::DoublyLinkedList;
+use crate::node::Node;
+
+// Elements per chunk. Small payloads fill a few cache lines per chunk, and inserting or
+// removing inside a chunk shifts at most this many elements.
+pub const CHUNK_CAPACITY: usize = 64;
+
+// Two neighbouring chunks are merged once they fit in this many elements together. Staying
+// below the capacity leaves room, so a merge is not undone by the next insert.
+const MERGE_THRESHOLD: usize = CHUNK_CAPACITY * 3 / 4;
+
+// Source of `UnrolledList::id`, which tells handles from different lists apart.
+static NEXT_LIST_ID: AtomicUsize = AtomicUsize::new(0);
+
+type ChunkNode<T> = Rc<RefCell<Node<Chunk<T>>>>;
+
+// A run of consecutive elements, stored inline. `handles` holds the locators of the elements a
+// handle was taken for, which is usually none of them.
+struct Chunk<T> {
+    items: Vec<T>,
+    handles: Vec<Rc<Locator<T>>>,
+}
+
+impl<T> Chunk<T> {
+    fn new() -> Self {
+        return Chunk {
+            items: Vec::with_capacity(CHUNK_CAPACITY),
+            handles: Vec::new(),
+        };
+    }
+}
+
+// Where a handed-out element currently lives. Updated whenever the element moves, and pointed
+// at no chunk once it is removed. `list` is the id of the list that handed it out.
+struct Locator<T> {
+    list: usize,
+    chunk: RefCell<Weak<RefCell<Node<Chunk<T>>>>>,
+    offset: Cell<usize>,
+}
+
+// Refers to one element of an `UnrolledList` and stays valid while the element shifts within
+// its chunk or moves to another one. Removing the element invalidates it.
+pub struct UnrolledHandle<T>(Rc<Locator<T>>);
+
+impl<T> Clone for UnrolledHandle<T> {
+    fn clone(&self) -> Self {
+        UnrolledHandle(self.0.clone())
+    }
+}
+
+impl<T> UnrolledHandle<T> {
+    pub fn is_removed(&self) -> bool {
+        return self.0.chunk.borrow().strong_count() == 0;
+    }
+
+    pub fn ptr_eq(&self, other: &UnrolledHandle<T>) -> bool {
+        return Rc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+// A list that keeps up to `CHUNK_CAPACITY` elements in each node, so iterating chases one
+// pointer per chunk instead of one per element. The chunks are themselves linked through a
+// `DoublyLinkedList`.
+//
+// Positions are found by walking chunks, so positional operations cost O(n / CHUNK_CAPACITY)
+// plus a shift within one chunk. Handles are created on request by `handle_at`; elements that
+// never had one cost nothing extra.
+pub struct UnrolledList<T> {
+    chunks: DoublyLinkedList<Chunk<T>>,
+    len: usize,
+    id: usize,
+}
+
+impl<T> UnrolledList<T> {
+    pub fn new() -> Self {
+        return UnrolledList {
+            chunks: DoublyLinkedList::new(),
+            len: 0,
+            id: NEXT_LIST_ID.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        return self.chunks.len();
+    }
+
+    pub fn push_first(&mut self, data: T) {
+        self.insert_at(0, data);
+    }
+
+    pub fn push_back(&mut self, data: T) {
+        self.insert_at(self.len, data);
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        return self.remove_at(0);
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        return self.remove_at(self.len.checked_sub(1)?);
+    }
+
+    // Panics if `index > len`, like `Vec::insert`.
+    pub fn insert_at(&mut self, index: usize, data: T) {
+        assert!(index <= self.len, "insertion index {} is out of bounds for length {}", index, self.len);
+        let (mut node, mut offset) = if index == self.len {
+            let tail = match self.chunks.tail.clone() {
+                Some(tail) => tail,
+                None => self.chunks.push_back(Chunk::new()),
+            };
+            let end = tail.borrow().data().items.len();
+            (tail, end)
+        } else {
+            self.locate(index).expect("index is in bounds")
+        };
+
+        if offset == CHUNK_CAPACITY {
+            // Appending to a full chunk starts a new one, so lists built by appending keep
+            // their chunks full.
+            node = self.split(&node, CHUNK_CAPACITY);
+            offset = 0;
+        } else if node.borrow().data().items.len() == CHUNK_CAPACITY {
+            let half = CHUNK_CAPACITY / 2;
+            let upper = self.split(&node, half);
+            if offset > half {
+                node = upper;
+                offset -= half;
+            }
+        }
+
+        let mut borrow = node.borrow_mut();
+        borrow.data_mut().items.insert(offset, data);
+        for locator in &borrow.data().handles {
+            if locator.offset.get() >= offset {
+                locator.offset.set(locator.offset.get() + 1);
+            }
+        }
+        drop(borrow);
+        self.len += 1;
+        self.debug_validate();
+    }
+
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let (node, offset) = self.locate(index)?;
+        return Some(self.take(node, offset));
+    }
+
+    pub fn get_nth(&self, index: usize) -> Option<&T> {
+        let (node, offset) = self.locate(index)?;
+        return self.chunk_of(&node).items.get(offset);
+    }
+
+    pub fn get_nth_mut(&mut self, index: usize) -> Option<&mut T> {
+        let (node, offset) = self.locate(index)?;
+        return self.chunk_of_mut(&node).items.get_mut(offset);
+    }
+
+    // A handle to the element at `index`, reusing the one handed out before if there is one.
+    pub fn handle_at(&mut self, index: usize) -> Option<UnrolledHandle<T>> {
+        let (node, offset) = self.locate(index)?;
+        let mut borrow = node.borrow_mut();
+        if let Some(locator) = borrow.data_mut().handles.iter().find(|locator| locator.offset.get() == offset) {
+            return Some(UnrolledHandle(locator.clone()));
+        }
+        let locator = Rc::new(Locator {
+            list: self.id,
+            chunk: RefCell::new(Rc::downgrade(&node)),
+            offset: Cell::new(offset),
+        });
+        borrow.data_mut().handles.push(locator.clone());
+        return Some(UnrolledHandle(locator));
+    }
+
+    // `None` once the element is removed, or if `handle` comes from another list.
+    pub fn get(&self, handle: &UnrolledHandle<T>) -> Option<&T> {
+        let (node, offset) = self.find(handle)?;
+        return self.chunk_of(&node).items.get(offset);
+    }
+
+    pub fn get_mut(&mut self, handle: &UnrolledHandle<T>) -> Option<&mut T> {
+        let (node, offset) = self.find(handle)?;
+        return self.chunk_of_mut(&node).items.get_mut(offset);
+    }
+
+    pub fn index_of(&self, handle: &UnrolledHandle<T>) -> Option<usize> {
+        let (node, mut index) = self.find(handle)?;
+        let mut current = node.borrow().prev.clone();
+        while let Some(chunk) = current {
+            index += chunk.borrow().data().items.len();
+            current = chunk.borrow().prev.clone();
+        }
+        return Some(index);
+    }
+
+    pub fn remove(&mut self, handle: UnrolledHandle<T>) -> Option<T> {
+        let (node, offset) = self.find(&handle)?;
+        return Some(self.take(node, offset));
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        return Iter {
+            list: self,
+            next: self.chunks.head.clone(),
+            items: [].iter(),
+        };
+    }
+
+    // Removed elements' handles are invalidated as their chunks are dropped.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    // Checks the chunk list, that no chunk is empty or over capacity, that the chunk lengths
+    // add up, and that every handle points back at its chunk and element.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.chunks.validate()?;
+        let mut counted = 0;
+        let mut index = 0;
+        let mut current = self.chunks.head.clone();
+        while let Some(node) = current {
+            let borrow = node.borrow();
+            let chunk = borrow.data();
+            let len = chunk.items.len();
+            let mut offsets: Vec<usize> = chunk.handles.iter().map(|locator| locator.offset.get()).collect();
+            offsets.sort_unstable();
+            offsets.dedup();
+            let handles_ok = offsets.len() == chunk.handles.len()
+                && offsets.last().is_none_or(|&last| last < len)
+                && chunk.handles.iter().all(|locator| {
+                    locator.chunk.borrow().upgrade().is_some_and(|owner| Rc::ptr_eq(&owner, &node))
+                });
+            if len == 0 || len > CHUNK_CAPACITY || !handles_ok {
+                return Err(ValidationError::BadChunk { index });
+            }
+            counted += len;
+            index += 1;
+            current = borrow.next.clone();
+        }
+        if counted != self.len {
+            return Err(ValidationError::CountMismatch { counted, len: self.len });
+        }
+        return Ok(());
+    }
+
+    // The chunk holding the element at `index` and the element's offset in it, walking chunks
+    // from whichever end is closer.
+    fn locate(&self, index: usize) -> Option<(ChunkNode<T>, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        if index < self.len / 2 {
+            let mut offset = index;
+            let mut current = self.chunks.head.clone();
+            while let Some(node) = current {
+                let len = node.borrow().data().items.len();
+                if offset < len {
+                    return Some((node, offset));
+                }
+                offset -= len;
+                current = node.borrow().next.clone();
+            }
+        } else {
+            let mut remaining = self.len - index;
+            let mut current = self.chunks.tail.clone();
+            while let Some(node) = current {
+                let len = node.borrow().data().items.len();
+                if remaining <= len {
+                    return Some((node, len - remaining));
+                }
+                remaining -= len;
+                current = node.borrow().prev.clone();
+            }
+        }
+        return None;
+    }
+
+    // Handles from another list are refused: `self` does not borrow that list, which could drop
+    // the chunk while a reference from `chunk_of` is still alive.
+    fn find(&self, handle: &UnrolledHandle<T>) -> Option<(ChunkNode<T>, usize)> {
+        if handle.0.list != self.id {
+            return None;
+        }
+        let node = handle.0.chunk.borrow().upgrade()?;
+        return Some((node, handle.0.offset.get()));
+    }
+
+    // Removes the element at `offset` of `node`, then drops the chunk if it ran empty or merges
+    // it with a neighbour if both are sparse.
+    fn take(&mut self, node: ChunkNode<T>, offset: usize) -> T {
+        let mut borrow = node.borrow_mut();
+        let data = borrow.data_mut().items.remove(offset);
+        borrow.data_mut().handles.retain(|locator| {
+            if locator.offset.get() == offset {
+                *locator.chunk.borrow_mut() = Weak::new();
+                return false;
+            }
+            if locator.offset.get() > offset {
+                locator.offset.set(locator.offset.get() - 1);
+            }
+            return true;
+        });
+        let len = borrow.data_mut().items.len();
+        drop(borrow);
+        self.len -= 1;
+
+        if len == 0 {
+            self.chunks.remove(node);
+        } else {
+            let next = node.borrow().next.clone();
+            let prev = node.borrow().prev.clone();
+            let fits = |other: &ChunkNode<T>| len + other.borrow().data().items.len() <= MERGE_THRESHOLD;
+            if let Some(next) = next.filter(fits) {
+                drop(prev);
+                self.merge(&node, next);
+            } else if let Some(prev) = prev.filter(fits) {
+                self.merge(&prev, node);
+            }
+        }
+        self.debug_validate();
+        return data;
+    }
+
+    // Moves the elements from `at` on into a new chunk after `node` and returns it.
+    fn split(&mut self, node: &ChunkNode<T>, at: usize) -> ChunkNode<T> {
+        let mut upper = Chunk::new();
+        let mut borrow = node.borrow_mut();
+        upper.items.extend(borrow.data_mut().items.drain(at..));
+        let (moved, kept) = borrow.data_mut().handles.drain(..).partition(|locator| locator.offset.get() >= at);
+        borrow.data_mut().handles = kept;
+        upper.handles = moved;
+        drop(borrow);
+
+        let upper_node = self.chunks.insert(Side::After, upper, node.clone());
+        for locator in &upper_node.borrow().data().handles {
+            locator.offset.set(locator.offset.get() - at);
+            *locator.chunk.borrow_mut() = Rc::downgrade(&upper_node);
+        }
+        return upper_node;
+    }
+
+    // Appends the elements of `next` to `node` and drops `next`, which must follow `node`.
+    fn merge(&mut self, node: &ChunkNode<T>, next: ChunkNode<T>) {
+        let mut borrow = node.borrow_mut();
+        let mut next_borrow = next.borrow_mut();
+        let shift = borrow.data_mut().items.len();
+        borrow.data_mut().items.append(&mut next_borrow.data_mut().items);
+        for locator in next_borrow.data_mut().handles.drain(..) {
+            locator.offset.set(locator.offset.get() + shift);
+            *locator.chunk.borrow_mut() = Rc::downgrade(node);
+            borrow.data_mut().handles.push(locator);
+        }
+        drop(next_borrow);
+        drop(borrow);
+        self.chunks.remove(next);
+    }
+
+    // Reads a chunk of this list for as long as `self` is borrowed, without a `Ref`, so `get`,
+    // `get_nth` and `iter` can hand out plain references.
+    //
+    // SAFETY: nothing can borrow the chunk mutably or drop it while the reference is alive.
+    // - Chunk nodes never leave the list. `chunks` is private, and handles only hold a `Weak`
+    //   in a private field, which `find` upgrades for the length of one call.
+    // - Every `borrow_mut` of a chunk and every unlink happens in a `&mut self` method, which
+    //   cannot run while `self` is borrowed. The `&self` methods only call `borrow`, which may
+    //   share the chunk with the reference.
+    // - `find` refuses handles of other lists, so another list's `&mut self` method cannot be
+    //   aimed at this list's chunks.
+    // - The list holds a strong reference to every linked chunk until a `&mut self` method
+    //   unlinks it.
+    fn chunk_of(&self, node: &ChunkNode<T>) -> &Chunk<T> {
+        return unsafe { (*node.as_ptr()).data() };
+    }
+
+    // SAFETY: as for `chunk_of`. `&mut self` also rules out any other reference into the chunk,
+    // from `chunk_of` or from a `borrow`.
+    fn chunk_of_mut(&mut self, node: &ChunkNode<T>) -> &mut Chunk<T> {
+        return unsafe { (*node.as_ptr()).data_mut() };
+    }
+
+    #[cfg(any(debug_assertions, feature = "validate"))]
+    fn debug_validate(&self) {
+        if let Err(error) = self.validate() {
+            panic!("list is corrupted: {}", error);
+        }
+    }
+
+    #[cfg(not(any(debug_assertions, feature = "validate")))]
+    fn debug_validate(&self) {}
+}
+
+impl<T> Default for UnrolledList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a, T> {
+    list: &'a UnrolledList<T>,
+    next: Option<ChunkNode<T>>,
+    items: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(item);
+            }
+            let node = self.next.take()?;
+            self.items = self.list.chunk_of(&node).items.iter();
+            self.next = node.borrow().next.clone();
+        }
+    }
+}